@@ -18,11 +18,11 @@ variable "PUSH_BY_DIGEST" { default = false }
 variable "CACHE_WRITE"    { default = false }
 
 group "release" {
-  targets = ["connect", "op", "bws", "infisical", "bao", "aio", "plugin"]
+  targets = ["connect", "op", "bws", "infisical", "bao", "aws", "gcp", "azure", "sops", "http", "keepass", "k8s", "bw", "doppler", "aio", "plugin"]
 }
 
 group "all" {
-  targets = ["connect", "op", "bws", "infisical", "bao", "aio", "debug", "plugin"]
+  targets = ["connect", "op", "bws", "infisical", "bao", "aws", "gcp", "azure", "sops", "http", "keepass", "k8s", "bw", "doppler", "aio", "debug", "plugin"]
 }
 
 group "plugin-build" {
@@ -98,7 +98,7 @@ target "op" {
   inherits = ["_common"]
   target = "op"
   args = {
-    FEATURES = "op,exec"
+    FEATURES = "op,fallback-cache,exec"
     DEFAULT_PROVIDER = "op"
   }
   cache-to   = cache_to_for("op")
@@ -111,7 +111,7 @@ target "connect" {
   inherits = ["_common"]
   target = "base"
   args = {
-    FEATURES = "connect,exec"
+    FEATURES = "connect,fallback-cache,exec"
     DEFAULT_PROVIDER = "op-connect"
   }
   cache-to   = cache_to_for("connect")
@@ -124,7 +124,7 @@ target "bws" {
   inherits = ["_common"]
   target = "base"
   args = {
-    FEATURES = "bws,exec"
+    FEATURES = "bws,fallback-cache,exec"
     DEFAULT_PROVIDER = "bws"
   }
   cache-to   = cache_to_for("bws")
@@ -138,7 +138,7 @@ target "infisical" {
   inherits = ["_common"]
   target = "base"
   args = {
-    FEATURES = "infisical,exec"
+    FEATURES = "infisical,fallback-cache,exec"
     DEFAULT_PROVIDER = "infisical"
   }
   cache-to   = cache_to_for("infisical")
@@ -151,7 +151,7 @@ target "bao" {
   inherits = ["_common"]
   target = "base"
   args = {
-    FEATURES = "bao,exec"
+    FEATURES = "bao,fallback-cache,exec"
     DEFAULT_PROVIDER = "bao"
   }
   cache-to   = cache_to_for("bao")
@@ -160,11 +160,128 @@ target "bao" {
   labels = { "org.opencontainers.image.version" = VERSION }
 }
 
+target "aws" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "aws,fallback-cache,exec"
+    DEFAULT_PROVIDER = "aws"
+  }
+  cache-to   = cache_to_for("aws")
+  cache-from = cache_from_for("aws")
+  tags = tags_for("aws")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "gcp" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "gcp,fallback-cache,exec"
+    DEFAULT_PROVIDER = "gcp"
+  }
+  cache-to   = cache_to_for("gcp")
+  cache-from = cache_from_for("gcp")
+  tags = tags_for("gcp")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "azure" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "azure,fallback-cache,exec"
+    DEFAULT_PROVIDER = "azure"
+  }
+  cache-to   = cache_to_for("azure")
+  cache-from = cache_from_for("azure")
+  tags = tags_for("azure")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "sops" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "sops,fallback-cache,exec"
+    DEFAULT_PROVIDER = "sops"
+  }
+  cache-to   = cache_to_for("sops")
+  cache-from = cache_from_for("sops")
+  tags = tags_for("sops")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "http" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "http,fallback-cache,exec"
+    DEFAULT_PROVIDER = "http"
+  }
+  cache-to   = cache_to_for("http")
+  cache-from = cache_from_for("http")
+  tags = tags_for("http")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "keepass" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "keepass,fallback-cache,exec"
+    DEFAULT_PROVIDER = "keepass"
+  }
+  cache-to   = cache_to_for("keepass")
+  cache-from = cache_from_for("keepass")
+  tags = tags_for("keepass")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "k8s" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "k8s,fallback-cache,exec"
+    DEFAULT_PROVIDER = "k8s"
+  }
+  cache-to   = cache_to_for("k8s")
+  cache-from = cache_from_for("k8s")
+  tags = tags_for("k8s")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "bw" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "bw,fallback-cache,exec"
+    DEFAULT_PROVIDER = "bw"
+  }
+  cache-to   = cache_to_for("bw")
+  cache-from = cache_from_for("bw")
+  tags = tags_for("bw")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
+target "doppler" {
+  inherits = ["_common"]
+  target = "base"
+  args = {
+    FEATURES = "doppler,fallback-cache,exec"
+    DEFAULT_PROVIDER = "doppler"
+  }
+  cache-to   = cache_to_for("doppler")
+  cache-from = cache_from_for("doppler")
+  tags = tags_for("doppler")
+  labels = { "org.opencontainers.image.version" = VERSION }
+}
+
 target "aio" {
   inherits = ["_common"]
   target = "aio"
   args = {
-    FEATURES = "op,connect,bws,infisical,bao,exec"
+    FEATURES = "op,connect,bws,infisical,bao,aws,gcp,azure,sops,exec-provider,http,keepass,k8s,bw,doppler,fallback-cache,exec"
   }
   cache-to   = cache_to_for("aio")
   cache-from = cache_from_for("aio")
@@ -176,7 +293,7 @@ target "plugin" {
   inherits = ["_common"]
   target = "plugin"
   args = {
-    FEATURES = "op,connect,bws,infisical,bao,volume"
+    FEATURES = "op,connect,bws,infisical,bao,aws,gcp,azure,sops,exec-provider,http,keepass,k8s,bw,doppler,fallback-cache,volume"
   }
   cache-to   = cache_to_for("plugin")
   cache-from = cache_from_for("plugin")
@@ -188,7 +305,7 @@ target "debug" {
   inherits = ["_common"]
   target = "debug"
   args = {
-    FEATURES = "op,connect,bws,infisical,bao,exec"
+    FEATURES = "op,connect,bws,infisical,bao,aws,gcp,azure,sops,exec-provider,http,keepass,k8s,bw,doppler,fallback-cache,exec"
   }
   cache-to   = cache_to_for("debug")
   cache-from = cache_from_for("debug")
@@ -2,7 +2,7 @@ use crate::cmd::config::compose::UpArgs;
 use crate::compose::ComposeMsg;
 use crate::env::EnvManager;
 use crate::logging::{LogFormat, Logger};
-use crate::provider::Provider;
+use crate::provider::ProviderSet;
 
 use secrecy::ExposeSecret;
 use tracing::debug;
@@ -10,7 +10,7 @@ use tracing::debug;
 pub async fn up(project: String, args: UpArgs) -> Result<(), crate::error::LocketError> {
     Logger::new(LogFormat::Compose, args.log_level).init()?;
     debug!("Starting project {} with: {:#?}", project, args);
-    let provider = Provider::try_from(args.provider)?.build().await?;
+    let provider = ProviderSet::try_from(args.provider)?.build().await?;
 
     let mut secrets = Vec::with_capacity(args.env_file.len() + args.env.len());
 
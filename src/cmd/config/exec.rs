@@ -1,6 +1,6 @@
 use crate::logging::{Logger, LoggerArgs};
 use crate::process::{ProcessTimeout, ShellCommand};
-use crate::provider::{Provider, ProviderArgs};
+use crate::provider::{ProviderArgs, ProviderSet};
 use crate::secrets::{Secret, SecretManagerArgs, SecretManagerConfig};
 use crate::watch::DebounceDuration;
 use clap::Args;
@@ -15,7 +15,7 @@ pub struct ExecConfig {
     pub env_files: Vec<Secret>,
     pub env_overrides: Vec<Secret>,
     pub manager: SecretManagerConfig,
-    pub provider: Provider,
+    pub provider: ProviderSet,
     pub timeout: ProcessTimeout,
     pub debounce: DebounceDuration,
     pub logger: Logger,
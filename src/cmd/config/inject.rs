@@ -1,6 +1,6 @@
 use crate::health::StatusFile;
 use crate::logging::{Logger, LoggerArgs};
-use crate::provider::{Provider, ProviderArgs};
+use crate::provider::{ProviderArgs, ProviderSet};
 use crate::secrets::{SecretManagerArgs, SecretManagerConfig};
 use crate::watch::DebounceDuration;
 use clap::{Args, ValueEnum};
@@ -33,7 +33,7 @@ pub struct InjectConfig {
     pub mode: InjectMode,
     pub status_file: Option<StatusFile>,
     pub manager: SecretManagerConfig,
-    pub provider: Provider,
+    pub provider: ProviderSet,
     pub debounce: DebounceDuration,
     pub logger: Logger,
 }
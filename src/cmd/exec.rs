@@ -32,7 +32,7 @@ pub async fn exec(config: ExecConfig) -> Result<(), LocketError> {
     let command = config.cmd;
     let mut process = ProcessManager::new(env_manager, command, interactive, config.timeout);
 
-    let files = SecretFileManager::new(config.manager, provider)?;
+    let files = SecretFileManager::new(config.manager, provider.clone())?;
 
     // Initial Start
     info!("resolving environment and starting process...");
@@ -48,11 +48,23 @@ pub async fn exec(config: ExecConfig) -> Result<(), LocketError> {
         // so we can clean up properly.
         handler = watcher.run().await?;
         handler.cleanup().await;
+        provider.shutdown().await;
         info!("watch loop terminated gracefully");
         Ok(())
     } else {
-        let result = handler.wait().await;
+        let result = loop {
+            tokio::select! {
+                res = handler.wait() => break res,
+                _ = handler.expired() => {
+                    info!("secrets expired, refreshing");
+                    if let Err(e) = handler.refresh().await {
+                        break Err(e);
+                    }
+                }
+            }
+        };
         handler.cleanup().await;
+        provider.shutdown().await;
         result.map_err(LocketError::from)
     }
 }
@@ -106,6 +118,16 @@ impl EventHandler for ExecOrchestrator {
         self.process.wait()
     }
 
+    fn expired(&self) -> BoxFuture<'static, ()> {
+        // Files and environment share the same provider.
+        self.files.expired()
+    }
+
+    async fn refresh(&mut self) -> Result<(), HandlerError> {
+        self.files.refresh().await?;
+        self.process.refresh().await
+    }
+
     async fn cleanup(&mut self) {
         self.process.cleanup().await;
     }
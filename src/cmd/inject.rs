@@ -1,6 +1,6 @@
 use crate::{
     cmd::config::inject::{InjectConfig, InjectMode},
-    events,
+    events::{self, EventHandler},
     secrets::SecretFileManager,
     watch::FsWatcher,
 };
@@ -23,7 +23,7 @@ pub async fn inject(config: InjectConfig) -> Result<(), crate::error::LocketErro
 
     let provider = config.provider.build().await?;
 
-    let manager = SecretFileManager::new(config.manager, provider)?;
+    let mut manager = SecretFileManager::new(config.manager, provider.clone())?;
 
     manager.inject_all().await?;
 
@@ -36,7 +36,18 @@ pub async fn inject(config: InjectConfig) -> Result<(), crate::error::LocketErro
         InjectMode::OneShot => Ok(()),
         InjectMode::Park => {
             tracing::info!("parking... (ctrl-c to exit)");
-            events::wait_for_signal(false).await;
+            let signal = events::wait_for_signal(false);
+            tokio::pin!(signal);
+            loop {
+                tokio::select! {
+                    _ = &mut signal => break,
+                    _ = manager.expired() => {
+                        info!("secrets expired, refreshing");
+                        manager.refresh().await?;
+                    }
+                }
+            }
+            provider.shutdown().await;
 
             info!("shutdown complete");
             Ok(())
@@ -44,6 +55,7 @@ pub async fn inject(config: InjectConfig) -> Result<(), crate::error::LocketErro
         InjectMode::Watch => {
             let watcher = FsWatcher::new(config.debounce, manager);
             watcher.run().await?;
+            provider.shutdown().await;
             Ok(())
         }
     }
@@ -1,5 +1,6 @@
 //! Custom parsers for configurations.
 
+use serde::de::{DeserializeOwned, IntoDeserializer};
 use serde::{Deserialize, Deserializer};
 use std::collections::HashMap;
 use std::fmt::Display;
@@ -62,6 +63,34 @@ where
     Ok(C::from(vec))
 }
 
+/// deserializes a list, a single value, or a comma separated string into Vec<T>.
+///
+/// The delimited string form mirrors clap's `value_delimiter = ','`, so the same
+/// value is accepted from the CLI, environment, TOML, and Docker driver options.
+pub fn delimited_vec<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Container<T> {
+        Str(String),
+        List(Vec<T>),
+    }
+
+    match Container::<T>::deserialize(deserializer)? {
+        Container::Str(s) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| T::deserialize(s.into_deserializer()))
+            .collect::<Result<Vec<T>, serde::de::value::Error>>()
+            .map_err(serde::de::Error::custom),
+        Container::List(items) => Ok(items),
+    }
+}
+
 /// Overwrites the base vector with the top vector if the top vector is not empty.
 pub fn vec_replace<T>(base: Vec<T>, top: Vec<T>) -> Vec<T> {
     if top.is_empty() { base } else { top }
@@ -125,6 +154,24 @@ mod tests {
         assert_eq!(merged, vec![1, 1, 2, 3]);
     }
 
+    #[test]
+    fn test_delimited_vec_forms() {
+        #[derive(Deserialize)]
+        struct Delimited {
+            #[serde(deserialize_with = "delimited_vec")]
+            items: Vec<String>,
+        }
+
+        let list: Delimited = toml::from_str(r#"items = ["a", "b"]"#).unwrap();
+        assert_eq!(list.items, vec!["a", "b"]);
+
+        let joined: Delimited = toml::from_str(r#"items = "a, b,""#).unwrap();
+        assert_eq!(joined.items, vec!["a", "b"]);
+
+        let single: Delimited = toml::from_str(r#"items = "a""#).unwrap();
+        assert_eq!(single.items, vec!["a"]);
+    }
+
     #[test]
     fn test_path_mapping_polymorphism() {
         let source_file = tempfile::NamedTempFile::new().unwrap();
@@ -104,6 +104,22 @@ pub trait EventHandler: Send + Sync {
         })
     }
 
+    /// A future that resolves when secrets resolved by the reactor have expired,
+    /// such as dynamic credentials whose lease could not be renewed.
+    ///
+    /// The event loop should then call [`EventHandler::refresh`].
+    /// The default implementation never resolves.
+    fn expired(&self) -> BoxFuture<'static, ()> {
+        Box::pin(std::future::pending())
+    }
+
+    /// Resolves and applies secrets again after they expired.
+    ///
+    /// Follows the same error contract as [`EventHandler::handle`].
+    async fn refresh(&mut self) -> Result<(), HandlerError> {
+        Ok(())
+    }
+
     /// Performs teardown and resource release.
     ///
     /// This hook allows the reactor to perform graceful shutdown operations (e.g.,
@@ -148,6 +164,14 @@ impl<H: EventHandler> EventHandler for StoppableHandler<H> {
         })
     }
 
+    fn expired(&self) -> BoxFuture<'static, ()> {
+        self.inner.expired()
+    }
+
+    async fn refresh(&mut self) -> Result<(), HandlerError> {
+        self.inner.refresh().await
+    }
+
     async fn cleanup(&mut self) {
         self.inner.cleanup().await
     }
@@ -38,7 +38,8 @@ impl LocketExitCode {
                 ProviderError::Network(_) => sysexits::ExitCode::Unavailable.into(),
                 ProviderError::NotFound(_) => sysexits::ExitCode::NoInput.into(),
                 ProviderError::Unauthorized(_) => sysexits::ExitCode::NoPerm.into(),
-                ProviderError::RateLimit => sysexits::ExitCode::TempFail.into(),
+                ProviderError::RateLimit { .. } => sysexits::ExitCode::TempFail.into(),
+                ProviderError::Unavailable(_) => sysexits::ExitCode::Unavailable.into(),
                 ProviderError::Other(_) => sysexits::ExitCode::Software.into(),
                 ProviderError::InvalidConfig(_) => sysexits::ExitCode::Config.into(),
                 ProviderError::Io(_) => sysexits::ExitCode::IoErr.into(),
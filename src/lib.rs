@@ -9,6 +9,17 @@
 //! * `op`: Enables the 1Password Service Account provider.
 //! * `connect`: Enables the 1Password Connect provider.
 //! * `bws`: Enables the Bitwarden Secrets Manager provider.
+//! * `aws`: Enables the AWS Secrets Manager and SSM Parameter Store provider.
+//! * `gcp`: Enables the Google Cloud Secret Manager provider.
+//! * `azure`: Enables the Azure Key Vault provider.
+//! * `sops`: Enables the SOPS encrypted file provider.
+//! * `exec-provider`: Enables the provider for external plugin executables.
+//! * `http`: Enables the generic HTTP webhook provider.
+//! * `keepass`: Enables the KeePass (.kdbx) database provider.
+//! * `k8s`: Enables the Kubernetes Secrets provider.
+//! * `bw`: Enables the Bitwarden / Vaultwarden vault provider, through `bw serve`.
+//! * `doppler`: Enables the Doppler provider.
+//! * `fallback-cache`: Enables the encrypted last-known-good cache of provider secrets.
 //! * `compose`: Enables Docker CLI Plugin for use as a Docker Compose Provider service
 //! * `exec`: Enables the `exec` command for process environment injection into a child process
 //! * `volume`: Enables the `volume` command for use as a Docker volume driver.
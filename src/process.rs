@@ -427,6 +427,31 @@ impl ProcessManager {
     }
 }
 
+impl ProcessManager {
+    /// Resolves the environment again, and restarts the process if it changed.
+    async fn reload(&mut self) {
+        match self.env.resolve().await {
+            Ok(resolved) => {
+                let new_hash = Self::hash_env(&resolved);
+                if new_hash != self.env_hash {
+                    self.env_hash = new_hash;
+                    tracing::info!("Environment changed, restarting process...");
+
+                    if let Err(e) = self.restart(&resolved).await {
+                        error!("Failed to restart process: {}", e);
+                    }
+                } else {
+                    debug!("Resolved environment is identical; skipping restart");
+                }
+            }
+            Err(e) => {
+                // Log but don't crash the watcher loop
+                error!("Failed to reload environment: {}", e);
+            }
+        }
+    }
+}
+
 impl Drop for ProcessManager {
     fn drop(&mut self) {
         if let ProcessState::Running {
@@ -462,28 +487,13 @@ impl EventHandler for ProcessManager {
         if events.is_empty() {
             return Ok(());
         }
-        match self.env.resolve().await {
-            Ok(resolved) => {
-                let new_hash = Self::hash_env(&resolved);
-                if new_hash != self.env_hash {
-                    self.env_hash = new_hash;
-                    tracing::info!(
-                        "Environment changed ({} events), restarting process...",
-                        events.len()
-                    );
+        debug!("{} env file events, reloading environment", events.len());
+        self.reload().await;
+        Ok(())
+    }
 
-                    if let Err(e) = self.restart(&resolved).await {
-                        error!("Failed to restart process: {}", e);
-                    }
-                } else {
-                    debug!("Files changed but resolved environment is identical; skipping restart");
-                }
-            }
-            Err(e) => {
-                // Log but don't crash the watcher loop
-                error!("Failed to reload environment: {}", e);
-            }
-        }
+    async fn refresh(&mut self) -> Result<(), HandlerError> {
+        self.reload().await;
         Ok(())
     }
 
@@ -7,6 +7,7 @@
 //! It also provides implementations for specific providers
 //! and a selection mechanism to choose the provider at runtime
 
+use crate::path::AbsolutePath;
 use async_trait::async_trait;
 use clap::{Args, ValueEnum};
 use locket_derive::LayeredConfig;
@@ -14,38 +15,105 @@ use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(not(any(
     feature = "op",
     feature = "connect",
     feature = "bws",
     feature = "infisical",
-    feature = "bao"
+    feature = "bao",
+    feature = "aws",
+    feature = "gcp",
+    feature = "azure",
+    feature = "sops",
+    feature = "exec-provider",
+    feature = "http",
+    feature = "keepass",
+    feature = "k8s",
+    feature = "bw",
+    feature = "doppler"
 )))]
 compile_error!(
-    "At least one provider feature must be enabled (e.g. --features op,connect,bws,infisical,bao)"
+    "At least one provider feature must be enabled (e.g. --features op,connect,bws,infisical,bao,aws,gcp,azure,sops,exec-provider,http,keepass,k8s,bw,doppler)"
 );
 
-#[cfg(any(feature = "bao", feature = "infisical"))]
+#[cfg(any(
+    feature = "bao",
+    feature = "infisical",
+    feature = "gcp",
+    feature = "azure"
+))]
 mod auth;
+#[cfg(feature = "aws")]
+mod aws;
+#[cfg(feature = "azure")]
+mod azure;
 #[cfg(feature = "bao")]
 mod bao;
+#[cfg(feature = "bw")]
+mod bw;
 #[cfg(feature = "bws")]
 mod bws;
+pub mod cache;
+mod composite;
 pub mod config;
 #[cfg(feature = "connect")]
 mod connect;
+#[cfg(feature = "doppler")]
+mod doppler;
+#[cfg(feature = "exec-provider")]
+mod exec;
+mod failover;
+#[cfg(feature = "fallback-cache")]
+pub mod fallback;
+#[cfg(feature = "gcp")]
+mod gcp;
+#[cfg(feature = "http")]
+mod http;
 #[cfg(feature = "infisical")]
 mod infisical;
+#[cfg(feature = "k8s")]
+mod k8s;
+#[cfg(feature = "keepass")]
+mod keepass;
+pub mod local;
 pub mod managed;
 #[cfg(feature = "op")]
 mod op;
+#[cfg(any(feature = "op", feature = "exec-provider"))]
+mod plugin;
 mod references;
+pub mod retry;
+mod singleflight;
+#[cfg(feature = "sops")]
+mod sops;
+#[cfg(all(
+    test,
+    any(
+        feature = "http",
+        feature = "k8s",
+        feature = "bw",
+        feature = "doppler",
+        feature = "bao"
+    )
+))]
+mod test_server;
 mod types;
 
+use cache::CachingProvider;
+use composite::CompositeProvider;
+use failover::FailoverProvider;
+#[cfg(feature = "fallback-cache")]
+use fallback::{FallbackArgs, FallbackConfig, FallbackProvider, FallbackStore};
+use local::{LocalArgs, LocalConfig, LocalProvider};
 use managed::{ManagedProvider, ProviderFactory};
 pub use references::{ReferenceParseError, ReferenceParser, SecretReference};
-pub use types::{AuthToken, ConcurrencyLimit, ServerUrl, ServerUrlError, TokenSource};
+use retry::{RetryArgs, RetryPolicy};
+use singleflight::SingleFlightProvider;
+pub use types::{
+    AuthToken, ConcurrencyLimit, ProviderDuration, ServerUrl, ServerUrlError, TokenSource,
+};
 
 /// Trait for configuration structs that can produce a "signature" representing their content's freshness.
 #[async_trait]
@@ -71,9 +139,14 @@ pub enum ProviderError {
     #[error("access denied: {0}")]
     Unauthorized(String),
 
-    /// Rate limiting
+    /// Rate limiting, with the delay requested by the backend if any
     #[error("rate limited")]
-    RateLimit,
+    RateLimit { retry_after: Option<Duration> },
+
+    /// The backend is temporarily unable to serve requests, e.g. a 503
+    /// from a sealed vault or an overloaded proxy
+    #[error("service unavailable: {0}")]
+    Unavailable(String),
 
     /// Generic error
     #[error("{0}")]
@@ -100,6 +173,72 @@ pub enum ProviderError {
     },
 }
 
+impl ProviderError {
+    /// Whether the error is likely caused by the backend being temporarily
+    /// unreachable, rather than by the request or the configuration.
+    ///
+    /// An equivalent backend may succeed where this one failed.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::Network(_) | Self::RateLimit { .. } | Self::Unavailable(_)
+        )
+    }
+
+    /// Copies the error, e.g. for every waiter of a shared fetch.
+    ///
+    /// Sources which can't be cloned are reduced to their message, keeping
+    /// the variant so that transient errors stay transient.
+    pub(crate) fn duplicate(&self) -> Self {
+        match self {
+            Self::Network(e) => Self::Network(e.to_string().into()),
+            Self::NotFound(s) => Self::NotFound(s.clone()),
+            Self::Unauthorized(s) => Self::Unauthorized(s.clone()),
+            Self::RateLimit { retry_after } => Self::RateLimit {
+                retry_after: *retry_after,
+            },
+            Self::Unavailable(s) => Self::Unavailable(s.clone()),
+            Self::Other(s) => Self::Other(s.clone()),
+            Self::InvalidConfig(s) => Self::InvalidConfig(s.clone()),
+            Self::InvalidId(s) => Self::InvalidId(s.clone()),
+            Self::Io(e) => Self::Io(std::io::Error::new(e.kind(), e.to_string())),
+            Self::Exec {
+                program,
+                status,
+                stderr,
+            } => Self::Exec {
+                program,
+                status: *status,
+                stderr: stderr.clone(),
+            },
+        }
+    }
+
+    /// Builds a rate limit error from a `429 Too Many Requests` response,
+    /// keeping the delay from its `Retry-After` header.
+    ///
+    /// Only the delay-seconds form of the header is understood.
+    #[cfg(any(
+        feature = "azure",
+        feature = "bao",
+        feature = "bw",
+        feature = "connect",
+        feature = "doppler",
+        feature = "gcp",
+        feature = "http",
+        feature = "infisical",
+        feature = "k8s"
+    ))]
+    pub(crate) fn rate_limited(headers: &reqwest::header::HeaderMap) -> Self {
+        let retry_after = headers
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        Self::RateLimit { retry_after }
+    }
+}
+
 /// Abstraction for a backend service that resolves secret references.
 #[async_trait]
 pub trait SecretsProvider: ReferenceParser + Send + Sync {
@@ -114,6 +253,25 @@ pub trait SecretsProvider: ReferenceParser + Send + Sync {
         &self,
         references: &[SecretReference],
     ) -> Result<HashMap<SecretReference, SecretString>, ProviderError>;
+
+    /// Resolves when secrets fetched earlier are no longer valid, e.g. because
+    /// their lease could not be renewed, and should be fetched again.
+    ///
+    /// Providers whose secrets don't expire never resolve.
+    async fn expired(&self) {
+        std::future::pending().await
+    }
+
+    /// Whether secrets fetched earlier are held under leases, which are no
+    /// longer renewed, and possibly revoked, once the provider is shut down.
+    async fn holds_leases(&self) -> bool {
+        false
+    }
+
+    /// Releases resources held for fetched secrets, e.g. revokes their leases.
+    ///
+    /// Called when the secrets are no longer in use, before exiting.
+    async fn shutdown(&self) {}
 }
 
 /// Provider backend configuration
@@ -130,11 +288,47 @@ pub enum Provider {
 
     #[cfg(feature = "bao")]
     Bao(config::bao::BaoConfig),
+
+    #[cfg(feature = "aws")]
+    Aws(config::aws::AwsConfig),
+
+    #[cfg(feature = "gcp")]
+    Gcp(config::gcp::GcpConfig),
+
+    #[cfg(feature = "azure")]
+    Azure(config::azure::AzureConfig),
+    #[cfg(feature = "sops")]
+    Sops(config::sops::SopsConfig),
+
+    #[cfg(feature = "exec-provider")]
+    Exec(config::exec::ExecProviderConfig),
+
+    #[cfg(feature = "http")]
+    Http(config::http::HttpConfig),
+
+    #[cfg(feature = "keepass")]
+    KeePass(config::keepass::KeePassConfig),
+
+    #[cfg(feature = "k8s")]
+    K8s(config::k8s::K8sConfig),
+
+    #[cfg(feature = "bw")]
+    Bw(config::bw::BwConfig),
+
+    #[cfg(feature = "doppler")]
+    Doppler(config::doppler::DopplerConfig),
 }
 
 impl Provider {
     pub async fn build(self) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
-        let managed = ManagedProvider::new(self).await?;
+        self.build_with_retry(RetryPolicy::default()).await
+    }
+
+    pub async fn build_with_retry(
+        self,
+        retry: RetryPolicy,
+    ) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+        let managed = ManagedProvider::new(self).await?.with_retry(retry);
         Ok(Arc::new(managed))
     }
 }
@@ -153,6 +347,26 @@ impl Signature for Provider {
             Self::Infisical(c) => c.signature().await,
             #[cfg(feature = "bao")]
             Self::Bao(c) => c.signature().await,
+            #[cfg(feature = "aws")]
+            Self::Aws(c) => c.signature().await,
+            #[cfg(feature = "gcp")]
+            Self::Gcp(c) => c.signature().await,
+            #[cfg(feature = "azure")]
+            Self::Azure(c) => c.signature().await,
+            #[cfg(feature = "sops")]
+            Self::Sops(c) => c.signature().await,
+            #[cfg(feature = "exec-provider")]
+            Self::Exec(c) => c.signature().await,
+            #[cfg(feature = "http")]
+            Self::Http(c) => c.signature().await,
+            #[cfg(feature = "keepass")]
+            Self::KeePass(c) => c.signature().await,
+            #[cfg(feature = "k8s")]
+            Self::K8s(c) => c.signature().await,
+            #[cfg(feature = "bw")]
+            Self::Bw(c) => c.signature().await,
+            #[cfg(feature = "doppler")]
+            Self::Doppler(c) => c.signature().await,
         }
     }
 }
@@ -170,6 +384,26 @@ impl ReferenceParser for Provider {
             Self::Infisical(cfg) => cfg.parse(raw),
             #[cfg(feature = "bao")]
             Self::Bao(cfg) => cfg.parse(raw),
+            #[cfg(feature = "aws")]
+            Self::Aws(cfg) => cfg.parse(raw),
+            #[cfg(feature = "gcp")]
+            Self::Gcp(cfg) => cfg.parse(raw),
+            #[cfg(feature = "azure")]
+            Self::Azure(cfg) => cfg.parse(raw),
+            #[cfg(feature = "sops")]
+            Self::Sops(cfg) => cfg.parse(raw),
+            #[cfg(feature = "exec-provider")]
+            Self::Exec(cfg) => cfg.parse(raw),
+            #[cfg(feature = "http")]
+            Self::Http(cfg) => cfg.parse(raw),
+            #[cfg(feature = "keepass")]
+            Self::KeePass(cfg) => cfg.parse(raw),
+            #[cfg(feature = "k8s")]
+            Self::K8s(cfg) => cfg.parse(raw),
+            #[cfg(feature = "bw")]
+            Self::Bw(cfg) => cfg.parse(raw),
+            #[cfg(feature = "doppler")]
+            Self::Doppler(cfg) => cfg.parse(raw),
         }
     }
 }
@@ -188,19 +422,270 @@ impl ProviderFactory for Provider {
             Self::Infisical(c) => Arc::new(infisical::InfisicalProvider::new(c.clone()).await?),
             #[cfg(feature = "bao")]
             Self::Bao(c) => Arc::new(bao::BaoProvider::new(c.clone()).await?),
+            #[cfg(feature = "aws")]
+            Self::Aws(c) => Arc::new(aws::AwsProvider::new(c.clone()).await?),
+            #[cfg(feature = "gcp")]
+            Self::Gcp(c) => Arc::new(gcp::GcpProvider::new(c.clone()).await?),
+            #[cfg(feature = "azure")]
+            Self::Azure(c) => Arc::new(azure::AzureProvider::new(c.clone()).await?),
+            #[cfg(feature = "sops")]
+            Self::Sops(c) => Arc::new(sops::SopsProvider::new(c.clone()).await?),
+            #[cfg(feature = "exec-provider")]
+            Self::Exec(c) => Arc::new(exec::ExecProvider::new(c.clone()).await?),
+            #[cfg(feature = "http")]
+            Self::Http(c) => Arc::new(http::HttpProvider::new(c.clone()).await?),
+            #[cfg(feature = "keepass")]
+            Self::KeePass(c) => Arc::new(keepass::KeePassProvider::new(c.clone()).await?),
+            #[cfg(feature = "k8s")]
+            Self::K8s(c) => Arc::new(k8s::K8sProvider::new(c.clone()).await?),
+            #[cfg(feature = "bw")]
+            Self::Bw(c) => Arc::new(bw::BwProvider::new(c.clone()).await?),
+            #[cfg(feature = "doppler")]
+            Self::Doppler(c) => Arc::new(doppler::DopplerProvider::new(c.clone()).await?),
         };
         Ok(provider)
     }
 }
 
+/// The set of provider backends configured for a single run.
+///
+/// References are routed to the first backend (in the order given) that
+/// recognizes them, so multiple backends can be mixed in the same templates.
+///
+/// Each backend is a failover chain of equivalent configurations, primary
+/// first. Most chains hold a single configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProviderSet {
+    chains: Vec<Vec<Provider>>,
+    retry: RetryPolicy,
+    cache_ttl: Option<Duration>,
+    local: LocalConfig,
+    #[cfg(feature = "fallback-cache")]
+    fallback: Option<FallbackConfig>,
+}
+
+impl ProviderSet {
+    pub fn new(backends: Vec<Provider>) -> Self {
+        Self::with_failover(backends.into_iter().map(|p| vec![p]).collect())
+    }
+
+    /// Creates a set from failover chains, each listing equivalent
+    /// configurations of one backend in order of preference.
+    pub fn with_failover(chains: Vec<Vec<Provider>>) -> Self {
+        Self {
+            chains,
+            retry: RetryPolicy::default(),
+            cache_ttl: None,
+            local: LocalConfig::default(),
+            #[cfg(feature = "fallback-cache")]
+            fallback: None,
+        }
+    }
+
+    /// Sets the policy for retrying transient failures of every backend.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Caches resolved secrets of every backend for the given TTL.
+    pub fn with_cache(mut self, ttl: Option<Duration>) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Allows `file://` and `env://` references to read what the config
+    /// allows. Neither is resolved otherwise.
+    pub fn with_local(mut self, local: LocalConfig) -> Self {
+        self.local = local;
+        self
+    }
+
+    /// Serves the last known good secrets of every backend from an
+    /// encrypted file while the backend is unreachable.
+    #[cfg(feature = "fallback-cache")]
+    pub fn with_fallback(mut self, fallback: Option<FallbackConfig>) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// The primary configuration of each backend.
+    pub fn backends(&self) -> impl Iterator<Item = &Provider> {
+        self.chains.iter().filter_map(|chain| chain.first())
+    }
+
+    pub fn chains(&self) -> &[Vec<Provider>] {
+        &self.chains
+    }
+
+    /// Builds the configured backends into a single provider.
+    ///
+    /// Each backend is independently managed, so a rotated token for one
+    /// backend only rebuilds that backend's client. Local `file://`,
+    /// `creds://` and `env://` references are resolved ahead of the
+    /// configured backends, since no provider claims those schemes.
+    ///
+    /// With a cache TTL, each backend is wrapped in a cache. Local
+    /// references are cheap to read and never cached.
+    ///
+    /// With a fallback cache, backends which are unreachable at startup
+    /// don't fail the build, so their secrets can be served from the
+    /// fallback until they recover.
+    ///
+    /// Concurrent fetches of the same references are coalesced into a
+    /// single request, shared by everyone holding the built provider.
+    pub async fn build(self) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+        #[cfg(feature = "fallback-cache")]
+        let store = self.fallback.map(|f| Arc::new(FallbackStore::new(f)));
+        #[cfg(feature = "fallback-cache")]
+        let deferred = store.is_some();
+        #[cfg(not(feature = "fallback-cache"))]
+        let deferred = false;
+
+        let mut backends: Vec<Arc<dyn SecretsProvider>> = Vec::with_capacity(self.chains.len() + 1);
+        backends.push(Arc::new(LocalProvider::new(self.local)));
+        for chain in self.chains {
+            let backend = Self::build_chain(chain, self.retry, deferred).await?;
+            #[cfg(feature = "fallback-cache")]
+            let backend: Arc<dyn SecretsProvider> = match &store {
+                Some(store) => Arc::new(FallbackProvider::new(backend, store.clone())),
+                None => backend,
+            };
+            backends.push(match self.cache_ttl {
+                Some(ttl) => Arc::new(CachingProvider::new(backend, ttl)),
+                None => backend,
+            });
+        }
+        let composite = Arc::new(CompositeProvider::new(backends));
+        Ok(Arc::new(SingleFlightProvider::new(composite)))
+    }
+
+    /// Builds a failover chain.
+    ///
+    /// Members of a chain are always built deferred: a configuration which
+    /// is unreachable at startup stays in the chain, and its client is
+    /// created on a later batch once it recovers. The build only fails if
+    /// no member is reachable and there is no fallback cache to serve from.
+    ///
+    /// Transient failures move on to the next alternate right away, so only
+    /// the last member of a chain retries.
+    async fn build_chain<C: ProviderFactory + 'static>(
+        chain: Vec<C>,
+        retry: RetryPolicy,
+        deferred: bool,
+    ) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+        let last = chain.len() - 1;
+        let mut members: Vec<Arc<dyn SecretsProvider>> = Vec::with_capacity(chain.len());
+        let mut reachable = false;
+        for (idx, provider) in chain.into_iter().enumerate() {
+            if last == 0 {
+                return Self::build_member(provider, retry, deferred).await;
+            }
+            let retry = if idx == last {
+                retry
+            } else {
+                RetryPolicy::none()
+            };
+            let managed = ManagedProvider::new_deferred(provider).await?;
+            reachable |= managed.is_ready().await;
+            members.push(Arc::new(managed.with_retry(retry)));
+        }
+
+        if !reachable && !deferred {
+            return Err(ProviderError::Unavailable(
+                "no provider of the failover chain is reachable".into(),
+            ));
+        }
+        Ok(Arc::new(FailoverProvider::new(members)))
+    }
+
+    async fn build_member<C: ProviderFactory + 'static>(
+        provider: C,
+        retry: RetryPolicy,
+        deferred: bool,
+    ) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+        let managed = if deferred {
+            ManagedProvider::new_deferred(provider).await?
+        } else {
+            ManagedProvider::new(provider).await?
+        };
+        Ok(Arc::new(managed.with_retry(retry)))
+    }
+}
+
+impl From<Provider> for ProviderSet {
+    fn from(provider: Provider) -> Self {
+        Self::new(vec![provider])
+    }
+}
+
 #[derive(
     Args, Debug, Clone, Hash, PartialEq, Eq, LayeredConfig, Deserialize, Serialize, Default,
 )]
 #[serde(rename_all = "kebab-case")]
 pub struct ProviderArgs {
-    /// Secrets provider backend to use.
-    #[arg(long, env = "SECRETS_PROVIDER")]
-    pub provider: Option<ProviderKind>,
+    /// Secrets provider backend(s) to use.
+    ///
+    /// Multiple providers can be provided, separated by commas, or supplied
+    /// multiple times as arguments. Each reference is resolved by the first
+    /// listed provider which recognizes it.
+    ///
+    /// Example: `--provider op-connect --provider bao`
+    #[arg(
+        long,
+        env = "SECRETS_PROVIDER",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub provider: Vec<ProviderKind>,
+
+    /// TOML file(s) with alternate provider configurations, tried in
+    /// order when a provider is unreachable, rate limited or unavailable.
+    ///
+    /// Each file holds provider settings in the same form as the main
+    /// config file, layered over the primary configuration, so it only
+    /// needs the settings which differ.
+    ///
+    /// Example: `--provider-failover /etc/locket/connect-replica.toml`
+    #[arg(
+        long,
+        env = "SECRETS_PROVIDER_FAILOVER",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub provider_failover: Vec<AbsolutePath>,
+
+    /// Optional: Keep resolved secrets in memory for this long, instead of
+    /// fetching them from the provider on every render
+    ///
+    /// Useful in watch mode, where every template change would otherwise
+    /// refetch every reference. Changes to a secret in the provider take
+    /// up to this long to be picked up. Cached values are zeroized when
+    /// they are evicted.
+    ///
+    /// Supports human-friendly durations like 30s or 5m. Plain numbers are seconds.
+    #[arg(long, env = "SECRETS_PROVIDER_CACHE_TTL")]
+    pub provider_cache_ttl: Option<ProviderDuration>,
+
+    /// What local `file://` and `env://` references may read
+    #[command(flatten, next_help_heading = "Local References")]
+    #[serde(flatten)]
+    pub local: LocalArgs,
+
+    /// Retry policy for transient provider failures
+    #[command(flatten, next_help_heading = "Provider Retry")]
+    #[serde(flatten)]
+    pub retry: RetryArgs,
+
+    /// Encrypted cache of the last known good secrets
+    #[cfg(feature = "fallback-cache")]
+    #[command(flatten, next_help_heading = "Provider Fallback Cache")]
+    #[serde(flatten)]
+    pub fallback: FallbackArgs,
 
     /// Provider-specific configuration
     #[command(flatten)]
@@ -208,31 +693,62 @@ pub struct ProviderArgs {
     pub config: ProviderConfigs,
 }
 
-impl TryFrom<ProviderArgs> for Provider {
+impl TryFrom<ProviderArgs> for ProviderSet {
     type Error = crate::error::LocketError;
 
     fn try_from(args: ProviderArgs) -> Result<Self, Self::Error> {
-        use crate::config::ApplyDefaults;
+        use crate::config::{ApplyDefaults, ConfigError, Overlay};
         let args = args.apply_defaults();
 
-        let kind = args.provider.ok_or_else(|| {
-            crate::config::ConfigError::Validation(
+        if args.provider.is_empty() {
+            return Err(ConfigError::Validation(
                 "Missing required argument: --provider <kind>".into(),
             )
-        })?;
+            .into());
+        }
 
-        match kind {
-            #[cfg(feature = "bws")]
-            ProviderKind::Bws => Ok(Provider::Bws(args.config.bws.try_into()?)),
-            #[cfg(feature = "op")]
-            ProviderKind::Op => Ok(Provider::Op(args.config.op.try_into()?)),
-            #[cfg(feature = "connect")]
-            ProviderKind::OpConnect => Ok(Provider::Connect(args.config.connect.try_into()?)),
-            #[cfg(feature = "infisical")]
-            ProviderKind::Infisical => Ok(Provider::Infisical(args.config.infisical.try_into()?)),
-            #[cfg(feature = "bao")]
-            ProviderKind::Bao => Ok(Provider::Bao(args.config.bao.try_into()?)),
+        let alternates = args
+            .provider_failover
+            .iter()
+            .map(|path| {
+                let content = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+                toml::from_str::<ProviderConfigs>(&content).map_err(ConfigError::Parse)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut backends = Vec::with_capacity(args.provider.len());
+        for (i, kind) in args.provider.iter().enumerate() {
+            if args.provider[..i].contains(kind) {
+                return Err(ConfigError::Validation(format!(
+                    "Provider '{}' specified more than once",
+                    kind.as_str()
+                ))
+                .into());
+            }
+            let primary = args.config.select(*kind)?;
+            let mut chain = vec![primary];
+            for alternate in &alternates {
+                let provider = args
+                    .config
+                    .clone()
+                    .overlay(alternate.clone())
+                    .select(*kind)?;
+                // Alternates which leave this provider's settings untouched
+                // are meant for another provider in the set.
+                if !chain.contains(&provider) {
+                    chain.push(provider);
+                }
+            }
+            backends.push(chain);
         }
+
+        let set = Self::with_failover(backends)
+            .with_retry(args.retry.try_into()?)
+            .with_cache(args.provider_cache_ttl.map(|ttl| ttl.0))
+            .with_local(args.local.try_into()?);
+        #[cfg(feature = "fallback-cache")]
+        let set = set.with_fallback(args.fallback.try_into()?);
+        Ok(set)
     }
 }
 
@@ -254,6 +770,73 @@ pub enum ProviderKind {
     /// OpenBao / HashiCorp Vault Provider
     #[cfg(feature = "bao")]
     Bao,
+    /// AWS Secrets Manager / Parameter Store Provider
+    #[cfg(feature = "aws")]
+    Aws,
+    /// Google Cloud Secret Manager Provider
+    #[cfg(feature = "gcp")]
+    Gcp,
+    /// Azure Key Vault Provider
+    #[cfg(feature = "azure")]
+    Azure,
+    /// SOPS Encrypted Files Provider
+    #[cfg(feature = "sops")]
+    Sops,
+    /// External Plugin Provider
+    #[cfg(feature = "exec-provider")]
+    Exec,
+    /// HTTP Webhook Provider
+    #[cfg(feature = "http")]
+    Http,
+    /// KeePass Database Provider
+    #[cfg(feature = "keepass")]
+    Keepass,
+    /// Kubernetes Secrets Provider
+    #[cfg(feature = "k8s")]
+    K8s,
+    /// Bitwarden / Vaultwarden Vault Provider (bw serve)
+    #[cfg(feature = "bw")]
+    Bw,
+    /// Doppler Provider
+    #[cfg(feature = "doppler")]
+    Doppler,
+}
+
+impl ProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "op")]
+            Self::Op => "op",
+            #[cfg(feature = "connect")]
+            Self::OpConnect => "op-connect",
+            #[cfg(feature = "bws")]
+            Self::Bws => "bws",
+            #[cfg(feature = "infisical")]
+            Self::Infisical => "infisical",
+            #[cfg(feature = "bao")]
+            Self::Bao => "bao",
+            #[cfg(feature = "aws")]
+            Self::Aws => "aws",
+            #[cfg(feature = "gcp")]
+            Self::Gcp => "gcp",
+            #[cfg(feature = "azure")]
+            Self::Azure => "azure",
+            #[cfg(feature = "sops")]
+            Self::Sops => "sops",
+            #[cfg(feature = "exec-provider")]
+            Self::Exec => "exec",
+            #[cfg(feature = "http")]
+            Self::Http => "http",
+            #[cfg(feature = "keepass")]
+            Self::Keepass => "keepass",
+            #[cfg(feature = "k8s")]
+            Self::K8s => "k8s",
+            #[cfg(feature = "bw")]
+            Self::Bw => "bw",
+            #[cfg(feature = "doppler")]
+            Self::Doppler => "doppler",
+        }
+    }
 }
 
 #[derive(
@@ -285,4 +868,178 @@ pub struct ProviderConfigs {
     #[command(flatten, next_help_heading = "OpenBao / Vault Provider")]
     #[serde(flatten)]
     pub bao: config::bao::BaoArgs,
+
+    #[cfg(feature = "aws")]
+    #[command(flatten, next_help_heading = "AWS Provider")]
+    #[serde(flatten)]
+    pub aws: config::aws::AwsArgs,
+
+    #[cfg(feature = "gcp")]
+    #[command(flatten, next_help_heading = "Google Cloud Secret Manager")]
+    #[serde(flatten)]
+    pub gcp: config::gcp::GcpArgs,
+
+    #[cfg(feature = "azure")]
+    #[command(flatten, next_help_heading = "Azure Key Vault")]
+    #[serde(flatten)]
+    pub azure: config::azure::AzureArgs,
+
+    #[cfg(feature = "sops")]
+    #[command(flatten, next_help_heading = "SOPS Provider")]
+    #[serde(flatten)]
+    pub sops: config::sops::SopsArgs,
+
+    #[cfg(feature = "exec-provider")]
+    #[command(flatten, next_help_heading = "Exec Plugin Provider")]
+    #[serde(flatten)]
+    pub exec: config::exec::ExecProviderArgs,
+
+    #[cfg(feature = "http")]
+    #[command(flatten, next_help_heading = "HTTP Webhook Provider")]
+    #[serde(flatten)]
+    pub http: config::http::HttpArgs,
+
+    #[cfg(feature = "keepass")]
+    #[command(flatten, next_help_heading = "KeePass Provider")]
+    #[serde(flatten)]
+    pub keepass: config::keepass::KeePassArgs,
+
+    #[cfg(feature = "k8s")]
+    #[command(flatten, next_help_heading = "Kubernetes Provider")]
+    #[serde(flatten)]
+    pub k8s: config::k8s::K8sArgs,
+
+    #[cfg(feature = "bw")]
+    #[command(flatten, next_help_heading = "Bitwarden Vault Provider")]
+    #[serde(flatten)]
+    pub bw: config::bw::BwArgs,
+
+    #[cfg(feature = "doppler")]
+    #[command(flatten, next_help_heading = "Doppler Provider")]
+    #[serde(flatten)]
+    pub doppler: config::doppler::DopplerArgs,
+}
+
+impl ProviderConfigs {
+    /// Builds the backend configuration for a single provider kind.
+    fn select(&self, kind: ProviderKind) -> Result<Provider, crate::error::LocketError> {
+        let provider = match kind {
+            #[cfg(feature = "bws")]
+            ProviderKind::Bws => Provider::Bws(self.bws.clone().try_into()?),
+            #[cfg(feature = "op")]
+            ProviderKind::Op => Provider::Op(self.op.clone().try_into()?),
+            #[cfg(feature = "connect")]
+            ProviderKind::OpConnect => Provider::Connect(self.connect.clone().try_into()?),
+            #[cfg(feature = "infisical")]
+            ProviderKind::Infisical => Provider::Infisical(self.infisical.clone().try_into()?),
+            #[cfg(feature = "bao")]
+            ProviderKind::Bao => Provider::Bao(self.bao.clone().try_into()?),
+            #[cfg(feature = "aws")]
+            ProviderKind::Aws => Provider::Aws(self.aws.clone().try_into()?),
+            #[cfg(feature = "gcp")]
+            ProviderKind::Gcp => Provider::Gcp(self.gcp.clone().try_into()?),
+            #[cfg(feature = "azure")]
+            ProviderKind::Azure => Provider::Azure(self.azure.clone().try_into()?),
+            #[cfg(feature = "sops")]
+            ProviderKind::Sops => Provider::Sops(self.sops.clone().try_into()?),
+            #[cfg(feature = "exec-provider")]
+            ProviderKind::Exec => Provider::Exec(self.exec.clone().try_into()?),
+            #[cfg(feature = "http")]
+            ProviderKind::Http => Provider::Http(self.http.clone().try_into()?),
+            #[cfg(feature = "keepass")]
+            ProviderKind::Keepass => Provider::KeePass(self.keepass.clone().try_into()?),
+            #[cfg(feature = "k8s")]
+            ProviderKind::K8s => Provider::K8s(self.k8s.clone().try_into()?),
+            #[cfg(feature = "bw")]
+            ProviderKind::Bw => Provider::Bw(self.bw.clone().try_into()?),
+            #[cfg(feature = "doppler")]
+            ProviderKind::Doppler => Provider::Doppler(self.doppler.clone().try_into()?),
+        };
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(
+        feature = "azure",
+        feature = "bao",
+        feature = "bw",
+        feature = "connect",
+        feature = "doppler",
+        feature = "gcp",
+        feature = "http",
+        feature = "infisical",
+        feature = "k8s"
+    ))]
+    #[test]
+    fn test_rate_limited_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+        let mut headers = HeaderMap::new();
+        assert!(matches!(
+            ProviderError::rate_limited(&headers),
+            ProviderError::RateLimit { retry_after: None }
+        ));
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert!(matches!(
+            ProviderError::rate_limited(&headers),
+            ProviderError::RateLimit { retry_after: Some(d) } if d == Duration::from_secs(7)
+        ));
+
+        // HTTP dates are not understood, and fall back to the retry policy.
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert!(matches!(
+            ProviderError::rate_limited(&headers),
+            ProviderError::RateLimit { retry_after: None }
+        ));
+    }
+
+    #[cfg(all(feature = "http", feature = "doppler"))]
+    #[test]
+    fn test_failover_chains() {
+        let dir = tempfile::tempdir().unwrap();
+        let replica = dir.path().join("replica.toml");
+        std::fs::write(&replica, "http-url = \"https://replica.example.com\"\n").unwrap();
+
+        let args: ProviderArgs = toml::from_str(&format!(
+            r#"
+            provider = ["http", "doppler"]
+            provider-failover = ["{}"]
+            http-url = "https://primary.example.com"
+            http-token = "http-token"
+            doppler-token = "doppler-token"
+            "#,
+            replica.display()
+        ))
+        .unwrap();
+        let set = ProviderSet::try_from(args).unwrap();
+
+        let [http, doppler] = set.chains() else {
+            panic!("expected two chains");
+        };
+        let urls: Vec<String> = http
+            .iter()
+            .map(|p| match p {
+                Provider::Http(c) => c.http_url.to_string(),
+                other => panic!("unexpected provider {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://primary.example.com/",
+                "https://replica.example.com/"
+            ]
+        );
+        // The alternate only changes the HTTP settings, so Doppler has no failover.
+        assert_eq!(doppler.len(), 1);
+        assert_eq!(set.backends().count(), 2);
+    }
 }
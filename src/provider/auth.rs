@@ -24,6 +24,13 @@ pub trait TokenExchange: Send + Sync {
     async fn login(&self) -> Result<ExpiringToken, ProviderError>;
 }
 
+#[async_trait]
+impl<E: TokenExchange + ?Sized> TokenExchange for Box<E> {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        (**self).login().await
+    }
+}
+
 /// Caches a client token and lazily renews it when it expires or is
 /// invalidated.
 ///
@@ -0,0 +1,315 @@
+//! AWS provider implementation.
+//!
+//! Fetches secrets from AWS Secrets Manager and SSM Parameter Store through
+//! their JSON APIs. Requests are signed with SigV4 using static (or file
+//! sourced) credentials, so no AWS SDK is required.
+//!
+//! Rotated credentials are picked up by the `ManagedProvider` wrapper,
+//! which rebuilds the client when a credential file changes.
+
+mod parameter_store;
+mod secrets_manager;
+mod sigv4;
+
+use super::{
+    ConcurrencyLimit, ProviderError, ReferenceParser, SecretsProvider, ServerUrl,
+    config::aws::{AwsConfig, AwsRegion},
+    references::{AwsSmReference, Extract, SecretReference, SsmReference, parse_aws_reference},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use reqwest::{Client, StatusCode};
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use sigv4::{Credentials, SignableRequest, Signer};
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
+/// Values resolved by a single request, keyed by reference.
+type SecretMap = HashMap<SecretReference, SecretString>;
+
+pub struct AwsProvider {
+    client: AwsClient,
+    max_concurrent: ConcurrencyLimit,
+}
+
+impl AwsProvider {
+    pub async fn new(config: AwsConfig) -> Result<Self, ProviderError> {
+        let credentials = Credentials {
+            access_key_id: config.aws_access_key_id.resolve().await?,
+            secret_access_key: config.aws_secret_access_key.resolve().await?,
+            session_token: match &config.aws_session_token {
+                Some(token) => Some(token.resolve().await?),
+                None => None,
+            },
+        };
+
+        let http = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        Ok(Self {
+            client: AwsClient {
+                http,
+                credentials,
+                region: config.aws_region,
+                endpoint: config.aws_endpoint,
+            },
+            max_concurrent: config.aws_max_concurrent,
+        })
+    }
+}
+
+impl ReferenceParser for AwsProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        parse_aws_reference(raw)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for AwsProvider {
+    async fn fetch_map(&self, references: &[SecretReference]) -> Result<SecretMap, ProviderError> {
+        let secrets: Vec<&AwsSmReference> = references
+            .iter()
+            .filter_map(AwsSmReference::extract)
+            .collect();
+        let parameters: Vec<&SsmReference> = references
+            .iter()
+            .filter_map(SsmReference::extract)
+            .collect();
+
+        // Requests to both services share the concurrency limit.
+        let mut requests = secrets_manager::requests(&self.client, secrets);
+        requests.extend(parameter_store::requests(&self.client, parameters));
+
+        let results = stream::iter(requests)
+            .buffer_unordered(self.max_concurrent.into_inner())
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut map = HashMap::new();
+        for partial in results {
+            map.extend(partial?);
+        }
+        Ok(map)
+    }
+}
+
+/// An AWS service reachable through the JSON 1.1 protocol.
+struct Service {
+    /// Human readable name, for errors.
+    name: &'static str,
+    /// Name used in the SigV4 credential scope.
+    signing_name: &'static str,
+    /// Subdomain of the service's regional endpoint.
+    endpoint_prefix: &'static str,
+    /// Prefix of the `X-Amz-Target` action header.
+    target_prefix: &'static str,
+}
+
+/// Signs and sends requests to AWS JSON protocol APIs.
+struct AwsClient {
+    http: Client,
+    credentials: Credentials,
+    region: AwsRegion,
+    endpoint: Option<ServerUrl>,
+}
+
+impl AwsClient {
+    fn endpoint(&self, service: &Service) -> Url {
+        if let Some(endpoint) = &self.endpoint {
+            return endpoint.endpoint([""]);
+        }
+        let region = self.region.as_str();
+        let domain = if region.starts_with("cn-") {
+            "amazonaws.com.cn"
+        } else {
+            "amazonaws.com"
+        };
+        let raw = format!("https://{}.{}.{}/", service.endpoint_prefix, region, domain);
+        Url::parse(&raw).expect("region is validated to be a valid host label")
+    }
+
+    /// Invokes `action` on `service` with a JSON request body.
+    async fn call<Req, Resp>(
+        &self,
+        service: &Service,
+        action: &str,
+        request: &Req,
+    ) -> Result<Resp, ProviderError>
+    where
+        Req: Serialize + ?Sized,
+        Resp: DeserializeOwned,
+    {
+        let url = self.endpoint(service);
+        let body = serde_json::to_vec(request).map_err(|e| ProviderError::Other(e.to_string()))?;
+        let target = format!("{}.{}", service.target_prefix, action);
+        let headers = [
+            ("content-type", "application/x-amz-json-1.1"),
+            ("x-amz-target", target.as_str()),
+        ];
+
+        let signer = Signer {
+            credentials: &self.credentials,
+            region: self.region.as_str(),
+            service: service.signing_name,
+        };
+        let auth_headers = signer.sign(
+            &SignableRequest {
+                method: "POST",
+                url: &url,
+                headers: &headers,
+                body: &body,
+            },
+            chrono::Utc::now(),
+        );
+
+        let mut req = self.http.post(url).body(body);
+        for (name, value) in headers {
+            req = req.header(name, value);
+        }
+        for (name, value) in auth_headers {
+            req = req.header(name, value);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        let status = resp.status();
+        if status.is_success() {
+            return resp
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e)));
+        }
+
+        let text = resp.text().await.unwrap_or_default();
+        Err(map_error(service, status, &text))
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct AwsErrorBody {
+    #[serde(rename = "__type", default)]
+    kind: String,
+    #[serde(alias = "Message", default)]
+    message: String,
+}
+
+/// Maps an AWS JSON protocol error response to a `ProviderError`.
+fn map_error(service: &Service, status: StatusCode, body: &str) -> ProviderError {
+    let error: AwsErrorBody = serde_json::from_str(body).unwrap_or_default();
+    // Error types may be namespaced, e.g. `com.amazonaws...#ThrottlingException`
+    let code = error.kind.rsplit('#').next().unwrap_or_default();
+    let detail = if error.message.is_empty() {
+        code.to_string()
+    } else {
+        format!("{}: {}", code, error.message)
+    };
+
+    match code {
+        "ResourceNotFoundException" | "ParameterNotFound" => ProviderError::NotFound(detail),
+        "AccessDeniedException"
+        | "UnrecognizedClientException"
+        | "InvalidSignatureException"
+        | "IncompleteSignature"
+        | "InvalidClientTokenId"
+        | "ExpiredTokenException"
+        | "MissingAuthenticationToken" => ProviderError::Unauthorized(detail),
+        "ThrottlingException" | "TooManyRequestsException" | "ThrottledException" => {
+            ProviderError::RateLimit { retry_after: None }
+        }
+        _ => match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => ProviderError::Unauthorized(detail),
+            StatusCode::TOO_MANY_REQUESTS => ProviderError::RateLimit { retry_after: None },
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => {
+                ProviderError::Unavailable(format!("AWS {} {}: {}", service.name, status, detail))
+            }
+            _ => ProviderError::Other(format!("AWS {} error {}: {}", service.name, status, detail)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    pub(super) fn client(endpoint: Option<&str>, region: &str) -> AwsClient {
+        AwsClient {
+            http: Client::new(),
+            credentials: Credentials {
+                access_key_id: SecretString::new("AKID".into()),
+                secret_access_key: SecretString::new("secret".into()),
+                session_token: None,
+            },
+            region: AwsRegion::from_str(region).unwrap(),
+            endpoint: endpoint.map(|e| ServerUrl::from_str(e).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_default_endpoint_is_regional() {
+        let client = client(None, "eu-west-1");
+        assert_eq!(
+            client.endpoint(&secrets_manager::SERVICE).as_str(),
+            "https://secretsmanager.eu-west-1.amazonaws.com/"
+        );
+        let client = self::client(None, "cn-north-1");
+        assert_eq!(
+            client.endpoint(&secrets_manager::SERVICE).as_str(),
+            "https://secretsmanager.cn-north-1.amazonaws.com.cn/"
+        );
+        assert_eq!(
+            client.endpoint(&parameter_store::SERVICE).as_str(),
+            "https://ssm.cn-north-1.amazonaws.com.cn/"
+        );
+    }
+
+    #[test]
+    fn test_endpoint_override() {
+        let client = client(Some("http://localhost:4566"), "us-east-1");
+        assert_eq!(
+            client.endpoint(&secrets_manager::SERVICE).as_str(),
+            "http://localhost:4566/"
+        );
+    }
+
+    #[test]
+    fn test_error_mapping() {
+        let svc = &secrets_manager::SERVICE;
+        let not_found = r#"{"__type":"ResourceNotFoundException","Message":"no such secret"}"#;
+        assert!(matches!(
+            map_error(svc, StatusCode::BAD_REQUEST, not_found),
+            ProviderError::NotFound(_)
+        ));
+
+        let expired =
+            r#"{"__type":"com.amazon.coral.service#ExpiredTokenException","message":"expired"}"#;
+        assert!(matches!(
+            map_error(svc, StatusCode::BAD_REQUEST, expired),
+            ProviderError::Unauthorized(_)
+        ));
+
+        let throttled = r#"{"__type":"ThrottlingException"}"#;
+        assert!(matches!(
+            map_error(svc, StatusCode::BAD_REQUEST, throttled),
+            ProviderError::RateLimit { .. }
+        ));
+
+        assert!(matches!(
+            map_error(svc, StatusCode::FORBIDDEN, "not json"),
+            ProviderError::Unauthorized(_)
+        ));
+        assert!(matches!(
+            map_error(svc, StatusCode::INTERNAL_SERVER_ERROR, ""),
+            ProviderError::Other(_)
+        ));
+    }
+}
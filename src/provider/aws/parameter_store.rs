@@ -0,0 +1,151 @@
+//! AWS SSM Parameter Store `GetParameters` support.
+
+use super::{AwsClient, SecretMap, Service};
+use crate::provider::{
+    ProviderError,
+    references::{SecretReference, SsmReference},
+};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::debug;
+
+pub(super) const SERVICE: Service = Service {
+    name: "Parameter Store",
+    signing_name: "ssm",
+    endpoint_prefix: "ssm",
+    target_prefix: "AmazonSSM",
+};
+
+/// Maximum number of names accepted by a single `GetParameters` call.
+const MAX_NAMES_PER_REQUEST: usize = 10;
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetParametersRequest<'a> {
+    names: Vec<&'a str>,
+    with_decryption: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetParametersResponse {
+    #[serde(default)]
+    parameters: Vec<Parameter>,
+    #[serde(default)]
+    invalid_parameters: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Parameter {
+    name: String,
+    value: SecretString,
+    /// The `:version` or `:label` suffix, when one was requested.
+    selector: Option<String>,
+    #[serde(rename = "ARN")]
+    arn: Option<String>,
+}
+
+/// Batches parameter references into `GetParameters` calls of at most
+/// ten names each, instead of one request per parameter.
+pub(super) fn requests<'a>(
+    client: &'a AwsClient,
+    mut references: Vec<&'a SsmReference>,
+) -> Vec<BoxFuture<'a, Result<SecretMap, ProviderError>>> {
+    references.sort_by(|a, b| a.name().cmp(b.name()));
+    references.dedup();
+
+    references
+        .chunks(MAX_NAMES_PER_REQUEST)
+        .map(|chunk| get_parameters(client, chunk.to_vec()).boxed())
+        .collect()
+}
+
+async fn get_parameters(
+    client: &AwsClient,
+    references: Vec<&SsmReference>,
+) -> Result<SecretMap, ProviderError> {
+    let request = GetParametersRequest {
+        names: references.iter().map(|r| r.name()).collect(),
+        with_decryption: true,
+    };
+
+    let resp: GetParametersResponse = client.call(&SERVICE, "GetParameters", &request).await?;
+    if !resp.invalid_parameters.is_empty() {
+        debug!(
+            "SSM parameters not found: {}",
+            resp.invalid_parameters.join(", ")
+        );
+    }
+
+    // Parameters come back without the selector in their name, and may be
+    // requested by ARN, so index them by every form they can be requested by.
+    let mut values: HashMap<String, SecretString> = HashMap::new();
+    for parameter in resp.parameters {
+        let selector = parameter.selector.as_deref().unwrap_or_default();
+        if let Some(arn) = &parameter.arn {
+            values.insert(format!("{}{}", arn, selector), parameter.value.clone());
+        }
+        values.insert(format!("{}{}", parameter.name, selector), parameter.value);
+    }
+
+    Ok(references
+        .into_iter()
+        .filter_map(|r| {
+            let value = values.get(r.name())?.clone();
+            Some((SecretReference::AwsParameterStore(r.clone()), value))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_requests_are_batched_by_ten() {
+        let client = super::super::tests::client(None, "us-east-1");
+        let refs: Vec<SsmReference> = (0..25)
+            .map(|i| SsmReference::from_str(&format!("ssm:///app/param-{i:02}")).unwrap())
+            .collect();
+        let mut borrowed: Vec<&SsmReference> = refs.iter().collect();
+        // Duplicates do not cost an extra request
+        borrowed.push(&refs[0]);
+
+        assert_eq!(requests(&client, borrowed).len(), 3);
+        assert!(requests(&client, Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_request_serialization() {
+        let request = GetParametersRequest {
+            names: vec!["/app/a", "/app/b:2"],
+            with_decryption: true,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"Names":["/app/a","/app/b:2"],"WithDecryption":true}"#
+        );
+    }
+
+    #[test]
+    fn test_response_deserialization() {
+        let json = r#"{
+            "Parameters": [
+                {"Name": "/app/b", "Type": "SecureString", "Value": "s3cret",
+                 "Version": 2, "Selector": ":2", "ARN": "arn:aws:ssm:us-east-1:1:parameter/app/b"}
+            ],
+            "InvalidParameters": ["/app/missing"]
+        }"#;
+        let resp: GetParametersResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.parameters.len(), 1);
+        assert_eq!(resp.parameters[0].value.expose_secret(), "s3cret");
+        assert_eq!(resp.parameters[0].selector.as_deref(), Some(":2"));
+        assert_eq!(resp.invalid_parameters, vec!["/app/missing"]);
+    }
+}
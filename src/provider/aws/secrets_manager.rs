@@ -0,0 +1,166 @@
+//! AWS Secrets Manager `GetSecretValue` support.
+
+use super::{AwsClient, SecretMap, Service};
+use crate::provider::{
+    ProviderError,
+    references::{AwsSmLocation, AwsSmReference, SecretReference},
+    types::JsonField,
+};
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use secrecy::{ExposeSecret, SecretString};
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::warn;
+
+pub(super) const SERVICE: Service = Service {
+    name: "Secrets Manager",
+    signing_name: "secretsmanager",
+    endpoint_prefix: "secretsmanager",
+    target_prefix: "secretsmanager",
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueRequest<'a> {
+    secret_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_stage: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version_id: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueResponse {
+    secret_string: Option<SecretString>,
+    secret_binary: Option<IgnoredAny>,
+}
+
+/// Builds one `GetSecretValue` request per secret version, no matter how
+/// many of its JSON keys are referenced.
+pub(super) fn requests<'a>(
+    client: &'a AwsClient,
+    references: Vec<&'a AwsSmReference>,
+) -> Vec<BoxFuture<'a, Result<SecretMap, ProviderError>>> {
+    let mut groups: HashMap<&AwsSmLocation, Vec<&AwsSmReference>> = HashMap::new();
+    for r in references {
+        groups.entry(&r.location).or_default().push(r);
+    }
+
+    groups
+        .into_iter()
+        .map(|(location, group_refs)| resolve_group(client, location, group_refs).boxed())
+        .collect()
+}
+
+async fn resolve_group(
+    client: &AwsClient,
+    location: &AwsSmLocation,
+    group_refs: Vec<&AwsSmReference>,
+) -> Result<SecretMap, ProviderError> {
+    let mut map = HashMap::new();
+    let value = match get_secret_value(client, location).await {
+        Ok(Some(value)) => value,
+        Ok(None) => {
+            warn!(
+                "Secret {} is binary; only string secrets can be injected",
+                location
+            );
+            return Ok(map);
+        }
+        // Whole secret not found: leave all of its keys unresolved.
+        Err(ProviderError::NotFound(_)) => return Ok(map),
+        Err(e) => return Err(e),
+    };
+
+    // Only parse the secret string as JSON if some reference needs a key.
+    let mut fields: Option<Option<HashMap<String, JsonField>>> = None;
+    for r in group_refs {
+        let Some(key) = &r.key else {
+            map.insert(SecretReference::AwsSecretsManager(r.clone()), value.clone());
+            continue;
+        };
+
+        let fields = fields
+            .get_or_insert_with(|| serde_json::from_str(value.expose_secret()).ok())
+            .as_ref();
+        match fields.map(|f| f.get(key)) {
+            None => warn!(
+                "Secret {} is not a JSON object; cannot select key '{}'",
+                location, key
+            ),
+            Some(Some(JsonField::Scalar(secret))) => {
+                map.insert(
+                    SecretReference::AwsSecretsManager(r.clone()),
+                    secret.clone(),
+                );
+            }
+            Some(Some(JsonField::Unsupported)) => warn!(
+                "Key '{}' in {} is not a scalar value; skipping",
+                key, location
+            ),
+            // Key not present. Leave unresolved, per fetch_map contract.
+            Some(None) => {}
+        }
+    }
+
+    Ok(map)
+}
+
+/// Returns the secret string of a secret version, or `None` if the
+/// secret only holds binary data.
+async fn get_secret_value(
+    client: &AwsClient,
+    location: &AwsSmLocation,
+) -> Result<Option<SecretString>, ProviderError> {
+    let request = GetSecretValueRequest {
+        secret_id: &location.secret_id,
+        version_stage: location.version_stage.as_deref(),
+        version_id: location.version_id.as_deref(),
+    };
+
+    let resp: GetSecretValueResponse = client.call(&SERVICE, "GetSecretValue", &request).await?;
+    match (resp.secret_string, resp.secret_binary) {
+        (Some(value), _) => Ok(Some(value)),
+        (None, Some(_)) => Ok(None),
+        (None, None) => Err(ProviderError::NotFound(location.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_omits_unset_version() {
+        let request = GetSecretValueRequest {
+            secret_id: "prod/db",
+            version_stage: None,
+            version_id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"SecretId":"prod/db"}"#
+        );
+
+        let request = GetSecretValueRequest {
+            secret_id: "prod/db",
+            version_stage: Some("AWSPREVIOUS"),
+            version_id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"SecretId":"prod/db","VersionStage":"AWSPREVIOUS"}"#
+        );
+    }
+
+    #[test]
+    fn test_response_binary_secret() {
+        let resp: GetSecretValueResponse =
+            serde_json::from_str(r#"{"Name":"bin","SecretBinary":"aGVsbG8="}"#).unwrap();
+        assert!(resp.secret_string.is_none());
+        assert!(resp.secret_binary.is_some());
+    }
+}
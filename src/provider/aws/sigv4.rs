@@ -0,0 +1,272 @@
+//! AWS Signature Version 4 request signing.
+//!
+//! Implements the header based variant for requests with a fully
+//! buffered body, which is all the JSON protocol APIs need.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Everything outside the RFC 3986 unreserved set, as required by the
+/// canonical request encoding.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Resolved AWS credentials used to sign requests.
+pub struct Credentials {
+    pub access_key_id: SecretString,
+    pub secret_access_key: SecretString,
+    pub session_token: Option<SecretString>,
+}
+
+/// Signs requests for a single service in a single region.
+pub struct Signer<'a> {
+    pub credentials: &'a Credentials,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+/// The parts of an HTTP request covered by the signature.
+pub struct SignableRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a Url,
+    pub headers: &'a [(&'a str, &'a str)],
+    pub body: &'a [u8],
+}
+
+impl Signer<'_> {
+    /// Returns the headers which must be added to the request for it to be
+    /// accepted: `x-amz-date`, `x-amz-security-token` (for temporary
+    /// credentials) and `authorization`.
+    ///
+    /// The `host` header is derived from the URL and signed, but not
+    /// returned, since the HTTP client sets it from the same URL.
+    pub fn sign(
+        &self,
+        request: &SignableRequest<'_>,
+        now: DateTime<Utc>,
+    ) -> Vec<(&'static str, String)> {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let scope = format!("{}/{}/{}/aws4_request", date, self.region, self.service);
+
+        let mut added: Vec<(&'static str, String)> = vec![("x-amz-date", amz_date.clone())];
+        if let Some(token) = &self.credentials.session_token {
+            added.push(("x-amz-security-token", token.expose_secret().to_string()));
+        }
+
+        let mut headers: Vec<(String, String)> = request
+            .headers
+            .iter()
+            .map(|(k, v)| (k.to_ascii_lowercase(), normalize_value(v)))
+            .chain(added.iter().map(|(k, v)| (k.to_string(), v.clone())))
+            .chain(std::iter::once(("host".to_string(), host(request.url))))
+            .collect();
+        headers.sort();
+
+        let signed_headers = headers
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v))
+            .collect();
+
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            request.method,
+            canonical_uri(request.url),
+            canonical_query(request.url),
+            canonical_headers,
+            signed_headers,
+            hex::encode(Sha256::digest(request.body)),
+        );
+
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes())),
+        );
+
+        let secret = format!("AWS4{}", self.credentials.secret_access_key.expose_secret());
+        let key = [date.as_str(), self.region, self.service, "aws4_request"]
+            .iter()
+            .fold(secret.into_bytes(), |key, part| hmac(&key, part.as_bytes()));
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+        added.push((
+            "authorization",
+            format!(
+                "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                ALGORITHM,
+                self.credentials.access_key_id.expose_secret(),
+                scope,
+                signed_headers,
+                signature
+            ),
+        ));
+        added
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// The value of the `host` header the HTTP client will send for this URL.
+fn host(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// Trims the value and collapses inner runs of spaces.
+fn normalize_value(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The URL path, with each already-encoded segment encoded again
+/// (every service except S3 expects the double encoding).
+fn canonical_uri(url: &Url) -> String {
+    let path = url.path();
+    if path.is_empty() {
+        return "/".to_string();
+    }
+    path.split('/')
+        .map(|segment| utf8_percent_encode(segment, UNRESERVED).to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| {
+            (
+                utf8_percent_encode(&k, UNRESERVED).to_string(),
+                utf8_percent_encode(&v, UNRESERVED).to_string(),
+            )
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn example_credentials(session_token: Option<&str>) -> Credentials {
+        Credentials {
+            access_key_id: SecretString::new("AKIDEXAMPLE".into()),
+            secret_access_key: SecretString::new("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".into()),
+            session_token: session_token.map(|t| SecretString::new(t.into())),
+        }
+    }
+
+    fn example_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap()
+    }
+
+    fn authorization<'a>(headers: &'a [(&'static str, String)]) -> &'a str {
+        headers
+            .iter()
+            .find(|(k, _)| *k == "authorization")
+            .map(|(_, v)| v.as_str())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_get_vanilla() {
+        // "get-vanilla" from the AWS SigV4 test suite
+        let credentials = example_credentials(None);
+        let signer = Signer {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "service",
+        };
+        let url = Url::parse("https://example.amazonaws.com/").unwrap();
+        let headers = signer.sign(
+            &SignableRequest {
+                method: "GET",
+                url: &url,
+                headers: &[],
+                body: b"",
+            },
+            example_time(),
+        );
+
+        assert_eq!(
+            authorization(&headers),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+        assert!(headers.contains(&("x-amz-date", "20150830T123600Z".to_string())));
+    }
+
+    #[test]
+    fn test_session_token_is_signed() {
+        let credentials = example_credentials(Some("session"));
+        let signer = Signer {
+            credentials: &credentials,
+            region: "us-east-1",
+            service: "secretsmanager",
+        };
+        let url = Url::parse("http://localhost:4566").unwrap();
+        let headers = signer.sign(
+            &SignableRequest {
+                method: "POST",
+                url: &url,
+                headers: &[("X-Amz-Target", "secretsmanager.GetSecretValue")],
+                body: b"{}",
+            },
+            example_time(),
+        );
+
+        assert!(headers.contains(&("x-amz-security-token", "session".to_string())));
+        assert!(
+            authorization(&headers)
+                .contains("SignedHeaders=host;x-amz-date;x-amz-security-token;x-amz-target,")
+        );
+    }
+
+    #[test]
+    fn test_host_includes_non_default_port() {
+        assert_eq!(
+            host(&Url::parse("http://localhost:4566/").unwrap()),
+            "localhost:4566"
+        );
+        assert_eq!(
+            host(&Url::parse("https://ssm.us-east-1.amazonaws.com:443/").unwrap()),
+            "ssm.us-east-1.amazonaws.com"
+        );
+    }
+
+    #[test]
+    fn test_canonical_query_is_sorted_and_encoded() {
+        let url = Url::parse("https://example.com/?b=2&a=hello%20world&a=1").unwrap();
+        assert_eq!(canonical_query(&url), "a=1&a=hello%20world&b=2");
+    }
+}
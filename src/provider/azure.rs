@@ -0,0 +1,324 @@
+//! Azure Key Vault provider implementation.
+//!
+//! Uses the Key Vault REST API to fetch secrets, and the Microsoft
+//! identity platform's OAuth 2.0 client credentials flow for
+//! authentication.
+//!
+//! The access token is lazily refreshed when it expires
+//! and it will gracefully handle rotating authentication when access is denied.
+
+use super::{
+    ConcurrencyLimit, ProviderError, SecretsProvider, ServerUrl,
+    auth::{ExpiringToken, SecretView, TokenAuthenticator, TokenExchange},
+    config::azure::AzureConfig,
+    references::{AkvReference, Extract, HasReference, SecretReference},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use reqwest::{Client, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::warn;
+use url::Url;
+use uuid::Uuid;
+
+/// Key Vault data plane API version.
+const API_VERSION: &str = "7.4";
+
+pub struct AzureProvider {
+    client: Client,
+    vaults: VaultEndpoints,
+    max_concurrent: ConcurrencyLimit,
+    auth: TokenAuthenticator<ClientCredentialsLogin>,
+}
+
+impl AzureProvider {
+    pub async fn new(config: AzureConfig) -> Result<Self, ProviderError> {
+        let client_secret = config.azure_client_secret.resolve().await?;
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        let auth = TokenAuthenticator::try_new(ClientCredentialsLogin {
+            client: client.clone(),
+            token_url: config.azure_authority_url.endpoint([
+                config.azure_tenant_id.as_str(),
+                "oauth2",
+                "v2.0",
+                "token",
+            ]),
+            client_id: config.azure_client_id,
+            client_secret,
+            scope: format!("https://{}/.default", config.azure_vault_domain),
+        })
+        .await?;
+
+        Ok(Self {
+            client,
+            vaults: VaultEndpoints {
+                domain: config.azure_vault_domain,
+                url: config.azure_vault_url,
+            },
+            max_concurrent: config.azure_max_concurrent,
+            auth,
+        })
+    }
+
+    async fn fetch(
+        &self,
+        reference: &AkvReference,
+        token: &SecretString,
+    ) -> Result<SecretString, ProviderError> {
+        let url = self.vaults.secret_url(reference)?;
+
+        let resp = self
+            .client
+            .get(url)
+            .query(&[("api-version", API_VERSION)])
+            .bearer_auth(token.expose_secret())
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        match resp.status() {
+            s if s.is_success() => {
+                let bundle: SecretBundle = resp
+                    .json()
+                    .await
+                    .map_err(|e| ProviderError::Network(Box::new(e)))?;
+                Ok(bundle.value)
+            }
+            StatusCode::NOT_FOUND => Err(ProviderError::NotFound(reference.to_string())),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
+                format!("Access denied for {}", reference),
+            )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(resp.headers())),
+            status @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => Err(ProviderError::Unavailable(format!(
+                "Azure Key Vault returned {}",
+                status
+            ))),
+            status => {
+                let txt = resp.text().await.unwrap_or_default();
+                Err(ProviderError::Other(format!(
+                    "Azure Key Vault error {}: {}",
+                    status, txt
+                )))
+            }
+        }
+    }
+
+    /// Fetches a secret, retrying once with a fresh token if access was denied.
+    async fn fetch_with_retry(
+        &self,
+        reference: &AkvReference,
+    ) -> Result<SecretString, ProviderError> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let token = self.auth.get_token().await?;
+
+            match self.fetch(reference, &token).await {
+                Ok(value) => return Ok(value),
+                // Token may need to be refreshed. Try invalidating the token
+                // to trigger a rotation and try again
+                Err(ProviderError::Unauthorized(_)) if attempt < 2 => {
+                    warn!(
+                        "Got Unauthorized for {}. Invalidating token and retrying...",
+                        reference
+                    );
+                    self.auth.invalidate(&token).await;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl HasReference for AzureProvider {
+    type Reference = AkvReference;
+}
+
+#[async_trait]
+impl SecretsProvider for AzureProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let refs: HashSet<&AkvReference> = references
+            .iter()
+            .filter_map(AkvReference::extract)
+            .collect();
+
+        if refs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let results = stream::iter(refs.into_iter().cloned())
+            .map(|r| async move {
+                match self.fetch_with_retry(&r).await {
+                    Ok(val) => Ok(Some((SecretReference::AzureKeyVault(r), val))),
+                    Err(ProviderError::NotFound(_)) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+            .buffer_unordered(self.max_concurrent.into_inner())
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut map = HashMap::new();
+        for res in results {
+            if let Some((k, v)) = res? {
+                map.insert(k, v);
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Resolves the base URL of each vault.
+struct VaultEndpoints {
+    /// DNS suffix appended to the vault name, e.g. `vault.azure.net`.
+    domain: String,
+    /// Fixed URL used for every vault instead of its own hostname.
+    url: Option<ServerUrl>,
+}
+
+impl VaultEndpoints {
+    /// `{vault}/secrets/{name}[/{version}]`
+    fn secret_url(&self, reference: &AkvReference) -> Result<Url, ProviderError> {
+        let vault = match &self.url {
+            Some(url) => url.clone(),
+            None => ServerUrl::from_str(&format!("https://{}.{}", reference.vault(), self.domain))
+                .map_err(|e| ProviderError::InvalidConfig(e.to_string()))?,
+        };
+        Ok(vault.endpoint(
+            ["secrets", reference.secret()]
+                .into_iter()
+                .chain(reference.version()),
+        ))
+    }
+}
+
+/// Client credentials exchange for a Microsoft Entra service principal.
+struct ClientCredentialsLogin {
+    client: Client,
+    token_url: Url,
+    client_id: Uuid,
+    client_secret: SecretString,
+    scope: String,
+}
+
+#[async_trait]
+impl TokenExchange for ClientCredentialsLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let payload = TokenRequest {
+            grant_type: "client_credentials",
+            client_id: &self.client_id,
+            client_secret: SecretView(&self.client_secret),
+            scope: &self.scope,
+        };
+
+        let resp = self
+            .client
+            .post(self.token_url.clone())
+            .form(&payload)
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::Unauthorized(format!(
+                "Azure login failed: {} - {}",
+                status, text
+            )));
+        }
+
+        let token: TokenResponse = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        Ok(ExpiringToken::new(token.access_token, token.expires_in))
+    }
+}
+
+#[derive(Serialize)]
+struct TokenRequest<'a> {
+    grant_type: &'static str,
+    client_id: &'a Uuid,
+    client_secret: SecretView<'a>,
+    scope: &'a str,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: SecretString,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+struct SecretBundle {
+    value: SecretString,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(raw: &str) -> AkvReference {
+        AkvReference::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn test_secret_url_uses_vault_hostname() {
+        let vaults = VaultEndpoints {
+            domain: "vault.azure.net".to_string(),
+            url: None,
+        };
+        assert_eq!(
+            vaults
+                .secret_url(&reference("akv://my-vault/db-password"))
+                .unwrap()
+                .as_str(),
+            "https://my-vault.vault.azure.net/secrets/db-password"
+        );
+
+        let vaults = VaultEndpoints {
+            domain: "vault.azure.cn".to_string(),
+            url: None,
+        };
+        assert_eq!(
+            vaults
+                .secret_url(&reference("akv://my-vault/db-password/abc123"))
+                .unwrap()
+                .as_str(),
+            "https://my-vault.vault.azure.cn/secrets/db-password/abc123"
+        );
+    }
+
+    #[test]
+    fn test_secret_url_override() {
+        let vaults = VaultEndpoints {
+            domain: "vault.azure.net".to_string(),
+            url: Some(ServerUrl::from_str("http://localhost:8443/").unwrap()),
+        };
+        assert_eq!(
+            vaults
+                .secret_url(&reference("akv://my-vault/db-password"))
+                .unwrap()
+                .as_str(),
+            "http://localhost:8443/secrets/db-password"
+        );
+    }
+}
@@ -1,107 +1,127 @@
 //! OpenBao / HashiCorp Vault provider implementation.
 //!
-//! Uses the KV v2 secrets engine to fetch secrets, and AppRole auth
-//! for authentication.
+//! Uses the KV secrets engine (v1 or v2, configured or detected per mount)
+//! to fetch secrets, and logs in with the configured auth method (AppRole
+//! by default). Dynamic secrets (`bao+dyn://`) are read from any
+//! secrets engine path which issues leased secrets, such as database
+//! credentials, and their leases are renewed until shutdown. Certificates
+//! (`bao+pki://`) are issued by a PKI secrets engine, and re-issued before
+//! they expire.
 //!
 //! The authentication token is lazily refreshed when it expires
 //! and it will gracefully handle rotating authentication when access is denied.
 
 use super::{
-    ConcurrencyLimit, ProviderError, SecretsProvider, ServerUrl,
-    auth::{ExpiringToken, SecretView, TokenAuthenticator, TokenExchange},
-    config::bao::{BaoConfig, BaoNamespace},
+    ConcurrencyLimit, ProviderError, ReferenceParser, SecretsProvider, ServerUrl,
+    auth::{TokenAuthenticator, TokenExchange},
+    config::bao::{BaoConfig, BaoKvMount, BaoNamespace, KvVersion},
     references::{
-        BaoMount, BaoReference, BaoSecretLocation, Extract, HasReference, SecretReference,
+        BaoField, BaoMount, BaoPath, BaoPkiRequest, BaoReference, BaoSecretLocation, Extract,
+        SecretReference, parse_bao_reference,
     },
+    types::JsonField,
 };
 use async_trait::async_trait;
 use futures::{StreamExt, stream};
-use reqwest::{Client, StatusCode};
+use kv::KvMounts;
+use leases::{Issued, Lease, LeaseApi, LeaseTerms, Leases};
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
 use secrecy::{ExposeSecret, SecretString};
-use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
-use std::time::Duration;
-use tracing::warn;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+mod kv;
+mod leases;
+mod login;
 
 pub struct BaoProvider {
-    client: Client,
-    config: ProviderConfig,
-    auth: TokenAuthenticator<AppRoleLogin>,
+    api: Arc<BaoApi>,
+    leases: Leases<BaoApi>,
 }
 
 impl BaoProvider {
     pub async fn new(config: BaoConfig) -> Result<Self, ProviderError> {
-        let secret_id = config.bao_secret_id.resolve().await?;
-        let auth_config = AuthConfig {
-            url: config.bao_url.clone(),
-            namespace: config.bao_namespace.clone(),
-            auth_mount: config.bao_auth_mount.clone(),
-            role_id: config.bao_role_id.clone(),
-            secret_id,
-        };
-
-        let provider_config = ProviderConfig::from(config);
-
-        let client = Client::builder()
-            .timeout(Duration::from_secs(10))
+        let builder = Client::builder().timeout(Duration::from_secs(10));
+        let client = login::configure_client(&config, builder)
+            .await?
             .build()
             .map_err(|e| ProviderError::Other(e.to_string()))?;
 
-        let auth = TokenAuthenticator::try_new(AppRoleLogin {
-            client: client.clone(),
-            config: auth_config,
-        })
-        .await?;
+        let auth = TokenAuthenticator::try_new(login::exchange(&config, &client).await?).await?;
 
-        Ok(Self {
+        let renew_at = config.bao_renew_at;
+        let mounts = KvMounts::new(config.bao_kv_mount.clone());
+        let provider_config = ProviderConfig::from(config);
+
+        let api = Arc::new(BaoApi {
             client,
             config: provider_config,
             auth,
+            mounts,
+        });
+        Ok(Self {
+            leases: Leases::new(api.clone(), renew_at),
+            api,
         })
     }
+}
 
-    /// Reads a KV v2 secret's full data map for a given location.
-    async fn fetch_group(
+/// Authenticated access to the OpenBao / Vault HTTP API.
+struct BaoApi {
+    client: Client,
+    config: ProviderConfig,
+    auth: TokenAuthenticator<Box<dyn TokenExchange>>,
+    mounts: KvMounts,
+}
+
+impl BaoApi {
+    /// Builds an authenticated request against the API.
+    fn request<'a>(
         &self,
-        location: &BaoSecretLocation,
+        method: Method,
+        segments: impl IntoIterator<Item = &'a str>,
         token: &SecretString,
-    ) -> Result<HashMap<String, KvV2Value>, ProviderError> {
-        let url = self.config.url.endpoint(
-            ["v1", location.mount.as_str(), "data"]
-                .into_iter()
-                .chain(location.path.segments()),
-        );
-
+    ) -> RequestBuilder {
+        let url = self.config.url.endpoint(["v1"].into_iter().chain(segments));
         let mut req = self
             .client
-            .get(url)
+            .request(method, url)
             .header("X-Vault-Token", token.expose_secret());
         if let Some(ns) = &self.config.namespace {
             req = req.header("X-Vault-Namespace", ns.as_str());
         }
+        req
+    }
 
+    /// Sends a request, mapping failure statuses to provider errors which
+    /// name the requested resource.
+    async fn send(
+        &self,
+        req: RequestBuilder,
+        resource: &(dyn fmt::Display + Sync),
+    ) -> Result<Response, ProviderError> {
         let resp = req
             .send()
             .await
             .map_err(|e| ProviderError::Network(Box::new(e)))?;
 
         match resp.status() {
-            StatusCode::OK => {
-                let wrapper: KvV2Response = resp
-                    .json()
-                    .await
-                    .map_err(|e| ProviderError::Network(Box::new(e)))?;
-                wrapper
-                    .data
-                    .data
-                    .ok_or_else(|| ProviderError::NotFound(location.to_string()))
-            }
-            StatusCode::NOT_FOUND => Err(ProviderError::NotFound(location.to_string())),
+            status if status.is_success() => Ok(resp),
+            StatusCode::NOT_FOUND => Err(ProviderError::NotFound(resource.to_string())),
             StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
-                format!("Access denied for {}", location),
+                format!("Access denied for {}", resource),
             )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(resp.headers())),
+            status @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => Err(ProviderError::Unavailable(format!(
+                "OpenBao returned {}",
+                status
+            ))),
             status => {
                 let txt = resp.text().await.unwrap_or_default();
                 Err(ProviderError::Other(format!(
@@ -112,24 +132,29 @@ impl BaoProvider {
         }
     }
 
-    /// Fetches a secret's data map, retrying once with a fresh token if access was denied.
-    async fn fetch_group_with_retry(
+    /// Runs an authenticated call, retrying once with a fresh token if access was denied.
+    async fn with_token<T, F, Fut>(
         &self,
-        location: &BaoSecretLocation,
-    ) -> Result<HashMap<String, KvV2Value>, ProviderError> {
+        resource: &(dyn fmt::Display + Sync),
+        call: F,
+    ) -> Result<T, ProviderError>
+    where
+        F: Fn(SecretString) -> Fut,
+        Fut: Future<Output = Result<T, ProviderError>>,
+    {
         let mut attempt = 0;
         loop {
             attempt += 1;
             let token = self.auth.get_token().await?;
 
-            match self.fetch_group(location, &token).await {
+            match call(token.clone()).await {
                 Ok(data) => return Ok(data),
                 // Token may need to be refreshed. Try invalidating the token
                 // to trigger a rotation and try again
                 Err(ProviderError::Unauthorized(_)) if attempt < 2 => {
                     warn!(
                         "Got Unauthorized for {}. Invalidating token and retrying...",
-                        location
+                        resource
                     );
                     self.auth.invalidate(&token).await;
                     continue;
@@ -138,10 +163,258 @@ impl BaoProvider {
             }
         }
     }
+
+    /// Reads a KV secret's full data map for a given location.
+    async fn fetch_group(
+        &self,
+        location: &BaoSecretLocation,
+    ) -> Result<HashMap<String, JsonField>, ProviderError> {
+        let kv = &self.kv_mount(&location.path).await?;
+        let secret = &location.path.strip_mount(&kv.mount).ok_or_else(|| {
+            ProviderError::InvalidConfig(format!(
+                "{} does not name a secret within the KV mount '{}'",
+                location, kv.mount
+            ))
+        })?;
+        if kv.version == KvVersion::V1 && location.version.is_some() {
+            return Err(ProviderError::InvalidConfig(format!(
+                "{} pins a version, but '{}' is a KV v1 mount which does not keep versions",
+                location, kv.mount
+            )));
+        }
+
+        self.with_token(location, |token| async move {
+            let data = match kv.version {
+                KvVersion::V1 => {
+                    let req = self.request(
+                        Method::GET,
+                        kv.mount.segments().chain(secret.segments()),
+                        &token,
+                    );
+                    let wrapper: KvV1Response = self
+                        .send(req, location)
+                        .await?
+                        .json()
+                        .await
+                        .map_err(|e| ProviderError::Network(Box::new(e)))?;
+                    wrapper.data
+                }
+                KvVersion::V2 => {
+                    let mut req = self.request(
+                        Method::GET,
+                        kv.mount.segments().chain(["data"]).chain(secret.segments()),
+                        &token,
+                    );
+                    if let Some(version) = location.version {
+                        req = req.query(&[("version", version.get())]);
+                    }
+                    let wrapper: KvV2Response = self
+                        .send(req, location)
+                        .await?
+                        .json()
+                        .await
+                        .map_err(|e| ProviderError::Network(Box::new(e)))?;
+                    wrapper.data.data
+                }
+            };
+            data.ok_or_else(|| ProviderError::NotFound(location.to_string()))
+        })
+        .await
+    }
+
+    /// Resolves the KV mount containing a secret's path. Mounts which are
+    /// neither configured nor already known are detected from the server.
+    async fn kv_mount(&self, path: &BaoPath) -> Result<BaoKvMount, ProviderError> {
+        if let Some(kv) = self.mounts.find(path) {
+            return Ok(kv);
+        }
+
+        let kv = match self.detect_mount(path).await {
+            Ok(kv) => {
+                debug!(
+                    "detected KV v{} mount '{}' for {}",
+                    kv.version, kv.mount, path
+                );
+                kv
+            }
+            // Detection requires access to the mounts API, which a
+            // narrow policy may not grant. Assume the historic layout.
+            Err(e @ (ProviderError::NotFound(_) | ProviderError::Unauthorized(_))) => {
+                let first = path.segments().next().unwrap_or_default();
+                let mount = BaoMount::try_from(first.to_string())
+                    .map_err(|e| ProviderError::InvalidConfig(e.to_string()))?;
+                warn!(
+                    "Could not detect the KV mount of {} ({}). Assuming a KV v2 mount at '{}', use --bao-kv-mount to configure it",
+                    path, e, mount
+                );
+                BaoKvMount {
+                    mount,
+                    version: KvVersion::V2,
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        self.mounts.insert(kv.clone());
+        Ok(kv)
+    }
+
+    /// Asks the server which mount a path belongs to, and its KV version.
+    ///
+    /// Denied access is expected from narrow policies, so it is not
+    /// retried with a fresh token.
+    async fn detect_mount(&self, path: &BaoPath) -> Result<BaoKvMount, ProviderError> {
+        let token = self.auth.get_token().await?;
+        let req = self.request(
+            Method::GET,
+            ["sys", "internal", "ui", "mounts"]
+                .into_iter()
+                .chain(path.segments()),
+            &token,
+        );
+        let resp: MountResponse = self
+            .send(req, path)
+            .await?
+            .json()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+        let mount = BaoMount::try_from(resp.data.path)
+            .map_err(|e| ProviderError::Other(format!("OpenBao returned {}", e)))?;
+        let version = match resp.data.options.and_then(|o| o.version).as_deref() {
+            Some("2") => KvVersion::V2,
+            _ => KvVersion::V1,
+        };
+        Ok(BaoKvMount { mount, version })
+    }
+}
+
+#[async_trait]
+impl LeaseApi for BaoApi {
+    async fn issue(&self, secret: &Issued) -> Result<Lease, ProviderError> {
+        match secret {
+            Issued::Dynamic(path) => self.read_dynamic(path).await,
+            Issued::Certificate(request) => self.issue_certificate(request).await,
+        }
+    }
+
+    async fn renew(
+        &self,
+        lease_id: &str,
+        increment: Duration,
+    ) -> Result<LeaseTerms, ProviderError> {
+        self.with_token(&lease_id, |token| async move {
+            let req = self
+                .request(Method::PUT, ["sys", "leases", "renew"], &token)
+                .json(&RenewParams {
+                    lease_id,
+                    increment: increment.as_secs(),
+                });
+            let resp: RenewResponse = self
+                .send(req, &lease_id)
+                .await?
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e)))?;
+            Ok(LeaseTerms {
+                duration: Duration::from_secs(resp.lease_duration),
+                renewable: resp.renewable,
+            })
+        })
+        .await
+    }
+
+    async fn revoke(&self, lease_id: &str) -> Result<(), ProviderError> {
+        self.with_token(&lease_id, |token| async move {
+            let req = self
+                .request(Method::PUT, ["sys", "leases", "revoke"], &token)
+                .json(&RevokeParams { lease_id });
+            self.send(req, &lease_id).await.map(|_| ())
+        })
+        .await
+    }
+}
+
+impl BaoApi {
+    /// Reads a dynamic secret, which issues new credentials under a new lease.
+    async fn read_dynamic(&self, path: &BaoPath) -> Result<Lease, ProviderError> {
+        self.with_token(path, |token| async move {
+            let req = self.request(Method::GET, path.segments(), &token);
+            let resp: LeasedResponse = self
+                .send(req, path)
+                .await?
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e)))?;
+            Ok(Lease {
+                id: resp.lease_id,
+                terms: LeaseTerms {
+                    duration: Duration::from_secs(resp.lease_duration),
+                    renewable: resp.renewable,
+                },
+                data: resp
+                    .data
+                    .ok_or_else(|| ProviderError::NotFound(path.to_string()))?,
+            })
+        })
+        .await
+    }
+
+    /// Issues a certificate from a PKI role. Certificates are not leased,
+    /// so they are tracked until their expiry instead.
+    async fn issue_certificate(&self, request: &BaoPkiRequest) -> Result<Lease, ProviderError> {
+        self.with_token(request, |token| async move {
+            let req = self
+                .request(Method::POST, request.path.segments(), &token)
+                .json(&request.params);
+            let resp: PkiIssueResponse = self
+                .send(req, request)
+                .await?
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+            let cert = resp.data;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let lifetime = cert.expiration.saturating_sub(now).max(1);
+            let ca_chain = if cert.ca_chain.is_empty() {
+                cert.issuing_ca.clone()
+            } else {
+                SecretString::from(cert.ca_chain.join("\n"))
+            };
+            Ok(Lease {
+                id: resp.lease_id,
+                terms: LeaseTerms {
+                    duration: Duration::from_secs(lifetime),
+                    renewable: false,
+                },
+                data: HashMap::from([
+                    (
+                        "certificate".to_string(),
+                        JsonField::Scalar(cert.certificate),
+                    ),
+                    (
+                        "private_key".to_string(),
+                        JsonField::Scalar(cert.private_key),
+                    ),
+                    ("issuing_ca".to_string(), JsonField::Scalar(cert.issuing_ca)),
+                    ("ca_chain".to_string(), JsonField::Scalar(ca_chain)),
+                    (
+                        "serial_number".to_string(),
+                        JsonField::Scalar(cert.serial_number),
+                    ),
+                ]),
+            })
+        })
+        .await
+    }
 }
 
-impl HasReference for BaoProvider {
-    type Reference = BaoReference;
+impl ReferenceParser for BaoProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        parse_bao_reference(raw)
+    }
 }
 
 #[async_trait]
@@ -157,20 +430,32 @@ impl SecretsProvider for BaoProvider {
             groups.entry(&r.location).or_default().push(r);
         }
 
-        if groups.is_empty() {
+        // Issued secrets are grouped by what issues them, so that all of
+        // their fields come from the same issuance.
+        let mut issued: HashMap<Issued, Vec<(&SecretReference, &BaoField)>> = HashMap::new();
+        for reference in references {
+            let (secret, field) = match reference {
+                SecretReference::BaoDynamic(r) => (Issued::Dynamic(r.path.clone()), &r.field),
+                SecretReference::BaoPki(r) => (Issued::Certificate(r.request.clone()), &r.field),
+                _ => continue,
+            };
+            issued.entry(secret).or_default().push((reference, field));
+        }
+
+        if groups.is_empty() && issued.is_empty() {
             return Ok(HashMap::new());
         }
 
         let fetches: Vec<_> = groups
             .into_iter()
             .map(|(location, group_refs)| async move {
-                let data = self.fetch_group_with_retry(location).await;
+                let data = self.api.fetch_group(location).await;
                 (group_refs, data)
             })
             .collect();
 
         let results = stream::iter(fetches)
-            .buffer_unordered(self.config.max_concurrent.into_inner())
+            .buffer_unordered(self.api.config.max_concurrent.into_inner())
             .collect::<Vec<_>>()
             .await;
 
@@ -179,21 +464,7 @@ impl SecretsProvider for BaoProvider {
             match data {
                 Ok(fields) => {
                     for r in group_refs {
-                        match fields.get(r.field.as_str()) {
-                            Some(KvV2Value::Scalar(secret)) => {
-                                map.insert(SecretReference::Bao(r.clone()), secret.clone());
-                            }
-                            Some(KvV2Value::Unsupported) => {
-                                warn!(
-                                    "Field '{}' in {} is not a scalar value; skipping",
-                                    r.field, r.location
-                                );
-                            }
-                            None => {
-                                // Field not present in the secret's data map.
-                                // Leave unresolved, per fetch_map contract.
-                            }
-                        }
+                        insert_field(&mut map, r, &fields, &r.field, &r.location);
                     }
                 }
                 // Whole secret not found: leave all of its fields unresolved.
@@ -202,67 +473,59 @@ impl SecretsProvider for BaoProvider {
             }
         }
 
-        Ok(map)
-    }
-}
-
-/// AppRole credential exchange for OpenBao / Vault.
-struct AppRoleLogin {
-    client: Client,
-    config: AuthConfig,
-}
-
-#[async_trait]
-impl TokenExchange for AppRoleLogin {
-    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
-        let url =
-            self.config
-                .url
-                .endpoint(["v1", "auth", self.config.auth_mount.as_str(), "login"]);
-
-        let payload = LoginParams {
-            role_id: &self.config.role_id,
-            secret_id: SecretView(&self.config.secret_id),
-        };
-
-        let mut req = self.client.post(url).json(&payload);
-        if let Some(ns) = &self.config.namespace {
-            req = req.header("X-Vault-Namespace", ns.as_str());
+        for (secret, group_refs) in issued {
+            match self.leases.data(&secret).await {
+                Ok(fields) => {
+                    for (r, field) in group_refs {
+                        insert_field(&mut map, r, &fields, field, &secret);
+                    }
+                }
+                Err(ProviderError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            }
         }
 
-        let resp = req
-            .send()
-            .await
-            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+        Ok(map)
+    }
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(ProviderError::Unauthorized(format!(
-                "AppRole login failed: {} - {}",
-                status, text
-            )));
-        }
+    async fn expired(&self) {
+        self.leases.expired().await
+    }
 
-        let login_resp: LoginResponse = resp
-            .json()
-            .await
-            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+    async fn holds_leases(&self) -> bool {
+        !self.leases.is_empty().await
+    }
 
-        Ok(ExpiringToken::new(
-            login_resp.auth.client_token,
-            login_resp.auth.lease_duration,
-        ))
+    async fn shutdown(&self) {
+        self.leases.revoke_all().await
     }
 }
 
-#[derive(Debug, Clone)]
-struct AuthConfig {
-    url: ServerUrl,
-    namespace: Option<BaoNamespace>,
-    auth_mount: BaoMount,
-    role_id: String,
-    secret_id: SecretString,
+/// Resolves a referenced field from a secret's data map.
+fn insert_field<R>(
+    map: &mut HashMap<SecretReference, SecretString>,
+    reference: &R,
+    fields: &HashMap<String, JsonField>,
+    field: &BaoField,
+    secret: &(dyn fmt::Display + Sync),
+) where
+    R: Clone + Into<SecretReference>,
+{
+    match fields.get(field.as_str()) {
+        Some(JsonField::Scalar(value)) => {
+            map.insert(reference.clone().into(), value.clone());
+        }
+        Some(JsonField::Unsupported) => {
+            warn!(
+                "Field '{}' in {} is not a scalar value; skipping",
+                field, secret
+            );
+        }
+        None => {
+            // Field not present in the secret's data map.
+            // Leave unresolved, per fetch_map contract.
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -282,21 +545,26 @@ impl From<BaoConfig> for ProviderConfig {
     }
 }
 
-#[derive(Serialize)]
-struct LoginParams<'a> {
-    role_id: &'a str,
-    secret_id: SecretView<'a>,
+#[derive(Deserialize)]
+struct MountResponse {
+    data: MountData,
 }
 
 #[derive(Deserialize)]
-struct LoginResponse {
-    auth: LoginAuth,
+struct MountData {
+    path: String,
+    #[serde(default)]
+    options: Option<MountOptions>,
 }
 
 #[derive(Deserialize)]
-struct LoginAuth {
-    client_token: SecretString,
-    lease_duration: u64,
+struct MountOptions {
+    version: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KvV1Response {
+    data: Option<HashMap<String, JsonField>>,
 }
 
 #[derive(Deserialize)]
@@ -306,103 +574,312 @@ struct KvV2Response {
 
 #[derive(Deserialize)]
 struct KvV2Data {
-    data: Option<HashMap<String, KvV2Value>>,
+    data: Option<HashMap<String, JsonField>>,
 }
 
-/// A single field value in a KV v2 secret's data map.
-///
-/// Scalars are captured as secrets at deserialization time so plaintext
-/// never sits in a non-zeroizing type. Numbers and bools resolve to their
-/// string form. Nulls, arrays, and objects cannot be injected as a value.
-enum KvV2Value {
-    Scalar(SecretString),
-    Unsupported,
+#[derive(Deserialize)]
+struct LeasedResponse {
+    lease_id: String,
+    lease_duration: u64,
+    renewable: bool,
+    data: Option<HashMap<String, JsonField>>,
 }
 
-impl<'de> Deserialize<'de> for KvV2Value {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        struct KvV2ValueVisitor;
+#[derive(Serialize)]
+struct RenewParams<'a> {
+    lease_id: &'a str,
+    increment: u64,
+}
 
-        impl<'de> Visitor<'de> for KvV2ValueVisitor {
-            type Value = KvV2Value;
+#[derive(Deserialize)]
+struct RenewResponse {
+    lease_duration: u64,
+    renewable: bool,
+}
 
-            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("a KV v2 field value")
-            }
+#[derive(Serialize)]
+struct RevokeParams<'a> {
+    lease_id: &'a str,
+}
 
-            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.into())))
-            }
+#[derive(Deserialize)]
+struct PkiIssueResponse {
+    #[serde(default)]
+    lease_id: String,
+    data: PkiCertificate,
+}
 
-            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.into())))
-            }
+#[derive(Deserialize)]
+struct PkiCertificate {
+    certificate: SecretString,
+    private_key: SecretString,
+    issuing_ca: SecretString,
+    #[serde(default)]
+    ca_chain: Vec<String>,
+    serial_number: SecretString,
+    /// Unix timestamp of the certificate's expiry.
+    expiration: u64,
+}
 
-            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.to_string().into())))
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{
+        AuthToken,
+        config::bao::{BaoAuthMethod, SecretId},
+        references::ReferenceSyntax,
+        test_server::TestServer,
+    };
+    use std::str::FromStr;
+
+    const LOOKUP: &str = r#"{"data":{"ttl":0}}"#;
+
+    fn secret(value: &str) -> Option<AuthToken> {
+        Some(AuthToken::new(SecretString::from(value.to_string())))
+    }
 
-            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.to_string().into())))
-            }
+    fn config(url: &str, kv_mounts: &[&str]) -> BaoConfig {
+        BaoConfig {
+            bao_url: ServerUrl::from_str(url).unwrap(),
+            bao_namespace: None,
+            bao_auth_method: BaoAuthMethod::Token,
+            bao_auth_mount: None,
+            bao_role_id: None,
+            bao_secret_id: None,
+            bao_secret_id_wrapped: false,
+            bao_token: secret("s.token"),
+            bao_auth_role: None,
+            bao_jwt: None,
+            bao_client_cert: None,
+            bao_client_key: None,
+            bao_username: None,
+            bao_password: None,
+            bao_kv_mount: kv_mounts
+                .iter()
+                .map(|m| BaoKvMount::from_str(m).unwrap())
+                .collect(),
+            bao_max_concurrent: Default::default(),
+            bao_renew_at: Default::default(),
+        }
+    }
 
-            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.to_string().into())))
-            }
+    async fn provider(url: &str, kv_mounts: &[&str]) -> BaoProvider {
+        BaoProvider::new(config(url, kv_mounts)).await.unwrap()
+    }
 
-            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Scalar(SecretString::new(v.to_string().into())))
-            }
+    fn reference(raw: &str) -> SecretReference {
+        BaoReference::try_parse(raw).unwrap().into()
+    }
 
-            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
-                Ok(KvV2Value::Unsupported)
-            }
+    fn resolved(map: &HashMap<SecretReference, SecretString>, raw: &str) -> String {
+        map[&reference(raw)].expose_secret().to_string()
+    }
 
-            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
-                while seq.next_element::<IgnoredAny>()?.is_some() {}
-                Ok(KvV2Value::Unsupported)
+    #[tokio::test]
+    async fn test_kv_mounts_are_detected() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v1/auth/token/lookup-self" => (200, LOOKUP.into()),
+            p if p.starts_with("/v1/sys/internal/ui/mounts/teams/") => (
+                200,
+                r#"{"data":{"path":"teams/payments/kv/","type":"kv","options":{"version":"1"}}}"#
+                    .into(),
+            ),
+            p if p.starts_with("/v1/sys/internal/ui/mounts/secret/") => (
+                200,
+                r#"{"data":{"path":"secret/","type":"kv","options":{"version":"2"}}}"#.into(),
+            ),
+            "/v1/teams/payments/kv/app" => (200, r#"{"data":{"password":"v1"}}"#.into()),
+            "/v1/secret/data/app" => (200, r#"{"data":{"data":{"password":"latest"}}}"#.into()),
+            "/v1/secret/data/app?version=3" => {
+                (200, r#"{"data":{"data":{"password":"pinned"}}}"#.into())
             }
+            _ => (404, r#"{"errors":[]}"#.into()),
+        });
+        let provider = provider(server.url(), &[]).await;
+
+        let refs = [
+            "bao://teams/payments/kv/app/password",
+            "bao://secret/app/password",
+            "bao://secret/app/password?version=3",
+        ];
+        let map = provider.fetch_map(&[reference(refs[0])]).await.unwrap();
+        assert_eq!(resolved(&map, refs[0]), "v1");
+
+        let map = provider.fetch_map(&refs.map(reference)).await.unwrap();
+        assert_eq!(resolved(&map, refs[0]), "v1");
+        assert_eq!(resolved(&map, refs[1]), "latest");
+        assert_eq!(resolved(&map, refs[2]), "pinned");
+
+        // Each mount is only detected once.
+        let detections = server
+            .requests()
+            .iter()
+            .filter(|r| r.path.starts_with("/v1/sys/internal/ui/mounts/"))
+            .count();
+        assert!(detections <= 3, "{detections} detections");
+        let again = provider.fetch_map(&refs.map(reference)).await.unwrap();
+        assert_eq!(again.len(), 3);
+        let after = server
+            .requests()
+            .iter()
+            .filter(|r| r.path.starts_with("/v1/sys/internal/ui/mounts/"))
+            .count();
+        assert_eq!(after, detections);
+    }
 
-            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
-                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
-                Ok(KvV2Value::Unsupported)
+    #[tokio::test]
+    async fn test_configured_and_fallback_mounts() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v1/auth/token/lookup-self" => (200, LOOKUP.into()),
+            p if p.starts_with("/v1/sys/internal/ui/mounts/") => {
+                (403, r#"{"errors":["permission denied"]}"#.into())
             }
-        }
+            "/v1/legacy/app" => (200, r#"{"data":{"password":"v1"}}"#.into()),
+            "/v1/secret/data/app" => (200, r#"{"data":{"data":{"password":"v2"}}}"#.into()),
+            _ => (404, r#"{"errors":[]}"#.into()),
+        });
+        let provider = provider(server.url(), &["legacy=1"]).await;
+
+        let refs = ["bao://legacy/app/password", "bao://secret/app/password"];
+        let map = provider.fetch_map(&refs.map(reference)).await.unwrap();
+        assert_eq!(resolved(&map, refs[0]), "v1");
+        assert_eq!(resolved(&map, refs[1]), "v2");
+        assert!(
+            !server
+                .requests()
+                .iter()
+                .any(|r| r.path.starts_with("/v1/sys/internal/ui/mounts/legacy")),
+            "configured mounts are not detected"
+        );
 
-        deserializer.deserialize_any(KvV2ValueVisitor)
+        // Versions only exist on KV v2 mounts.
+        let err = provider
+            .fetch_map(&[reference("bao://legacy/app/password?version=2")])
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(m) if m.contains("KV v1")));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_signature_follows_client_certificate() {
+        use crate::path::AbsolutePath;
+        use crate::provider::Signature;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("client.crt");
+        let key = dir.path().join("client.key");
+        std::fs::write(&cert, "cert-1").unwrap();
+        std::fs::write(&key, "key-1").unwrap();
+
+        let mut cfg = config("http://127.0.0.1:8200", &[]);
+        cfg.bao_auth_method = BaoAuthMethod::Cert;
+        cfg.bao_token = None;
+        cfg.bao_client_cert = Some(AbsolutePath::new(&cert));
+        cfg.bao_client_key = Some(AuthToken::from_str(&format!("file:{}", key.display())).unwrap());
+        let before = cfg.signature().await.unwrap();
+
+        // A renewed certificate may keep its key.
+        std::fs::write(&cert, "cert-2").unwrap();
+        let renewed = cfg.signature().await.unwrap();
+        assert_ne!(renewed, before);
+
+        std::fs::write(&key, "key-2").unwrap();
+        assert_ne!(cfg.signature().await.unwrap(), renewed);
+    }
 
-    #[test]
-    fn test_kv_scalars_deserialize_as_secrets() {
-        let json = r#"{"password": "hunter2", "port": 5432, "enabled": true, "ratio": 1.5}"#;
-        let fields: HashMap<String, KvV2Value> = serde_json::from_str(json).unwrap();
+    #[tokio::test]
+    async fn test_rate_limit_is_transient() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v1/auth/token/lookup-self" => (200, LOOKUP.into()),
+            _ => (429, r#"{"errors":["rate limit quota exceeded"]}"#.into()),
+        });
+        let provider = provider(server.url(), &["secret=2"]).await;
 
-        let expect_secret = |key: &str| match &fields[key] {
-            KvV2Value::Scalar(s) => s.expose_secret().to_string(),
-            KvV2Value::Unsupported => panic!("field '{key}' should be a scalar"),
-        };
-
-        assert_eq!(expect_secret("password"), "hunter2");
-        assert_eq!(expect_secret("port"), "5432");
-        assert_eq!(expect_secret("enabled"), "true");
-        assert_eq!(expect_secret("ratio"), "1.5");
+        let err = provider
+            .fetch_map(&[reference("bao://secret/app/password")])
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::RateLimit { .. }), "{err}");
+        assert!(err.is_transient());
     }
 
-    #[test]
-    fn test_kv_structured_values_unsupported() {
-        let json = r#"{"nothing": null, "list": [1, 2], "nested": {"a": 1}}"#;
-        let fields: HashMap<String, KvV2Value> = serde_json::from_str(json).unwrap();
+    #[tokio::test]
+    async fn test_rebuild_after_failed_login_reuses_unwrapped_secret_id() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let login_up = Arc::new(AtomicBool::new(false));
+        let up = login_up.clone();
+        let server = TestServer::start(move |req| match req.path.as_str() {
+            "/v1/sys/wrapping/lookup" => (
+                200,
+                r#"{"data":{"creation_path":"auth/approle/role/locket/secret-id"}}"#.into(),
+            ),
+            "/v1/sys/wrapping/unwrap" => (200, r#"{"data":{"secret_id":"unwrapped"}}"#.into()),
+            "/v1/auth/approle/login" if up.load(Ordering::SeqCst) => (
+                200,
+                r#"{"auth":{"client_token":"s.client","lease_duration":3600}}"#.into(),
+            ),
+            _ => (503, r#"{"errors":["sealed"]}"#.into()),
+        });
+        let mut cfg = config(server.url(), &[]);
+        cfg.bao_auth_method = BaoAuthMethod::AppRole;
+        cfg.bao_role_id = Some("role".into());
+        cfg.bao_secret_id = secret("s.wrapping-rebuild").map(SecretId::from);
+        cfg.bao_secret_id_wrapped = true;
+
+        // The login fails after the wrapping token was used up...
+        assert!(BaoProvider::new(cfg.clone()).await.is_err());
+
+        // ...so building the provider again must only retry the login.
+        login_up.store(true, Ordering::SeqCst);
+        BaoProvider::new(cfg).await.unwrap();
+
+        let requests = server.requests();
+        let unwraps = requests
+            .iter()
+            .filter(|r| r.path == "/v1/sys/wrapping/unwrap")
+            .count();
+        assert_eq!(unwraps, 1);
+        let last = requests.last().unwrap();
+        assert_eq!(last.path, "/v1/auth/approle/login");
+        assert_eq!(last.body, r#"{"role_id":"role","secret_id":"unwrapped"}"#);
+    }
 
-        for key in ["nothing", "list", "nested"] {
-            assert!(matches!(fields[key], KvV2Value::Unsupported));
-        }
+    #[tokio::test]
+    async fn test_sealed_primary_fails_over() {
+        use crate::provider::{Provider, ProviderSet, retry::RetryPolicy};
+
+        let sealed = TestServer::start(|_| (503, r#"{"errors":["Vault is sealed"]}"#.into()));
+        let replica = TestServer::start(|req| match req.path.as_str() {
+            "/v1/auth/token/lookup-self" => (200, LOOKUP.into()),
+            "/v1/secret/data/app" => (200, r#"{"data":{"data":{"password":"dr"}}}"#.into()),
+            _ => (404, r#"{"errors":[]}"#.into()),
+        });
+        let mut primary = config(sealed.url(), &["secret=2"]);
+        primary.bao_auth_method = BaoAuthMethod::Userpass;
+        primary.bao_token = None;
+        primary.bao_username = Some("locket".into());
+        primary.bao_password = secret("hunter2");
+
+        let err = BaoProvider::new(primary.clone()).await.err().unwrap();
+        assert!(matches!(err, ProviderError::Unavailable(_)), "{err}");
+
+        let chain = ProviderSet::build_chain(
+            vec![
+                Provider::Bao(primary),
+                Provider::Bao(config(replica.url(), &["secret=2"])),
+            ],
+            RetryPolicy::none(),
+            false,
+        )
+        .await
+        .unwrap();
+        let map = chain
+            .fetch_map(&[reference("bao://secret/app/password")])
+            .await
+            .unwrap();
+        assert_eq!(resolved(&map, "bao://secret/app/password"), "dr");
     }
 }
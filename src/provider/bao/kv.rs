@@ -0,0 +1,85 @@
+//! KV secrets engine mounts, as configured or detected from the server.
+//!
+//! A `bao://` reference names the full path of a secret, and mounts may be
+//! nested, so the mount a secret lives in is looked up by prefix. The most
+//! nested known mount wins, mirroring how the server routes requests.
+
+use crate::provider::{config::bao::BaoKvMount, references::BaoPath};
+use std::sync::RwLock;
+
+pub(super) struct KvMounts {
+    known: RwLock<Vec<BaoKvMount>>,
+}
+
+impl KvMounts {
+    pub fn new(configured: Vec<BaoKvMount>) -> Self {
+        Self {
+            known: RwLock::new(configured),
+        }
+    }
+
+    /// The most nested known mount containing `path`.
+    pub fn find(&self, path: &BaoPath) -> Option<BaoKvMount> {
+        let known = self.known.read().unwrap_or_else(|e| e.into_inner());
+        known
+            .iter()
+            .filter(|kv| path.strip_mount(&kv.mount).is_some())
+            .max_by_key(|kv| kv.mount.segments().count())
+            .cloned()
+    }
+
+    /// Remembers a detected mount, so it is only detected once.
+    pub fn insert(&self, mount: BaoKvMount) {
+        let mut known = self.known.write().unwrap_or_else(|e| e.into_inner());
+        if !known.iter().any(|kv| kv.mount == mount.mount) {
+            known.push(mount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::config::bao::KvVersion;
+    use crate::provider::references::BaoReference;
+    use std::str::FromStr;
+
+    fn path(raw: &str) -> BaoPath {
+        BaoReference::from_str(raw).unwrap().location.path
+    }
+
+    #[test]
+    fn test_most_nested_mount_wins() {
+        let mounts = KvMounts::new(vec![
+            BaoKvMount::from_str("teams=2").unwrap(),
+            BaoKvMount::from_str("teams/payments/kv=1").unwrap(),
+        ]);
+
+        let nested = mounts
+            .find(&path("bao://teams/payments/kv/app/password"))
+            .unwrap();
+        assert_eq!(nested.mount.as_str(), "teams/payments/kv");
+        assert_eq!(nested.version, KvVersion::V1);
+
+        let outer = mounts
+            .find(&path("bao://teams/billing/app/password"))
+            .unwrap();
+        assert_eq!(outer.mount.as_str(), "teams");
+
+        // A mount only contains the paths below it, not itself.
+        assert_eq!(
+            mounts
+                .find(&path("bao://teams/payments/kv/password"))
+                .unwrap()
+                .mount
+                .as_str(),
+            "teams"
+        );
+        assert!(mounts.find(&path("bao://secret/app/password")).is_none());
+
+        mounts.insert(BaoKvMount::from_str("secret=2").unwrap());
+        mounts.insert(BaoKvMount::from_str("secret=1").unwrap());
+        let secret = mounts.find(&path("bao://secret/app/password")).unwrap();
+        assert_eq!(secret.version, KvVersion::V2);
+    }
+}
@@ -0,0 +1,647 @@
+//! Lease tracking for issued OpenBao / Vault secrets.
+//!
+//! A dynamic secret (e.g. database credentials) is issued under a lease,
+//! and a PKI certificate is issued with an expiry. Every field of one
+//! issued secret belongs together. The active lease for each secret is
+//! remembered so that all fields referencing it resolve from the same
+//! issued secret, instead of issuing a new one per field or per render.
+//!
+//! A background task renews every renewable lease once a configured
+//! fraction of its duration has passed. When a lease can't be renewed (it
+//! is not renewable, it reached its max TTL, or the renewal failed), it is
+//! forgotten and [`Leases::expired`] resolves, so the caller can fetch and
+//! render a freshly issued secret before the old one lapses. The forgotten
+//! lease is revoked once its secret was issued anew. Until then, expiry is
+//! signalled again every so often, in case the render failed, and a last
+//! time when the old lease lapses.
+
+use crate::provider::{
+    ProviderError,
+    config::bao::RenewFraction,
+    references::{BaoPath, BaoPkiRequest},
+    types::JsonField,
+};
+use async_trait::async_trait;
+use futures::future::join_all;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify, OnceCell, watch};
+use tokio::time::Instant;
+use tokio_util::sync::{CancellationToken, DropGuard};
+use tracing::{debug, info, warn};
+
+/// A secret which is issued anew on every read, instead of being stored.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Issued {
+    /// Read from a dynamic secrets engine path.
+    Dynamic(BaoPath),
+    /// Issued by a PKI secrets engine.
+    Certificate(BaoPkiRequest),
+}
+
+impl fmt::Display for Issued {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Dynamic(path) => write!(f, "{}", path),
+            Self::Certificate(request) => write!(f, "{}", request),
+        }
+    }
+}
+
+/// The lease operations of the OpenBao / Vault API.
+#[async_trait]
+pub(super) trait LeaseApi: Send + Sync + 'static {
+    /// Issues a new secret.
+    async fn issue(&self, secret: &Issued) -> Result<Lease, ProviderError>;
+
+    /// Extends a lease by the given increment, returning its new terms.
+    async fn renew(&self, lease_id: &str, increment: Duration)
+    -> Result<LeaseTerms, ProviderError>;
+
+    /// Revokes a lease, invalidating its secret.
+    async fn revoke(&self, lease_id: &str) -> Result<(), ProviderError>;
+}
+
+/// A secret issued under a lease. Secrets which expire without a lease
+/// (such as certificates) have an empty id and are not renewable.
+pub(super) struct Lease {
+    pub id: String,
+    pub terms: LeaseTerms,
+    pub data: HashMap<String, JsonField>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LeaseTerms {
+    pub duration: Duration,
+    pub renewable: bool,
+}
+
+struct ActiveLease {
+    id: String,
+    data: Arc<HashMap<String, JsonField>>,
+    /// The duration originally granted, requested again on every renewal.
+    increment: Duration,
+    renewable: bool,
+    /// When the lease should be renewed or replaced. `None` for secrets
+    /// which are not leased.
+    act_at: Option<Instant>,
+    /// When the secret lapses unless it is renewed.
+    ends_at: Option<Instant>,
+}
+
+impl ActiveLease {
+    fn new(lease: Lease, renew_at: RenewFraction) -> Self {
+        Self {
+            act_at: act_at(lease.terms.duration, renew_at),
+            ends_at: ends_at(lease.terms.duration),
+            id: lease.id,
+            data: Arc::new(lease.data),
+            increment: lease.terms.duration,
+            renewable: lease.terms.renewable,
+        }
+    }
+}
+
+/// Acts once the given fraction of the duration has passed, leaving enough
+/// time to re-render before the secret is invalidated.
+fn act_at(duration: Duration, renew_at: RenewFraction) -> Option<Instant> {
+    (!duration.is_zero()).then(|| Instant::now() + duration.mul_f64(renew_at.as_f64()))
+}
+
+fn ends_at(duration: Duration) -> Option<Instant> {
+    (!duration.is_zero()).then(|| Instant::now() + duration)
+}
+
+/// How long to wait for a forgotten secret to be issued anew, before
+/// signalling its expiry again.
+const REMIND_AFTER: Duration = Duration::from_secs(30);
+
+/// A forgotten lease, which is still in use until its secret is issued anew.
+struct Retired {
+    id: String,
+    /// When to signal expiry again, unless the secret was issued anew.
+    remind_at: Instant,
+    ends_at: Instant,
+}
+
+type Issuance = Arc<OnceCell<Arc<HashMap<String, JsonField>>>>;
+
+struct Shared<A> {
+    api: Arc<A>,
+    renew_at: RenewFraction,
+    active: Mutex<HashMap<Issued, ActiveLease>>,
+    /// Secrets being issued, which concurrent callers wait for.
+    issuing: Mutex<HashMap<Issued, Issuance>>,
+    retired: Mutex<HashMap<Issued, Retired>>,
+    /// Wakes the renewal task when a lease was added.
+    changed: Notify,
+    /// Generation counter, bumped whenever a lease is forgotten.
+    expired: watch::Sender<u64>,
+}
+
+pub(super) struct Leases<A> {
+    shared: Arc<Shared<A>>,
+    token: CancellationToken,
+    _renewal: DropGuard,
+}
+
+impl<A: LeaseApi> Leases<A> {
+    /// Starts tracking leases. The renewal task runs until
+    /// [`Leases::revoke_all`] is called or the tracker is dropped.
+    pub fn new(api: Arc<A>, renew_at: RenewFraction) -> Self {
+        let shared = Arc::new(Shared {
+            api,
+            renew_at,
+            active: Mutex::new(HashMap::new()),
+            issuing: Mutex::new(HashMap::new()),
+            retired: Mutex::new(HashMap::new()),
+            changed: Notify::new(),
+            expired: watch::Sender::new(0),
+        });
+        let token = CancellationToken::new();
+        tokio::spawn(renew_loop(shared.clone(), token.clone()));
+        Self {
+            shared,
+            _renewal: token.clone().drop_guard(),
+            token,
+        }
+    }
+
+    /// Returns the data of the active lease for the secret, issuing a new
+    /// one if there is none.
+    ///
+    /// Concurrent callers for the same secret share one issuance, while
+    /// different secrets are issued in parallel.
+    pub async fn data(
+        &self,
+        secret: &Issued,
+    ) -> Result<Arc<HashMap<String, JsonField>>, ProviderError> {
+        if let Some(lease) = self.shared.active.lock().await.get(secret) {
+            return Ok(lease.data.clone());
+        }
+
+        let issuance = self
+            .shared
+            .issuing
+            .lock()
+            .await
+            .entry(secret.clone())
+            .or_default()
+            .clone();
+        let result = issuance
+            .get_or_try_init(|| self.issue(secret))
+            .await
+            .cloned();
+
+        let mut issuing = self.shared.issuing.lock().await;
+        if issuing
+            .get(secret)
+            .is_some_and(|current| Arc::ptr_eq(current, &issuance))
+        {
+            issuing.remove(secret);
+        }
+        result
+    }
+
+    async fn issue(
+        &self,
+        secret: &Issued,
+    ) -> Result<Arc<HashMap<String, JsonField>>, ProviderError> {
+        // Another caller may have issued it since the lookup.
+        if let Some(lease) = self.shared.active.lock().await.get(secret) {
+            return Ok(lease.data.clone());
+        }
+
+        let lease = ActiveLease::new(self.shared.api.issue(secret).await?, self.shared.renew_at);
+        debug!(
+            "issued secret {} (lease '{}', {:?})",
+            secret, lease.id, lease.increment
+        );
+        if self.token.is_cancelled() {
+            revoke(&*self.shared.api, secret, &lease.id).await;
+            return Err(ProviderError::Other(format!(
+                "leases were revoked while {} was issued",
+                secret
+            )));
+        }
+        let data = lease.data.clone();
+        self.shared
+            .active
+            .lock()
+            .await
+            .insert(secret.clone(), lease);
+        self.shared.changed.notify_one();
+
+        // The forgotten lease was only kept for whoever still used it.
+        let retired = self.shared.retired.lock().await.remove(secret);
+        if let Some(retired) = retired {
+            revoke(&*self.shared.api, secret, &retired.id).await;
+        }
+        Ok(data)
+    }
+
+    /// Resolves the next time a lease is forgotten because it could not be
+    /// renewed.
+    pub async fn expired(&self) {
+        let mut rx = self.shared.expired.subscribe();
+        if rx.changed().await.is_err() {
+            std::future::pending().await
+        }
+    }
+
+    /// Whether no issued secret may be in use at the moment.
+    pub async fn is_empty(&self) -> bool {
+        self.shared.active.lock().await.is_empty() && self.shared.retired.lock().await.is_empty()
+    }
+
+    /// Stops renewing, and revokes every active or forgotten lease.
+    pub async fn revoke_all(&self) {
+        self.token.cancel();
+        let mut leases: Vec<_> = self
+            .shared
+            .active
+            .lock()
+            .await
+            .drain()
+            .map(|(secret, lease)| (secret, lease.id))
+            .collect();
+        leases.extend(
+            self.shared
+                .retired
+                .lock()
+                .await
+                .drain()
+                .map(|(secret, retired)| (secret, retired.id)),
+        );
+        let revocations = leases
+            .iter()
+            .map(|(secret, id)| revoke(&*self.shared.api, secret, id));
+        join_all(revocations).await;
+    }
+}
+
+/// Revokes a lease, if the secret has one. Failures are only logged, since
+/// the lease still lapses on its own.
+async fn revoke<A: LeaseApi>(api: &A, secret: &Issued, lease_id: &str) {
+    if lease_id.is_empty() {
+        return;
+    }
+    match api.revoke(lease_id).await {
+        Ok(()) => info!("revoked lease for {}", secret),
+        Err(e) => warn!("failed to revoke lease for {}: {}", secret, e),
+    }
+}
+
+async fn renew_loop<A: LeaseApi>(shared: Arc<Shared<A>>, token: CancellationToken) {
+    loop {
+        let renewal = shared
+            .active
+            .lock()
+            .await
+            .values()
+            .filter_map(|lease| lease.act_at)
+            .min();
+        let reminder = shared
+            .retired
+            .lock()
+            .await
+            .values()
+            .map(|retired| retired.remind_at)
+            .min();
+        let next = renewal.into_iter().chain(reminder).min();
+        let due = async {
+            match next {
+                Some(at) => tokio::time::sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = shared.changed.notified() => {}
+            _ = due => {
+                renew_due(&shared).await;
+                remind_due(&shared).await;
+            }
+        }
+    }
+}
+
+/// Renews every lease that is due, and forgets those which can't be renewed.
+async fn renew_due<A: LeaseApi>(shared: &Shared<A>) {
+    let now = Instant::now();
+    let due: Vec<_> = shared
+        .active
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, lease)| lease.act_at.is_some_and(|at| at <= now))
+        .map(|(secret, lease)| {
+            (
+                secret.clone(),
+                lease.id.clone(),
+                lease.data.clone(),
+                lease.increment,
+                lease.renewable,
+            )
+        })
+        .collect();
+
+    let mut forgotten = false;
+    for (secret, id, data, increment, renewable) in due {
+        let outcome = if renewable {
+            shared.api.renew(&id, increment).await
+        } else {
+            Err(ProviderError::Other("lease is not renewable".into()))
+        };
+
+        let mut active = shared.active.lock().await;
+        // The secret may have been re-issued while it was being renewed.
+        let Some(lease) = active
+            .get_mut(&secret)
+            .filter(|lease| Arc::ptr_eq(&lease.data, &data))
+        else {
+            continue;
+        };
+        match outcome {
+            Ok(terms) => {
+                debug!("renewed lease for {} ({:?})", secret, terms.duration);
+                // A shorter duration than requested means the lease reached
+                // its max TTL, so it must be replaced next time.
+                lease.renewable = terms.renewable && terms.duration >= increment;
+                lease.act_at = act_at(terms.duration, shared.renew_at);
+                lease.ends_at = ends_at(terms.duration);
+            }
+            Err(e) => {
+                warn!(
+                    "lease for {} can't be renewed ({}); it will be re-issued",
+                    secret, e
+                );
+                let lease = active.remove(&secret).expect("lease is active");
+                if let Some(ends_at) = lease.ends_at {
+                    shared.retired.lock().await.insert(
+                        secret,
+                        Retired {
+                            id: lease.id,
+                            remind_at: ends_at.min(now + REMIND_AFTER),
+                            ends_at,
+                        },
+                    );
+                }
+                forgotten = true;
+            }
+        }
+    }
+
+    if forgotten {
+        shared.expired.send_modify(|generation| *generation += 1);
+    }
+}
+
+/// Signals expiry again for forgotten secrets which were not issued anew,
+/// e.g. because rendering them failed, until their lease lapses.
+async fn remind_due<A: LeaseApi>(shared: &Shared<A>) {
+    let now = Instant::now();
+    let mut remind = false;
+    shared.retired.lock().await.retain(|secret, retired| {
+        if retired.remind_at > now {
+            return true;
+        }
+        remind = true;
+        if retired.ends_at <= now {
+            warn!("lease for {} lapsed before it was replaced", secret);
+            return false;
+        }
+        debug!(
+            "{} was not issued anew yet, signalling expiry again",
+            secret
+        );
+        retired.remind_at = retired.ends_at.min(now + REMIND_AFTER);
+        true
+    });
+
+    if remind {
+        shared.expired.send_modify(|generation| *generation += 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::{ExposeSecret, SecretString};
+    use std::sync::Mutex as StdMutex;
+
+    /// Issues numbered secrets, and records every lease operation.
+    #[derive(Default)]
+    struct StubApi {
+        renewable: bool,
+        fail_renewal: bool,
+        issue_delay: Duration,
+        log: StdMutex<Vec<String>>,
+    }
+
+    impl StubApi {
+        fn log(&self) -> Vec<String> {
+            self.log.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl LeaseApi for StubApi {
+        async fn issue(&self, _secret: &Issued) -> Result<Lease, ProviderError> {
+            tokio::time::sleep(self.issue_delay).await;
+            let mut log = self.log.lock().unwrap();
+            let id = format!(
+                "lease-{}",
+                log.iter().filter(|l| l.starts_with("issue")).count()
+            );
+            log.push(format!("issue {id}"));
+            Ok(Lease {
+                data: HashMap::from([(
+                    "username".to_string(),
+                    JsonField::Scalar(SecretString::from(format!("{id}-user"))),
+                )]),
+                id,
+                terms: LeaseTerms {
+                    duration: Duration::from_secs(60),
+                    renewable: self.renewable,
+                },
+            })
+        }
+
+        async fn renew(
+            &self,
+            lease_id: &str,
+            increment: Duration,
+        ) -> Result<LeaseTerms, ProviderError> {
+            self.log.lock().unwrap().push(format!("renew {lease_id}"));
+            if self.fail_renewal {
+                return Err(ProviderError::NotFound(lease_id.to_string()));
+            }
+            Ok(LeaseTerms {
+                duration: increment,
+                renewable: true,
+            })
+        }
+
+        async fn revoke(&self, lease_id: &str) -> Result<(), ProviderError> {
+            self.log.lock().unwrap().push(format!("revoke {lease_id}"));
+            Ok(())
+        }
+    }
+
+    fn path() -> Issued {
+        role("app")
+    }
+
+    fn role(name: &str) -> Issued {
+        Issued::Dynamic(BaoPath::new(vec!["database".into(), "creds".into(), name.into()]).unwrap())
+    }
+
+    fn username(data: &HashMap<String, JsonField>) -> String {
+        match &data["username"] {
+            JsonField::Scalar(s) => s.expose_secret().to_string(),
+            JsonField::Unsupported => panic!("unexpected field"),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reuses_active_lease() {
+        let api = Arc::new(StubApi::default());
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+
+        let first = leases.data(&path()).await.unwrap();
+        let second = leases.data(&path()).await.unwrap();
+
+        assert_eq!(username(&first), "lease-0-user");
+        assert_eq!(username(&second), "lease-0-user");
+        assert_eq!(api.log(), vec!["issue lease-0"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn renews_before_expiry() {
+        let api = Arc::new(StubApi {
+            renewable: true,
+            ..Default::default()
+        });
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+        leases.data(&path()).await.unwrap();
+
+        tokio::time::sleep(Duration::from_secs(39)).await;
+        assert_eq!(api.log(), vec!["issue lease-0"]);
+
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert_eq!(api.log(), vec!["issue lease-0", "renew lease-0"]);
+
+        tokio::time::sleep(Duration::from_secs(40)).await;
+        assert_eq!(
+            api.log(),
+            vec!["issue lease-0", "renew lease-0", "renew lease-0"]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_renewal_expires_and_reissues() {
+        let api = Arc::new(StubApi {
+            renewable: true,
+            fail_renewal: true,
+            ..Default::default()
+        });
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+        leases.data(&path()).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(60), leases.expired())
+            .await
+            .expect("expiry should be signalled");
+
+        let data = leases.data(&path()).await.unwrap();
+        assert_eq!(username(&data), "lease-1-user");
+        // The forgotten lease is revoked once it was replaced.
+        assert_eq!(
+            api.log(),
+            vec![
+                "issue lease-0",
+                "renew lease-0",
+                "issue lease-1",
+                "revoke lease-0"
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expiry_is_signalled_again_until_reissued() {
+        let api = Arc::new(StubApi::default());
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+        leases.data(&path()).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(41), leases.expired())
+            .await
+            .expect("expiry should be signalled");
+
+        // Rendering the new secret failed, so nothing issued it anew. Expiry
+        // is signalled once more when the old lease lapses, and then no more.
+        tokio::time::timeout(Duration::from_secs(21), leases.expired())
+            .await
+            .expect("expiry should be signalled again");
+        assert!(
+            tokio::time::timeout(Duration::from_secs(120), leases.expired())
+                .await
+                .is_err()
+        );
+        assert!(leases.is_empty().await);
+        assert_eq!(api.log(), vec!["issue lease-0"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn issues_different_secrets_in_parallel() {
+        let api = Arc::new(StubApi {
+            issue_delay: Duration::from_secs(1),
+            ..Default::default()
+        });
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+
+        let (first, second) = (role("a"), role("b"));
+        let start = Instant::now();
+        let (a, b, c) = tokio::join!(
+            leases.data(&first),
+            leases.data(&second),
+            leases.data(&first)
+        );
+        assert_eq!(start.elapsed(), Duration::from_secs(1));
+
+        // Concurrent callers for one secret share its issuance.
+        let (a, b, c) = (a.unwrap(), b.unwrap(), c.unwrap());
+        assert!(Arc::ptr_eq(&a, &c));
+        assert_ne!(username(&a), username(&b));
+        assert_eq!(api.log().len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn non_renewable_lease_expires() {
+        let api = Arc::new(StubApi::default());
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+        leases.data(&path()).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(60), leases.expired())
+            .await
+            .expect("expiry should be signalled");
+        assert_eq!(api.log(), vec!["issue lease-0"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn revoke_all_revokes_and_stops_renewal() {
+        let api = Arc::new(StubApi {
+            renewable: true,
+            ..Default::default()
+        });
+        let leases = Leases::new(api.clone(), RenewFraction::default());
+        leases.data(&path()).await.unwrap();
+
+        leases.revoke_all().await;
+        tokio::time::sleep(Duration::from_secs(120)).await;
+
+        assert_eq!(api.log(), vec!["issue lease-0", "revoke lease-0"]);
+    }
+}
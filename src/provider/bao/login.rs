@@ -0,0 +1,749 @@
+//! Credential exchanges for the OpenBao / Vault auth methods.
+//!
+//! Each auth method logs in through its own [`TokenExchange`], selected
+//! by `--bao-auth-method`. Credentials given as `file:` are read again on
+//! every login, so a rotated credential (such as a projected Kubernetes
+//! service account token) is picked up when the client token is renewed.
+//!
+//! A response-wrapped AppRole secret ID is the exception: wrapping tokens
+//! are single use, so each one is unwrapped once, and the secret ID it
+//! wraps is kept with the configuration and reused whenever the exchange is
+//! built again.
+
+use crate::provider::{
+    AuthToken, ProviderError, ServerUrl,
+    auth::{ExpiringToken, SecretView, TokenExchange},
+    config::bao::{BaoAuthMethod, BaoConfig, BaoNamespace},
+    references::BaoMount,
+};
+use async_trait::async_trait;
+use reqwest::{Client, ClientBuilder, Identity, RequestBuilder, Response, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+
+/// Service account token mounted into every Kubernetes pod by default.
+const SERVICE_ACCOUNT_TOKEN: &str = "file:/var/run/secrets/kubernetes.io/serviceaccount/token";
+
+/// Builds the credential exchange for the configured auth method.
+///
+/// Fails if a credential the method requires is missing, or if a wrapped
+/// secret ID can't be unwrapped.
+pub(super) async fn exchange(
+    config: &BaoConfig,
+    client: &Client,
+) -> Result<Box<dyn TokenExchange>, ProviderError> {
+    let endpoint = LoginEndpoint {
+        client: client.clone(),
+        url: config.bao_url.clone(),
+        namespace: config.bao_namespace.clone(),
+        mount: config.auth_mount(),
+    };
+    let method = config.bao_auth_method;
+
+    Ok(match method {
+        BaoAuthMethod::AppRole => {
+            let role_id = required(&config.bao_role_id, method, "--bao-role-id")?;
+            let configured = required(&config.bao_secret_id, method, "--bao-secret-id")?;
+            let mut secret_id = configured.token().clone();
+            if config.bao_secret_id_wrapped {
+                // Concurrent builds with the same wrapping token wait for a
+                // single unwrap.
+                let wrapping_token = secret_id.resolve().await?;
+                secret_id = AuthToken::new(
+                    configured
+                        .unwrapped(&wrapping_token, || {
+                            endpoint.unwrap_secret_id(&wrapping_token)
+                        })
+                        .await?,
+                );
+            }
+            Box::new(AppRoleLogin {
+                endpoint,
+                role_id,
+                secret_id,
+            })
+        }
+        BaoAuthMethod::Token => Box::new(TokenLogin {
+            endpoint,
+            token: required(&config.bao_token, method, "--bao-token")?,
+        }),
+        BaoAuthMethod::Kubernetes => Box::new(JwtLogin {
+            endpoint,
+            method,
+            role: required(&config.bao_auth_role, method, "--bao-auth-role")?,
+            jwt: config.bao_jwt.clone().unwrap_or_else(|| {
+                SERVICE_ACCOUNT_TOKEN
+                    .parse()
+                    .expect("service account token path is valid")
+            }),
+        }),
+        BaoAuthMethod::Jwt => Box::new(JwtLogin {
+            endpoint,
+            method,
+            role: required(&config.bao_auth_role, method, "--bao-auth-role")?,
+            jwt: required(&config.bao_jwt, method, "--bao-jwt")?,
+        }),
+        BaoAuthMethod::Cert => Box::new(CertLogin {
+            endpoint,
+            role: config.bao_auth_role.clone(),
+        }),
+        BaoAuthMethod::Userpass => Box::new(UserpassLogin {
+            endpoint,
+            username: required(&config.bao_username, method, "--bao-username")?,
+            password: required(&config.bao_password, method, "--bao-password")?,
+        }),
+    })
+}
+
+/// Fails with a transient error if the server is rate limiting, or can't
+/// serve the request right now, e.g. because it is sealed or a standby.
+/// Another attempt, or another server of a failover chain, may succeed.
+fn transient(resp: &Response) -> Result<(), ProviderError> {
+    let status = resp.status();
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(ProviderError::rate_limited(resp.headers()));
+    }
+    if status.is_server_error() {
+        return Err(ProviderError::Unavailable(format!(
+            "OpenBao returned {}",
+            status
+        )));
+    }
+    Ok(())
+}
+
+/// Adds the client certificate to the HTTP client when logging in with the
+/// `cert` auth method, which authenticates the TLS connection itself.
+pub(super) async fn configure_client(
+    config: &BaoConfig,
+    builder: ClientBuilder,
+) -> Result<ClientBuilder, ProviderError> {
+    if config.bao_auth_method != BaoAuthMethod::Cert {
+        return Ok(builder);
+    }
+    let method = config.bao_auth_method;
+    let cert = required(&config.bao_client_cert, method, "--bao-client-cert")?;
+    let key = required(&config.bao_client_key, method, "--bao-client-key")?;
+
+    let mut pem = tokio::fs::read(cert.as_path()).await.map_err(|e| {
+        ProviderError::InvalidConfig(format!("failed to read {}: {e}", cert.as_path().display()))
+    })?;
+    pem.push(b'\n');
+    pem.extend_from_slice(key.resolve().await?.expose_secret().as_bytes());
+    let identity = Identity::from_pem(&pem).map_err(|e| {
+        ProviderError::InvalidConfig(format!("invalid client certificate or key: {e}"))
+    })?;
+    Ok(builder.identity(identity))
+}
+
+fn required<T: Clone>(
+    value: &Option<T>,
+    method: BaoAuthMethod,
+    flag: &str,
+) -> Result<T, ProviderError> {
+    value.clone().ok_or_else(|| {
+        ProviderError::InvalidConfig(format!("{flag} is required for the {method} auth method"))
+    })
+}
+
+/// The login endpoint of an auth method's mount.
+struct LoginEndpoint {
+    client: Client,
+    url: ServerUrl,
+    namespace: Option<BaoNamespace>,
+    mount: BaoMount,
+}
+
+impl LoginEndpoint {
+    /// Logs in at `auth/<mount>/login[/<suffix>]` with the given payload.
+    async fn login(
+        &self,
+        method: BaoAuthMethod,
+        suffix: Option<&str>,
+        payload: &impl Serialize,
+    ) -> Result<ExpiringToken, ProviderError> {
+        let url = self.url.endpoint(
+            ["v1", "auth"]
+                .into_iter()
+                .chain(self.mount.segments())
+                .chain(["login"])
+                .chain(suffix),
+        );
+
+        let mut req = self.client.post(url).json(payload);
+        if let Some(ns) = &self.namespace {
+            req = req.header("X-Vault-Namespace", ns.as_str());
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        transient(&resp)?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::Unauthorized(format!(
+                "{} login failed: {} - {}",
+                method, status, text
+            )));
+        }
+
+        let login_resp: LoginResponse = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        Ok(ExpiringToken::new(
+            login_resp.auth.client_token,
+            login_resp.auth.lease_duration,
+        ))
+    }
+
+    /// Unwraps a response-wrapped AppRole secret ID.
+    ///
+    /// The wrapping token is looked up first, which doesn't use it up, to
+    /// make sure it wraps a secret ID generated by this mount. Only then is
+    /// it unwrapped.
+    async fn unwrap_secret_id(
+        &self,
+        wrapping_token: &SecretString,
+    ) -> Result<SecretString, ProviderError> {
+        let url = self.url.endpoint(["v1", "sys", "wrapping", "lookup"]);
+        let req = self.client.post(url).json(&WrappingLookupParams {
+            token: SecretView(wrapping_token),
+        });
+        let lookup: WrappingLookupResponse = self.wrapping_call(req).await?;
+
+        let creation_path = lookup.data.creation_path;
+        let prefix = format!("auth/{}/role/", self.mount);
+        let valid = creation_path
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix("/secret-id"))
+            .is_some_and(|role| !role.is_empty() && !role.contains('/'));
+        if !valid {
+            return Err(ProviderError::Unauthorized(format!(
+                "the wrapping token in --bao-secret-id was created at '{}', not by '{}<role>/secret-id'; refusing to unwrap it",
+                creation_path, prefix
+            )));
+        }
+
+        let url = self.url.endpoint(["v1", "sys", "wrapping", "unwrap"]);
+        let req = self
+            .client
+            .post(url)
+            .header("X-Vault-Token", wrapping_token.expose_secret());
+        let unwrapped: UnwrapResponse = self.wrapping_call(req).await?;
+        Ok(unwrapped.data.secret_id)
+    }
+
+    /// Sends a request to the response-wrapping API.
+    async fn wrapping_call<T: serde::de::DeserializeOwned>(
+        &self,
+        mut req: RequestBuilder,
+    ) -> Result<T, ProviderError> {
+        if let Some(ns) = &self.namespace {
+            req = req.header("X-Vault-Namespace", ns.as_str());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        transient(&resp)?;
+        match resp.status() {
+            status if status.is_success() => resp
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e))),
+            // Invalid wrapping tokens are reported as bad requests.
+            StatusCode::BAD_REQUEST | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
+                "the wrapping token in --bao-secret-id is invalid, expired or was already used. \
+                     If locket did not unwrap it before, it may have been intercepted: \
+                     revoke the Secret ID and investigate"
+                    .to_string(),
+            )),
+            status => {
+                let text = resp.text().await.unwrap_or_default();
+                Err(ProviderError::Other(format!(
+                    "OpenBao error {}: {}",
+                    status, text
+                )))
+            }
+        }
+    }
+}
+
+/// AppRole credential exchange.
+struct AppRoleLogin {
+    endpoint: LoginEndpoint,
+    role_id: String,
+    secret_id: AuthToken,
+}
+
+#[async_trait]
+impl TokenExchange for AppRoleLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let secret_id = self.secret_id.resolve().await?;
+        let payload = AppRoleParams {
+            role_id: &self.role_id,
+            secret_id: SecretView(&secret_id),
+        };
+        self.endpoint
+            .login(BaoAuthMethod::AppRole, None, &payload)
+            .await
+    }
+}
+
+/// Uses an existing client token. Looking the token up both validates it
+/// and tells when it expires.
+struct TokenLogin {
+    endpoint: LoginEndpoint,
+    token: AuthToken,
+}
+
+#[async_trait]
+impl TokenExchange for TokenLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let token = self.token.resolve().await?;
+        let endpoint = &self.endpoint;
+        let url = endpoint
+            .url
+            .endpoint(["v1", "auth", "token", "lookup-self"]);
+
+        let mut req = endpoint
+            .client
+            .get(url)
+            .header("X-Vault-Token", token.expose_secret());
+        if let Some(ns) = &endpoint.namespace {
+            req = req.header("X-Vault-Namespace", ns.as_str());
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        transient(&resp)?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(ProviderError::Unauthorized(format!(
+                "token lookup failed: {} - {}",
+                status, text
+            )));
+        }
+
+        let lookup: TokenLookupResponse = resp
+            .json()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        Ok(ExpiringToken::new(token, lookup.data.ttl))
+    }
+}
+
+/// Kubernetes service account and generic JWT / OIDC exchange, which
+/// share a payload.
+struct JwtLogin {
+    endpoint: LoginEndpoint,
+    method: BaoAuthMethod,
+    role: String,
+    jwt: AuthToken,
+}
+
+#[async_trait]
+impl TokenExchange for JwtLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let jwt = self.jwt.resolve().await?;
+        let payload = JwtParams {
+            role: &self.role,
+            jwt: SecretView(&jwt),
+        };
+        self.endpoint.login(self.method, None, &payload).await
+    }
+}
+
+/// TLS client certificate exchange. The certificate is presented by the
+/// HTTP client, see [`configure_client`].
+struct CertLogin {
+    endpoint: LoginEndpoint,
+    role: Option<String>,
+}
+
+#[async_trait]
+impl TokenExchange for CertLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let payload = CertParams {
+            name: self.role.as_deref(),
+        };
+        self.endpoint
+            .login(BaoAuthMethod::Cert, None, &payload)
+            .await
+    }
+}
+
+/// Username and password exchange.
+struct UserpassLogin {
+    endpoint: LoginEndpoint,
+    username: String,
+    password: AuthToken,
+}
+
+#[async_trait]
+impl TokenExchange for UserpassLogin {
+    async fn login(&self) -> Result<ExpiringToken, ProviderError> {
+        let password = self.password.resolve().await?;
+        let payload = UserpassParams {
+            password: SecretView(&password),
+        };
+        self.endpoint
+            .login(BaoAuthMethod::Userpass, Some(&self.username), &payload)
+            .await
+    }
+}
+
+#[derive(Serialize)]
+struct AppRoleParams<'a> {
+    role_id: &'a str,
+    secret_id: SecretView<'a>,
+}
+
+#[derive(Serialize)]
+struct JwtParams<'a> {
+    role: &'a str,
+    jwt: SecretView<'a>,
+}
+
+#[derive(Serialize)]
+struct CertParams<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct UserpassParams<'a> {
+    password: SecretView<'a>,
+}
+
+#[derive(Serialize)]
+struct WrappingLookupParams<'a> {
+    token: SecretView<'a>,
+}
+
+#[derive(Deserialize)]
+struct WrappingLookupResponse {
+    data: WrappingLookupData,
+}
+
+#[derive(Deserialize)]
+struct WrappingLookupData {
+    creation_path: String,
+}
+
+#[derive(Deserialize)]
+struct UnwrapResponse {
+    data: UnwrappedSecretId,
+}
+
+#[derive(Deserialize)]
+struct UnwrappedSecretId {
+    secret_id: SecretString,
+}
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    auth: LoginAuth,
+}
+
+#[derive(Deserialize)]
+struct LoginAuth {
+    client_token: SecretString,
+    lease_duration: u64,
+}
+
+#[derive(Deserialize)]
+struct TokenLookupResponse {
+    data: TokenLookupData,
+}
+
+#[derive(Deserialize)]
+struct TokenLookupData {
+    /// Remaining lifetime in seconds, `0` for tokens which never expire.
+    ttl: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{config::bao::SecretId, test_server::TestServer};
+    use std::str::FromStr;
+
+    fn config(url: &str, method: BaoAuthMethod) -> BaoConfig {
+        BaoConfig {
+            bao_url: ServerUrl::from_str(url).unwrap(),
+            bao_namespace: None,
+            bao_auth_method: method,
+            bao_auth_mount: None,
+            bao_role_id: None,
+            bao_secret_id: None,
+            bao_secret_id_wrapped: false,
+            bao_token: None,
+            bao_auth_role: None,
+            bao_jwt: None,
+            bao_client_cert: None,
+            bao_client_key: None,
+            bao_username: None,
+            bao_password: None,
+            bao_kv_mount: Vec::new(),
+            bao_max_concurrent: Default::default(),
+            bao_renew_at: Default::default(),
+        }
+    }
+
+    fn secret(value: &str) -> Option<AuthToken> {
+        Some(AuthToken::new(SecretString::from(value.to_string())))
+    }
+
+    const LOGIN: &str = r#"{"auth":{"client_token":"s.client","lease_duration":3600}}"#;
+
+    #[tokio::test]
+    async fn test_missing_credentials_are_rejected() {
+        let client = Client::new();
+        for method in [
+            BaoAuthMethod::AppRole,
+            BaoAuthMethod::Token,
+            BaoAuthMethod::Kubernetes,
+            BaoAuthMethod::Jwt,
+            BaoAuthMethod::Userpass,
+        ] {
+            let err = exchange(&config("http://bao.internal", method), &client)
+                .await
+                .err()
+                .unwrap();
+            assert!(
+                matches!(&err, ProviderError::InvalidConfig(m) if m.contains("is required for the")),
+                "{method}: {err}"
+            );
+        }
+
+        let cert = config("http://bao.internal", BaoAuthMethod::Cert);
+        let err = configure_client(&cert, Client::builder())
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(m) if m.contains("--bao-client-cert")));
+    }
+
+    #[tokio::test]
+    async fn test_kubernetes_login() {
+        let server = TestServer::start(|_| (200, LOGIN.into()));
+        let mut cfg = config(server.url(), BaoAuthMethod::Kubernetes);
+        cfg.bao_auth_role = Some("locket".into());
+        cfg.bao_jwt = secret("sa-jwt");
+
+        exchange(&cfg, &Client::new())
+            .await
+            .unwrap()
+            .login()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/v1/auth/kubernetes/login");
+        assert_eq!(requests[0].body, r#"{"role":"locket","jwt":"sa-jwt"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_userpass_login_uses_custom_mount() {
+        let server = TestServer::start(|_| (200, LOGIN.into()));
+        let mut cfg = config(server.url(), BaoAuthMethod::Userpass);
+        cfg.bao_auth_mount = Some(BaoMount::from_str("people").unwrap());
+        cfg.bao_username = Some("alice".into());
+        cfg.bao_password = secret("hunter2");
+
+        exchange(&cfg, &Client::new())
+            .await
+            .unwrap()
+            .login()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/v1/auth/people/login/alice");
+        assert_eq!(requests[0].body, r#"{"password":"hunter2"}"#);
+    }
+
+    #[tokio::test]
+    async fn test_token_login_looks_up_token() {
+        let server = TestServer::start(|req| match req.header("x-vault-token") {
+            Some("s.valid") => (200, r#"{"data":{"ttl":0}}"#.into()),
+            _ => (403, r#"{"errors":["permission denied"]}"#.into()),
+        });
+
+        let mut cfg = config(server.url(), BaoAuthMethod::Token);
+        cfg.bao_token = secret("s.valid");
+        exchange(&cfg, &Client::new())
+            .await
+            .unwrap()
+            .login()
+            .await
+            .unwrap();
+        assert_eq!(server.requests()[0].path, "/v1/auth/token/lookup-self");
+
+        cfg.bao_token = secret("s.revoked");
+        let err = exchange(&cfg, &Client::new())
+            .await
+            .unwrap()
+            .login()
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::Unauthorized(_)));
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_secret_id_is_unwrapped_once() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v1/sys/wrapping/lookup" => (
+                200,
+                r#"{"data":{"creation_path":"auth/approle/role/locket/secret-id"}}"#.into(),
+            ),
+            "/v1/sys/wrapping/unwrap" if req.header("x-vault-token") == Some("s.wrapping-once") => {
+                (
+                    200,
+                    r#"{"data":{"secret_id":"unwrapped","secret_id_accessor":"a"}}"#.into(),
+                )
+            }
+            _ => (200, LOGIN.into()),
+        });
+        let mut cfg = config(server.url(), BaoAuthMethod::AppRole);
+        cfg.bao_role_id = Some("role".into());
+        cfg.bao_secret_id = secret("s.wrapping-once").map(SecretId::from);
+        cfg.bao_secret_id_wrapped = true;
+
+        let login = exchange(&cfg, &Client::new()).await.unwrap();
+        login.login().await.unwrap();
+        login.login().await.unwrap();
+
+        let requests = server.requests();
+        let paths: Vec<_> = requests.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/v1/sys/wrapping/lookup",
+                "/v1/sys/wrapping/unwrap",
+                "/v1/auth/approle/login",
+                "/v1/auth/approle/login",
+            ]
+        );
+        assert_eq!(requests[0].body, r#"{"token":"s.wrapping-once"}"#);
+        assert_eq!(
+            requests[2].body,
+            r#"{"role_id":"role","secret_id":"unwrapped"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unwrapped_secret_id_is_kept_per_configuration() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v1/sys/wrapping/lookup" => (
+                200,
+                r#"{"data":{"creation_path":"auth/approle/role/locket/secret-id"}}"#.into(),
+            ),
+            "/v1/sys/wrapping/unwrap" => {
+                let token = req.header("x-vault-token").unwrap_or_default();
+                (200, format!(r#"{{"data":{{"secret_id":"{token}-id"}}}}"#))
+            }
+            _ => (200, LOGIN.into()),
+        });
+        let mut cfg = config(server.url(), BaoAuthMethod::AppRole);
+        cfg.bao_role_id = Some("role".into());
+        cfg.bao_secret_id = secret("s.wrapping-a").map(SecretId::from);
+        cfg.bao_secret_id_wrapped = true;
+        let unwraps = || {
+            server
+                .requests()
+                .iter()
+                .filter(|r| r.path == "/v1/sys/wrapping/unwrap")
+                .count()
+        };
+
+        // Clones share the secret ID, but an unrelated configuration with
+        // the same wrapping token does not.
+        exchange(&cfg, &Client::new()).await.unwrap();
+        exchange(&cfg.clone(), &Client::new()).await.unwrap();
+        assert_eq!(unwraps(), 1);
+        let mut other = cfg.clone();
+        other.bao_secret_id = secret("s.wrapping-a").map(SecretId::from);
+        exchange(&other, &Client::new()).await.unwrap();
+        assert_eq!(unwraps(), 2);
+
+        // A wrapping token replaced in its file is unwrapped anew.
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("secret-id");
+        std::fs::write(&file, "s.wrapping-a").unwrap();
+        let token = AuthToken::from_str(&format!("file:{}", file.display())).unwrap();
+        cfg.bao_secret_id = Some(SecretId::from(token));
+        exchange(&cfg, &Client::new()).await.unwrap();
+        std::fs::write(&file, "s.wrapping-b").unwrap();
+        exchange(&cfg, &Client::new())
+            .await
+            .unwrap()
+            .login()
+            .await
+            .unwrap();
+        assert_eq!(unwraps(), 4);
+        assert_eq!(
+            server.requests().last().unwrap().body,
+            r#"{"role_id":"role","secret_id":"s.wrapping-b-id"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wrapped_secret_id_rejects_used_or_foreign_tokens() {
+        let mut cfg = config("http://unused", BaoAuthMethod::AppRole);
+        cfg.bao_role_id = Some("role".into());
+        cfg.bao_secret_id = secret("s.wrapping-rejected").map(SecretId::from);
+        cfg.bao_secret_id_wrapped = true;
+
+        // Already unwrapped by someone else.
+        let server = TestServer::start(|_| {
+            (
+                400,
+                r#"{"errors":["wrapping token is not valid or does not exist"]}"#.into(),
+            )
+        });
+        cfg.bao_url = ServerUrl::from_str(server.url()).unwrap();
+        let err = exchange(&cfg, &Client::new()).await.err().unwrap();
+        assert!(
+            matches!(&err, ProviderError::Unauthorized(m) if m.contains("already used")),
+            "{err}"
+        );
+
+        // Wraps something other than a secret ID of the configured mount.
+        for creation_path in [
+            "sys/wrapping/wrap",
+            "auth/other/role/locket/secret-id",
+            "auth/approle/role/locket/custom-secret-id",
+        ] {
+            let body = format!(r#"{{"data":{{"creation_path":"{creation_path}"}}}}"#);
+            let server = TestServer::start(move |_| (200, body.clone()));
+            cfg.bao_url = ServerUrl::from_str(server.url()).unwrap();
+            let err = exchange(&cfg, &Client::new()).await.err().unwrap();
+            assert!(
+                matches!(&err, ProviderError::Unauthorized(m) if m.contains("refusing to unwrap")),
+                "{creation_path}: {err}"
+            );
+            assert!(
+                server
+                    .requests()
+                    .iter()
+                    .all(|r| r.path != "/v1/sys/wrapping/unwrap"),
+                "{creation_path} must not be unwrapped"
+            );
+        }
+    }
+}
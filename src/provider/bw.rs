@@ -0,0 +1,460 @@
+//! Bitwarden / Vaultwarden vault provider implementation.
+//!
+//! Talks to the Vault Management API of a local `bw serve` process, which
+//! holds the decrypted vault of a logged in Bitwarden CLI. This works for
+//! both bitwarden.com and self-hosted Vaultwarden accounts, and needs no
+//! Secrets Manager subscription.
+//!
+//! Before each batch, the vault is unlocked (if a master password is
+//! configured) and synced, so edits made in other clients are picked up.
+//! Each referenced item is then fetched once, no matter how many of its
+//! fields are referenced.
+
+use super::{
+    AuthToken, ConcurrencyLimit, ProviderError, SecretsProvider, ServerUrl,
+    config::bw::BwConfig,
+    references::{BwField, BwItem, BwReference, Extract, HasReference, SecretReference},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use reqwest::{Client, RequestBuilder, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, info};
+
+pub struct BwProvider {
+    client: Client,
+    url: ServerUrl,
+    password: Option<AuthToken>,
+    max_concurrent: ConcurrencyLimit,
+}
+
+/// Every `bw serve` response is wrapped in this envelope.
+#[derive(Deserialize)]
+struct Envelope<T> {
+    success: bool,
+    message: Option<String>,
+    data: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    template: StatusTemplate,
+}
+
+#[derive(Deserialize)]
+struct StatusTemplate {
+    status: String,
+}
+
+#[derive(Serialize)]
+struct UnlockRequest<'a> {
+    password: &'a str,
+}
+
+#[derive(Deserialize)]
+struct List<T> {
+    data: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct StringValue {
+    data: SecretString,
+}
+
+#[derive(Deserialize)]
+struct Item {
+    id: String,
+    name: String,
+    notes: Option<SecretString>,
+    login: Option<Login>,
+    #[serde(default)]
+    fields: Vec<CustomField>,
+}
+
+#[derive(Deserialize)]
+struct Login {
+    username: Option<SecretString>,
+    password: Option<SecretString>,
+    totp: Option<SecretString>,
+}
+
+#[derive(Deserialize)]
+struct CustomField {
+    name: Option<String>,
+    value: Option<SecretString>,
+}
+
+impl BwProvider {
+    pub async fn new(config: BwConfig) -> Result<Self, ProviderError> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        let provider = Self {
+            client,
+            url: config.bw_url,
+            password: config.bw_password,
+            max_concurrent: config.bw_max_concurrent,
+        };
+        provider.unlock().await?;
+        Ok(provider)
+    }
+
+    /// Sends a request and unwraps the response envelope.
+    async fn call<T: DeserializeOwned>(
+        &self,
+        req: RequestBuilder,
+        what: &str,
+    ) -> Result<T, ProviderError> {
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        let status = resp.status();
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(ProviderError::rate_limited(resp.headers()));
+            }
+            StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT => {
+                return Err(ProviderError::Unavailable(format!(
+                    "bw serve returned {} for {}",
+                    status, what
+                )));
+            }
+            _ => {}
+        }
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+        let envelope: Envelope<T> = serde_json::from_str(&text).map_err(|_| {
+            ProviderError::Other(format!("bw serve error {} for {}: {}", status, what, text))
+        })?;
+
+        match envelope {
+            Envelope {
+                success: true,
+                data: Some(data),
+                ..
+            } => Ok(data),
+            Envelope { message, .. } => {
+                let message = message.unwrap_or_else(|| status.to_string());
+                let lower = message.to_ascii_lowercase();
+                if status == StatusCode::NOT_FOUND || lower.contains("not found") {
+                    Err(ProviderError::NotFound(what.to_string()))
+                } else if lower.contains("locked") || lower.contains("password") {
+                    Err(ProviderError::Unauthorized(format!(
+                        "{}: {}",
+                        what, message
+                    )))
+                } else {
+                    Err(ProviderError::Other(format!(
+                        "bw serve error for {}: {}",
+                        what, message
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Makes sure the vault is unlocked, unlocking it with the master
+    /// password if one is configured.
+    async fn unlock(&self) -> Result<(), ProviderError> {
+        let status: Status = self
+            .call(self.client.get(self.url.endpoint(["status"])), "status")
+            .await?;
+
+        match status.template.status.as_str() {
+            "unlocked" => Ok(()),
+            "locked" => {
+                let Some(password) = &self.password else {
+                    return Err(ProviderError::Unauthorized(
+                        "the Bitwarden vault is locked and no --bw-password was provided".into(),
+                    ));
+                };
+                let password = password.resolve().await?;
+                let req = self
+                    .client
+                    .post(self.url.endpoint(["unlock"]))
+                    .json(&UnlockRequest {
+                        password: password.expose_secret(),
+                    });
+                self.call::<serde_json::Value>(req, "unlock").await?;
+                info!("Unlocked the Bitwarden vault");
+                Ok(())
+            }
+            other => Err(ProviderError::Unauthorized(format!(
+                "the Bitwarden CLI is {}; run `bw login` before `bw serve`",
+                other
+            ))),
+        }
+    }
+
+    /// Pulls the latest vault content from the server.
+    async fn sync(&self) -> Result<(), ProviderError> {
+        self.call::<serde_json::Value>(self.client.post(self.url.endpoint(["sync"])), "sync")
+            .await?;
+        Ok(())
+    }
+
+    async fn fetch_item(&self, item: &BwItem) -> Result<Item, ProviderError> {
+        match item {
+            BwItem::Id(id) => {
+                let url = self.url.endpoint(["object", "item", &id.to_string()]);
+                self.call(self.client.get(url), &format!("item {}", id))
+                    .await
+            }
+            BwItem::Name(name) => {
+                let url = self.url.endpoint(["list", "object", "items"]);
+                let req = self.client.get(url).query(&[("search", name)]);
+                let list: List<Item> = self.call(req, &format!("item '{}'", name)).await?;
+
+                // Search also matches usernames, urls and partial names
+                let mut matches = list.data.into_iter().filter(|i| &i.name == name);
+                match (matches.next(), matches.next()) {
+                    (Some(item), None) => Ok(item),
+                    (None, _) => Err(ProviderError::NotFound(format!("item '{}'", name))),
+                    (Some(_), Some(_)) => Err(ProviderError::Other(format!(
+                        "multiple items are named '{}'; reference the item by id instead",
+                        name
+                    ))),
+                }
+            }
+        }
+    }
+
+    async fn fetch_totp(&self, item: &Item) -> Result<SecretString, ProviderError> {
+        let url = self.url.endpoint(["object", "totp", &item.id]);
+        let totp: StringValue = self
+            .call(self.client.get(url), &format!("TOTP of '{}'", item.name))
+            .await?;
+        Ok(totp.data)
+    }
+
+    /// Resolves one field of an item. Returns `None` if the item does not
+    /// have it.
+    async fn field(
+        &self,
+        item: &Item,
+        field: &BwField,
+    ) -> Result<Option<SecretString>, ProviderError> {
+        let login = item.login.as_ref();
+        Ok(match field {
+            BwField::UserName => login.and_then(|l| l.username.clone()),
+            BwField::Password => login.and_then(|l| l.password.clone()),
+            BwField::Notes => item.notes.clone(),
+            BwField::Totp => match login.and_then(|l| l.totp.as_ref()) {
+                Some(_) => Some(self.fetch_totp(item).await?),
+                None => None,
+            },
+            BwField::Custom(name) => item
+                .fields
+                .iter()
+                .find(|f| f.name.as_deref() == Some(name))
+                .and_then(|f| f.value.clone()),
+        })
+    }
+}
+
+impl HasReference for BwProvider {
+    type Reference = BwReference;
+}
+
+#[async_trait]
+impl SecretsProvider for BwProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        // Group references by item so an item with multiple referenced
+        // fields is only fetched once, instead of once per field.
+        let mut groups: HashMap<&BwItem, Vec<&BwReference>> = HashMap::new();
+        for r in references.iter().filter_map(BwReference::extract) {
+            groups.entry(r.item()).or_default().push(r);
+        }
+
+        if groups.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        // The vault may have been locked (e.g. `bw lock`) since the last batch
+        self.unlock().await?;
+        self.sync().await?;
+
+        let fetches: Vec<_> = groups
+            .into_iter()
+            .map(|(item, group_refs)| async move {
+                let item = match self.fetch_item(item).await {
+                    Ok(item) => item,
+                    // Whole item not found: leave all of its fields unresolved.
+                    Err(ProviderError::NotFound(what)) => {
+                        debug!("{} not found", what);
+                        return Ok(Vec::new());
+                    }
+                    Err(e) => return Err(e),
+                };
+                let mut values = Vec::with_capacity(group_refs.len());
+                for r in group_refs {
+                    match self.field(&item, r.field()).await? {
+                        Some(value) => values.push((SecretReference::Bw(r.clone()), value)),
+                        None => debug!("{} not found", r),
+                    }
+                }
+                Ok(values)
+            })
+            .collect();
+
+        let results = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent.into_inner())
+            .collect::<Vec<Result<_, ProviderError>>>()
+            .await;
+
+        let mut map = HashMap::new();
+        for values in results {
+            map.extend(values?);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ReferenceParser;
+    use crate::provider::test_server::{Request, TestServer};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const GITHUB_ID: &str = "3f2504e0-4f89-11d3-9a0c-0305e82c3301";
+
+    fn ok(data: &str) -> (u16, String) {
+        (200, format!(r#"{{"success":true,"data":{data}}}"#))
+    }
+
+    fn vault(req: &Request, unlocked: &AtomicBool) -> (u16, String) {
+        let github = format!(
+            r#"{{"id":"{GITHUB_ID}","name":"GitHub","notes":"recovery codes","login":{{"username":"octocat","password":"gh-pass","totp":"JBSWY3DPEHPK3PXP"}},"fields":[{{"name":"Deploy Key","value":"custom-value","type":1}}]}}"#
+        );
+        let similar = r#"{"id":"x","name":"GitHub Enterprise","login":{"username":"other"}}"#;
+        match (req.method.as_str(), req.path.as_str()) {
+            ("GET", "/status") => {
+                let status = if unlocked.load(Ordering::SeqCst) {
+                    "unlocked"
+                } else {
+                    "locked"
+                };
+                ok(&format!(
+                    r#"{{"object":"template","template":{{"status":"{status}"}}}}"#
+                ))
+            }
+            ("POST", "/unlock") if req.body.contains("master-pass") => {
+                unlocked.store(true, Ordering::SeqCst);
+                ok(r#"{"object":"message","title":"Your vault is now unlocked!"}"#)
+            }
+            ("POST", "/unlock") => (
+                400,
+                r#"{"success":false,"message":"Invalid master password."}"#.into(),
+            ),
+            _ if !unlocked.load(Ordering::SeqCst) => (
+                400,
+                r#"{"success":false,"message":"Vault is locked."}"#.into(),
+            ),
+            ("POST", "/sync") => ok(r#"{"object":"message","title":"Syncing complete."}"#),
+            ("GET", "/list/object/items?search=GitHub") => ok(&format!(
+                r#"{{"object":"list","data":[{github},{similar}]}}"#
+            )),
+            ("GET", "/list/object/items?search=Missing") => ok(r#"{"object":"list","data":[]}"#),
+            ("GET", p) if p == format!("/object/item/{GITHUB_ID}") => ok(&github),
+            ("GET", p) if p == format!("/object/totp/{GITHUB_ID}") => {
+                ok(r#"{"object":"string","data":"123456"}"#)
+            }
+            _ => (404, r#"{"success":false,"message":"Not found."}"#.into()),
+        }
+    }
+
+    fn start(unlocked: bool) -> TestServer {
+        let unlocked = Arc::new(AtomicBool::new(unlocked));
+        TestServer::start(move |req| vault(req, &unlocked))
+    }
+
+    fn config(url: &str, password: Option<&str>) -> BwConfig {
+        BwConfig {
+            bw_url: url.parse().unwrap(),
+            bw_password: password.map(|p| AuthToken::new(SecretString::from(p.to_string()))),
+            bw_max_concurrent: ConcurrencyLimit::new(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_fields() {
+        let server = start(true);
+        let provider = BwProvider::new(config(server.url(), None)).await.unwrap();
+
+        let refs: Vec<SecretReference> = [
+            "bw://GitHub/username",
+            "bw://GitHub/password",
+            "bw://GitHub/notes",
+            "bw://GitHub/Deploy%20Key",
+            &format!("bw://{GITHUB_ID}/totp"),
+            "bw://GitHub/missing-field",
+            "bw://Missing/password",
+        ]
+        .into_iter()
+        .map(|r| provider.parse(r).unwrap())
+        .collect();
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "octocat");
+        assert_eq!(map[&refs[1]].expose_secret(), "gh-pass");
+        assert_eq!(map[&refs[2]].expose_secret(), "recovery codes");
+        assert_eq!(map[&refs[3]].expose_secret(), "custom-value");
+        assert_eq!(map[&refs[4]].expose_secret(), "123456");
+        assert!(!map.contains_key(&refs[5]));
+        assert!(!map.contains_key(&refs[6]));
+
+        let requests = server.requests();
+        let count = |path: &str| requests.iter().filter(|r| r.path == path).count();
+        assert_eq!(count("/sync"), 1);
+        assert_eq!(count("/list/object/items?search=GitHub"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_with_password() {
+        let server = start(false);
+        let err = BwProvider::new(config(server.url(), None))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+
+        let err = BwProvider::new(config(server.url(), Some("wrong")))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+
+        let provider = BwProvider::new(config(server.url(), Some("master-pass")))
+            .await
+            .unwrap();
+        let refs = [provider.parse("bw://GitHub/password").unwrap()];
+        let map = provider.fetch_map(&refs).await.unwrap();
+        assert_eq!(map[&refs[0]].expose_secret(), "gh-pass");
+
+        let unlocks: Vec<Request> = server
+            .requests()
+            .into_iter()
+            .filter(|r| r.path == "/unlock")
+            .collect();
+        assert_eq!(unlocks.len(), 2);
+        assert_eq!(unlocks[1].header("content-type"), Some("application/json"));
+        assert_eq!(unlocks[1].body, r#"{"password":"master-pass"}"#);
+    }
+}
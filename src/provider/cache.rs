@@ -0,0 +1,229 @@
+//! In-memory cache of resolved secrets.
+//!
+//! Wraps a provider backend and keeps every resolved value for a fixed TTL,
+//! keyed by its reference, so repeated renders (e.g. every template save in
+//! watch mode) don't hit the backend API again. References which were not
+//! found are never cached, and neither are leased secrets, which may be
+//! revoked or replaced before the TTL runs out.
+//!
+//! Values are held as `SecretString`s, which zeroize their memory when
+//! dropped. Expired entries are evicted (and thereby zeroized) on the next
+//! fetch, and the whole cache is zeroized when the provider is dropped.
+
+use super::{ProviderError, ReferenceParser, SecretReference, SecretsProvider};
+use async_trait::async_trait;
+use secrecy::SecretString;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub struct CachingProvider {
+    inner: Arc<dyn SecretsProvider>,
+    ttl: Duration,
+    entries: Mutex<HashMap<SecretReference, CacheEntry>>,
+}
+
+struct CacheEntry {
+    value: SecretString,
+    expires: Instant,
+}
+
+impl CachingProvider {
+    pub fn new(inner: Arc<dyn SecretsProvider>, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Evicts expired entries, and splits the references into cached
+    /// values and references which need to be fetched.
+    fn lookup(
+        &self,
+        references: &[SecretReference],
+    ) -> (HashMap<SecretReference, SecretString>, Vec<SecretReference>) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| entry.expires > now);
+
+        let mut hits = HashMap::new();
+        let mut misses = Vec::new();
+        for reference in references {
+            match entries.get(reference) {
+                Some(entry) => {
+                    hits.insert(reference.clone(), entry.value.clone());
+                }
+                None => misses.push(reference.clone()),
+            }
+        }
+        (hits, misses)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for CachingProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let (mut resolved, misses) = self.lookup(references);
+        if misses.is_empty() {
+            tracing::debug!("all {} references served from cache", resolved.len());
+            return Ok(resolved);
+        }
+
+        let fetched = self.inner.fetch_map(&misses).await?;
+
+        let expires = Instant::now() + self.ttl;
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        for (reference, value) in fetched.iter().filter(|(r, _)| r.is_cacheable()) {
+            entries.insert(
+                reference.clone(),
+                CacheEntry {
+                    value: value.clone(),
+                    expires,
+                },
+            );
+        }
+        drop(entries);
+
+        resolved.extend(fetched);
+        Ok(resolved)
+    }
+
+    /// Drops every cached value once the backend reports them expired, so
+    /// the next fetch reaches the backend again.
+    async fn expired(&self) {
+        self.inner.expired().await;
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    async fn holds_leases(&self) -> bool {
+        self.inner.holds_leases().await
+    }
+
+    async fn shutdown(&self) {
+        self.inner.shutdown().await
+    }
+}
+
+impl ReferenceParser for CachingProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        self.inner.parse(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+
+    /// Backend which resolves every reference except `missing`, to a value
+    /// which changes with every call.
+    struct CountingBackend {
+        calls: Mutex<Vec<Vec<SecretReference>>>,
+    }
+
+    impl ReferenceParser for CountingBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for CountingBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            let mut calls = self.calls.lock().unwrap();
+            calls.push(references.to_vec());
+            let n = calls.len();
+            Ok(references
+                .iter()
+                .filter(|r| r.to_string() != "missing")
+                .map(|r| (r.clone(), SecretString::from(format!("{r}-{n}"))))
+                .collect())
+        }
+    }
+
+    fn mock(raw: &str) -> SecretReference {
+        SecretReference::Mock(raw.to_string())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn serves_cached_values_until_expiry() {
+        let backend = Arc::new(CountingBackend {
+            calls: Mutex::new(Vec::new()),
+        });
+        let cache = CachingProvider::new(backend.clone(), Duration::from_secs(60));
+
+        let map = cache
+            .fetch_map(&[mock("a"), mock("missing")])
+            .await
+            .unwrap();
+        assert_eq!(map[&mock("a")].expose_secret(), "a-1");
+        assert!(!map.contains_key(&mock("missing")));
+
+        // Only uncached references reach the backend.
+        let map = cache
+            .fetch_map(&[mock("a"), mock("b"), mock("missing")])
+            .await
+            .unwrap();
+        assert_eq!(map[&mock("a")].expose_secret(), "a-1");
+        assert_eq!(map[&mock("b")].expose_secret(), "b-2");
+        assert_eq!(
+            backend.calls.lock().unwrap()[1],
+            vec![mock("b"), mock("missing")]
+        );
+
+        let map = cache.fetch_map(&[mock("a")]).await.unwrap();
+        assert_eq!(map[&mock("a")].expose_secret(), "a-1");
+        assert_eq!(backend.calls.lock().unwrap().len(), 2);
+
+        tokio::time::advance(Duration::from_secs(61)).await;
+        let map = cache.fetch_map(&[mock("a")]).await.unwrap();
+        assert_eq!(map[&mock("a")].expose_secret(), "a-3");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn evicts_expired_entries() {
+        let backend = Arc::new(CountingBackend {
+            calls: Mutex::new(Vec::new()),
+        });
+        let cache = CachingProvider::new(backend, Duration::from_secs(60));
+
+        cache.fetch_map(&[mock("a"), mock("b")]).await.unwrap();
+        tokio::time::advance(Duration::from_secs(61)).await;
+        cache.fetch_map(&[mock("c")]).await.unwrap();
+
+        let entries = cache.entries.lock().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries.contains_key(&mock("c")));
+    }
+
+    #[cfg(feature = "bao")]
+    #[tokio::test(start_paused = true)]
+    async fn never_caches_leased_secrets() {
+        use crate::provider::references::parse_bao_reference;
+
+        let backend = Arc::new(CountingBackend {
+            calls: Mutex::new(Vec::new()),
+        });
+        let cache = CachingProvider::new(backend.clone(), Duration::from_secs(60));
+        let leased = parse_bao_reference("bao+dyn://database/creds/app#password").unwrap();
+        let refs = [mock("a"), leased.clone()];
+
+        cache.fetch_map(&refs).await.unwrap();
+        let map = cache.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&mock("a")].expose_secret(), "a-1");
+        assert_eq!(map[&leased].expose_secret(), format!("{leased}-2"));
+        assert_eq!(backend.calls.lock().unwrap()[1], vec![leased]);
+    }
+}
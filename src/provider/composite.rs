@@ -0,0 +1,264 @@
+//! Routes secret references across multiple provider backends.
+//!
+//! Each reference is owned by the first backend (in configuration order)
+//! which recognizes its syntax. The owner is recorded when the reference is
+//! parsed, so it is never parsed again to be routed. A batch is split per
+//! backend, the sub-batches are fetched concurrently, and the results are
+//! merged.
+
+use super::{ProviderError, ReferenceParser, SecretReference, SecretsProvider};
+use async_trait::async_trait;
+use futures::future::{join_all, select_all, try_join_all};
+use secrecy::SecretString;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub struct CompositeProvider {
+    backends: Vec<Arc<dyn SecretsProvider>>,
+    /// Index of the backend which produced each parsed reference
+    owners: Mutex<HashMap<SecretReference, usize>>,
+}
+
+impl CompositeProvider {
+    pub fn new(backends: Vec<Arc<dyn SecretsProvider>>) -> Self {
+        Self {
+            backends,
+            owners: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Index of the backend which owns the given reference, if it was
+    /// parsed by this provider.
+    fn route(&self, reference: &SecretReference) -> Option<usize> {
+        let owners = self.owners.lock().unwrap_or_else(|e| e.into_inner());
+        owners.get(reference).copied()
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for CompositeProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let mut batches: Vec<Vec<SecretReference>> = vec![Vec::new(); self.backends.len()];
+        for reference in references {
+            match self.route(reference) {
+                Some(idx) => batches[idx].push(reference.clone()),
+                None => tracing::warn!("no provider recognized reference '{}'", reference),
+            }
+        }
+
+        let fetches = self
+            .backends
+            .iter()
+            .zip(batches.iter())
+            .filter(|(_, batch)| !batch.is_empty())
+            .map(|(backend, batch)| backend.fetch_map(batch));
+
+        let mut merged = HashMap::with_capacity(references.len());
+        for map in try_join_all(fetches).await? {
+            merged.extend(map);
+        }
+        Ok(merged)
+    }
+
+    async fn expired(&self) {
+        if self.backends.is_empty() {
+            return std::future::pending().await;
+        }
+        select_all(self.backends.iter().map(|b| b.expired())).await;
+    }
+
+    async fn holds_leases(&self) -> bool {
+        join_all(self.backends.iter().map(|b| b.holds_leases()))
+            .await
+            .into_iter()
+            .any(|held| held)
+    }
+
+    async fn shutdown(&self) {
+        join_all(self.backends.iter().map(|b| b.shutdown())).await;
+    }
+}
+
+impl ReferenceParser for CompositeProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        let (idx, reference) = self
+            .backends
+            .iter()
+            .enumerate()
+            .find_map(|(idx, backend)| backend.parse(raw).map(|r| (idx, r)))?;
+        let mut owners = self.owners.lock().unwrap_or_else(|e| e.into_inner());
+        owners.entry(reference.clone()).or_insert(idx);
+        Some(reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+    use std::sync::Mutex;
+
+    /// Backend which recognizes references with a fixed prefix and
+    /// records every batch it is asked to resolve.
+    struct PrefixBackend {
+        prefix: &'static str,
+        fail: bool,
+        calls: Mutex<Vec<Vec<SecretReference>>>,
+    }
+
+    impl PrefixBackend {
+        fn new(prefix: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                prefix,
+                fail: false,
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn failing(prefix: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                prefix,
+                fail: true,
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    impl ReferenceParser for PrefixBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            raw.starts_with(self.prefix)
+                .then(|| SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for PrefixBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            self.calls.lock().unwrap().push(references.to_vec());
+            if self.fail {
+                return Err(ProviderError::Unauthorized("denied".into()));
+            }
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::new(format!("{}-value", r).into())))
+                .collect())
+        }
+    }
+
+    fn mock(raw: &str) -> SecretReference {
+        SecretReference::Mock(raw.to_string())
+    }
+
+    fn parse_all(composite: &CompositeProvider, raws: &[&str]) -> Vec<SecretReference> {
+        raws.iter()
+            .map(|raw| composite.parse(raw).unwrap_or_else(|| mock(raw)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn splits_batch_across_backends() {
+        let a = PrefixBackend::new("a:");
+        let b = PrefixBackend::new("b:");
+        let composite = CompositeProvider::new(vec![a.clone(), b.clone()]);
+
+        let refs = parse_all(&composite, &["a:1", "b:1", "a:2", "z:1"]);
+        let map = composite.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[&mock("b:1")].expose_secret(), "b:1-value");
+        assert_eq!(
+            *a.calls.lock().unwrap(),
+            vec![vec![mock("a:1"), mock("a:2")]]
+        );
+        assert_eq!(*b.calls.lock().unwrap(), vec![vec![mock("b:1")]]);
+    }
+
+    #[tokio::test]
+    async fn first_matching_backend_wins() {
+        let first = PrefixBackend::new("shared:");
+        let second = PrefixBackend::new("shared:");
+        let composite = CompositeProvider::new(vec![first.clone(), second.clone()]);
+
+        assert_eq!(composite.parse("shared:x"), Some(mock("shared:x")));
+        assert_eq!(composite.parse("other:x"), None);
+
+        composite.fetch_map(&[mock("shared:x")]).await.unwrap();
+        assert_eq!(first.calls.lock().unwrap().len(), 1);
+        assert!(second.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn skips_backends_without_references() {
+        let a = PrefixBackend::new("a:");
+        let b = PrefixBackend::failing("b:");
+        let composite = CompositeProvider::new(vec![a.clone(), b.clone()]);
+
+        let refs = parse_all(&composite, &["a:1"]);
+        composite.fetch_map(&refs).await.unwrap();
+        assert!(b.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn propagates_backend_errors() {
+        let a = PrefixBackend::new("a:");
+        let b = PrefixBackend::failing("b:");
+        let composite = CompositeProvider::new(vec![a, b]);
+
+        let refs = parse_all(&composite, &["a:1", "b:1"]);
+        let err = composite.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)));
+    }
+
+    /// Backend which recognizes every reference, and counts how often it
+    /// was asked to.
+    struct CountingParser {
+        parsed: Mutex<usize>,
+    }
+
+    impl ReferenceParser for CountingParser {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            *self.parsed.lock().unwrap() += 1;
+            Some(mock(raw))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for CountingParser {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::from("value")))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_without_parsing_again() {
+        let a = PrefixBackend::new("a:");
+        let counting = Arc::new(CountingParser {
+            parsed: Mutex::new(0),
+        });
+        let composite = CompositeProvider::new(vec![a.clone(), counting.clone()]);
+
+        let refs = parse_all(&composite, &["a:1", "b:1"]);
+        assert_eq!(*counting.parsed.lock().unwrap(), 1);
+
+        let map = composite.fetch_map(&refs).await.unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(*counting.parsed.lock().unwrap(), 1);
+        assert_eq!(*a.calls.lock().unwrap(), vec![vec![mock("a:1")]]);
+
+        // A reference this provider never parsed has no owner.
+        let map = composite.fetch_map(&[mock("c:1")]).await.unwrap();
+        assert!(map.is_empty());
+    }
+}
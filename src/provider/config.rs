@@ -1,10 +1,30 @@
+#[cfg(feature = "aws")]
+pub mod aws;
+#[cfg(feature = "azure")]
+pub mod azure;
 #[cfg(feature = "bao")]
 pub mod bao;
+#[cfg(feature = "bw")]
+pub mod bw;
 #[cfg(feature = "bws")]
 pub mod bws;
 #[cfg(feature = "connect")]
 pub mod connect;
+#[cfg(feature = "doppler")]
+pub mod doppler;
+#[cfg(feature = "exec-provider")]
+pub mod exec;
+#[cfg(feature = "gcp")]
+pub mod gcp;
+#[cfg(feature = "http")]
+pub mod http;
 #[cfg(feature = "infisical")]
 pub mod infisical;
+#[cfg(feature = "k8s")]
+pub mod k8s;
+#[cfg(feature = "keepass")]
+pub mod keepass;
 #[cfg(feature = "op")]
 pub mod op;
+#[cfg(feature = "sops")]
+pub mod sops;
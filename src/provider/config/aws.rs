@@ -0,0 +1,165 @@
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, ReferenceParser, SecretReference, ServerUrl,
+    Signature, references::parse_aws_reference,
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AwsConfigError {
+    #[error(
+        "invalid region '{0}': expected lowercase ascii letters, digits and '-' (e.g. 'us-east-1')"
+    )]
+    Region(String),
+}
+
+/// An AWS region name (e.g. `us-east-1`).
+///
+/// Used both in the default service hostnames and in the SigV4
+/// credential scope, so it is restricted to the characters AWS uses.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AwsRegion(String);
+
+impl AwsRegion {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for AwsRegion {
+    type Error = AwsConfigError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let valid = !value.is_empty()
+            && !value.starts_with('-')
+            && !value.ends_with('-')
+            && value
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !valid {
+            return Err(AwsConfigError::Region(value));
+        }
+        Ok(Self(value))
+    }
+}
+
+impl FromStr for AwsRegion {
+    type Err = AwsConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s.to_string())
+    }
+}
+
+impl From<AwsRegion> for String {
+    fn from(region: AwsRegion) -> Self {
+        region.0
+    }
+}
+
+impl fmt::Display for AwsRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AwsConfig {
+    pub aws_region: AwsRegion,
+    pub aws_access_key_id: AuthToken,
+    pub aws_secret_access_key: AuthToken,
+    pub aws_session_token: Option<AuthToken>,
+    pub aws_endpoint: Option<ServerUrl>,
+    pub aws_max_concurrent: ConcurrencyLimit,
+}
+
+impl ReferenceParser for AwsConfig {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        parse_aws_reference(raw)
+    }
+}
+
+#[async_trait]
+impl Signature for AwsConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        // Temporary credentials are usually rotated together, so any
+        // change to one of them should rebuild the client.
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.aws_access_key_id.signature().await?.hash(&mut hasher);
+        self.aws_secret_access_key
+            .signature()
+            .await?
+            .hash(&mut hasher);
+        if let Some(token) = &self.aws_session_token {
+            token.signature().await?.hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "AwsConfig")]
+pub struct AwsArgs {
+    /// AWS region of the secrets and parameters (e.g. `us-east-1`)
+    #[arg(long, env = "AWS_REGION")]
+    pub aws_region: Option<AwsRegion>,
+
+    /// AWS access key ID
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix
+    #[arg(long, env = "AWS_ACCESS_KEY_ID", hide_env_values = true)]
+    pub aws_access_key_id: Option<AuthToken>,
+
+    /// AWS secret access key
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix
+    #[arg(long, env = "AWS_SECRET_ACCESS_KEY", hide_env_values = true)]
+    pub aws_secret_access_key: Option<AuthToken>,
+
+    /// AWS session token, for temporary credentials
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix
+    #[arg(long, env = "AWS_SESSION_TOKEN", hide_env_values = true)]
+    #[locket(optional)]
+    pub aws_session_token: Option<AuthToken>,
+
+    /// Override the AWS API endpoint (e.g. a VPC endpoint or a local mock)
+    ///
+    /// By default the regional endpoint of each service is used.
+    #[arg(long, env = "AWS_ENDPOINT_URL")]
+    #[locket(optional)]
+    pub aws_endpoint: Option<ServerUrl>,
+
+    /// Maximum allowed concurrent requests to the AWS API
+    #[arg(long, env = "AWS_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub aws_max_concurrent: Option<ConcurrencyLimit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_region_validation() {
+        for good in ["us-east-1", "eu-central-2", "us-gov-west-1"] {
+            assert!(AwsRegion::from_str(good).is_ok(), "'{good}' should parse");
+        }
+        for bad in ["", "US-EAST-1", "us east 1", "-us-east-1", "us.east.1"] {
+            assert!(
+                AwsRegion::from_str(bad).is_err(),
+                "'{bad}' should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, ServerUrl, Signature,
+    references::{AkvReference, HasReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AzureConfig {
+    pub azure_tenant_id: String,
+    pub azure_client_id: Uuid,
+    pub azure_client_secret: AuthToken,
+    pub azure_authority_url: ServerUrl,
+    pub azure_vault_domain: String,
+    pub azure_vault_url: Option<ServerUrl>,
+    pub azure_max_concurrent: ConcurrencyLimit,
+}
+
+impl HasReference for AzureConfig {
+    type Reference = AkvReference;
+}
+
+#[async_trait]
+impl Signature for AzureConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        self.azure_client_secret.signature().await
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "AzureConfig")]
+pub struct AzureArgs {
+    /// Microsoft Entra tenant ID (or domain) of the service principal
+    #[arg(long, env = "AZURE_TENANT_ID")]
+    pub azure_tenant_id: Option<String>,
+
+    /// Application (client) ID of the service principal
+    #[arg(long, env = "AZURE_CLIENT_ID")]
+    pub azure_client_id: Option<Uuid>,
+
+    /// Client secret of the service principal
+    ///
+    /// Either provide the secret directly or via a file with `file:` prefix
+    #[arg(long, env = "AZURE_CLIENT_SECRET", hide_env_values = true)]
+    pub azure_client_secret: Option<AuthToken>,
+
+    /// Microsoft Entra authority used to acquire access tokens
+    #[arg(long, env = "AZURE_AUTHORITY_HOST")]
+    #[locket(default = "https://login.microsoftonline.com")]
+    pub azure_authority_url: Option<ServerUrl>,
+
+    /// DNS suffix of Key Vault in the target cloud (e.g. `vault.azure.cn`)
+    ///
+    /// Vaults are reached at `https://<vault-name>.<domain>`, and tokens are
+    /// requested for the `https://<domain>` resource.
+    #[arg(long, env = "AZURE_VAULT_DOMAIN")]
+    #[locket(default = "vault.azure.net")]
+    pub azure_vault_domain: Option<String>,
+
+    /// Override the Key Vault URL (e.g. a private endpoint or a local mock)
+    ///
+    /// When set, every vault is reached through this URL instead of its own hostname.
+    #[arg(long, env = "AZURE_VAULT_URL")]
+    #[locket(optional)]
+    pub azure_vault_url: Option<ServerUrl>,
+
+    /// Maximum allowed concurrent requests to Key Vault
+    #[arg(long, env = "AZURE_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub azure_max_concurrent: Option<ConcurrencyLimit>,
+}
@@ -1,14 +1,20 @@
+use crate::path::AbsolutePath;
 use crate::provider::{
-    AuthToken, ConcurrencyLimit, ProviderError, ServerUrl, Signature,
-    references::{BaoMount, BaoReference, HasReference},
+    AuthToken, ConcurrencyLimit, ProviderError, ReferenceParser, SecretReference, ServerUrl,
+    Signature,
+    references::{BaoMount, parse_bao_reference},
 };
 use async_trait::async_trait;
-use clap::Args;
+use clap::{Args, ValueEnum};
 use locket_derive::LayeredConfig;
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
+use tokio::sync::OnceCell;
 
 #[derive(Debug, Error)]
 pub enum BaoConfigError {
@@ -16,6 +22,14 @@ pub enum BaoConfigError {
         "invalid namespace '{0}': expected non-empty '/' separated segments of visible ascii, relative to root (no leading '/')"
     )]
     Namespace(String),
+
+    #[error("invalid renewal fraction '{0}': expected a number between 0 and 1 (e.g. 0.67)")]
+    RenewFraction(String),
+
+    #[error(
+        "invalid KV mount '{0}': expected '<mount>=<version>' with version 1 or 2 (e.g. 'legacy=1')"
+    )]
+    KvMount(String),
 }
 
 /// An OpenBao / Vault namespace path (e.g. `admin/team1`).
@@ -78,24 +92,320 @@ impl fmt::Display for BaoNamespace {
     }
 }
 
+/// The fraction of a lease's duration (or a certificate's lifetime) after
+/// which it is renewed or re-issued, e.g. `0.67`.
+///
+/// Held in thousandths so that configurations stay comparable and hashable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct RenewFraction(u16);
+
+impl RenewFraction {
+    pub fn as_f64(self) -> f64 {
+        f64::from(self.0) / 1000.0
+    }
+}
+
+impl Default for RenewFraction {
+    fn default() -> Self {
+        Self(670)
+    }
+}
+
+impl TryFrom<f64> for RenewFraction {
+    type Error = BaoConfigError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        let thousandths = (value * 1000.0).round();
+        if !(1.0..1000.0).contains(&thousandths) {
+            return Err(BaoConfigError::RenewFraction(value.to_string()));
+        }
+        Ok(Self(thousandths as u16))
+    }
+}
+
+impl From<RenewFraction> for f64 {
+    fn from(fraction: RenewFraction) -> Self {
+        fraction.as_f64()
+    }
+}
+
+impl FromStr for RenewFraction {
+    type Err = BaoConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: f64 = s
+            .trim()
+            .parse()
+            .map_err(|_| BaoConfigError::RenewFraction(s.to_string()))?;
+        Self::try_from(value)
+    }
+}
+
+impl fmt::Display for RenewFraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_f64())
+    }
+}
+
+/// The version of a KV secrets engine, which determines its API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KvVersion {
+    /// Unversioned, secrets are read from `<mount>/<path>`
+    V1,
+    /// Versioned, secrets are read from `<mount>/data/<path>`
+    V2,
+}
+
+impl fmt::Display for KvVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::V1 => f.write_str("1"),
+            Self::V2 => f.write_str("2"),
+        }
+    }
+}
+
+/// A KV secrets engine mount and its version (e.g. `legacy=1`).
+///
+/// Configured mounts are used as-is, instead of detecting them from the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct BaoKvMount {
+    pub mount: BaoMount,
+    pub version: KvVersion,
+}
+
+impl FromStr for BaoKvMount {
+    type Err = BaoConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || BaoConfigError::KvMount(s.to_string());
+        let (mount, version) = s.rsplit_once('=').ok_or_else(invalid)?;
+        let version = match version.trim() {
+            "1" => KvVersion::V1,
+            "2" => KvVersion::V2,
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            mount: BaoMount::from_str(mount.trim()).map_err(|_| invalid())?,
+            version,
+        })
+    }
+}
+
+impl TryFrom<String> for BaoKvMount {
+    type Error = BaoConfigError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::from_str(&value)
+    }
+}
+
+impl From<BaoKvMount> for String {
+    fn from(mount: BaoKvMount) -> Self {
+        mount.to_string()
+    }
+}
+
+impl fmt::Display for BaoKvMount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.mount, self.version)
+    }
+}
+
+/// The auth method locket logs in to OpenBao / Vault with.
+#[derive(Debug, Serialize, Default, Deserialize, Clone, PartialEq, Eq, Hash, ValueEnum, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum BaoAuthMethod {
+    /// AppRole, with `--bao-role-id` and `--bao-secret-id`
+    #[default]
+    #[value(name = "approle")]
+    AppRole,
+    /// An existing client token, with `--bao-token`
+    Token,
+    /// A Kubernetes service account token, with `--bao-auth-role`
+    Kubernetes,
+    /// A JWT / OIDC token, with `--bao-auth-role` and `--bao-jwt`
+    #[value(alias = "oidc")]
+    #[serde(alias = "oidc")]
+    Jwt,
+    /// A TLS client certificate, with `--bao-client-cert` and `--bao-client-key`
+    Cert,
+    /// A username and password, with `--bao-username` and `--bao-password`
+    Userpass,
+}
+
+impl BaoAuthMethod {
+    /// The path the auth method is mounted at unless configured otherwise.
+    pub fn default_mount(self) -> BaoMount {
+        let mount = match self {
+            Self::AppRole => "approle",
+            Self::Token => "token",
+            Self::Kubernetes => "kubernetes",
+            Self::Jwt => "jwt",
+            Self::Cert => "cert",
+            Self::Userpass => "userpass",
+        };
+        BaoMount::from_str(mount).expect("default mounts are valid")
+    }
+}
+
+impl fmt::Display for BaoAuthMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .fmt(f)
+    }
+}
+
+/// An AppRole secret ID, which may be given as a response-wrapping token.
+///
+/// A wrapping token can only be unwrapped once, but the provider is built
+/// again whenever its configuration changes or its creation is retried. The
+/// unwrapped secret ID is therefore kept here, and shared by every clone of
+/// the configuration, for as long as the same wrapping token is configured.
+#[derive(Debug, Clone)]
+pub struct SecretId {
+    token: AuthToken,
+    unwrapped: Arc<Mutex<Option<Unwrapped>>>,
+}
+
+#[derive(Debug)]
+struct Unwrapped {
+    wrapping_token: SecretString,
+    secret_id: Arc<OnceCell<SecretString>>,
+}
+
+impl SecretId {
+    pub fn token(&self) -> &AuthToken {
+        &self.token
+    }
+
+    /// Returns the secret ID wrapped by the token, calling `unwrap` only if
+    /// this wrapping token was not unwrapped successfully before.
+    pub(crate) async fn unwrapped<F, Fut>(
+        &self,
+        wrapping_token: &SecretString,
+        unwrap: F,
+    ) -> Result<SecretString, ProviderError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<SecretString, ProviderError>>,
+    {
+        let cell = {
+            let mut unwrapped = self.unwrapped.lock().unwrap_or_else(|e| e.into_inner());
+            match unwrapped.as_ref() {
+                Some(u) if u.wrapping_token.expose_secret() == wrapping_token.expose_secret() => {
+                    u.secret_id.clone()
+                }
+                // A replaced wrapping token is unwrapped anew.
+                _ => {
+                    let secret_id = Arc::new(OnceCell::new());
+                    *unwrapped = Some(Unwrapped {
+                        wrapping_token: wrapping_token.clone(),
+                        secret_id: secret_id.clone(),
+                    });
+                    secret_id
+                }
+            }
+        };
+        cell.get_or_try_init(unwrap).await.cloned()
+    }
+}
+
+impl From<AuthToken> for SecretId {
+    fn from(token: AuthToken) -> Self {
+        Self {
+            token,
+            unwrapped: Arc::default(),
+        }
+    }
+}
+
+impl PartialEq for SecretId {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token
+    }
+}
+
+impl Eq for SecretId {}
+
+impl Hash for SecretId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.token.hash(state);
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BaoConfig {
     pub bao_url: ServerUrl,
     pub bao_namespace: Option<BaoNamespace>,
-    pub bao_auth_mount: BaoMount,
-    pub bao_role_id: String,
-    pub bao_secret_id: AuthToken,
+    pub bao_auth_method: BaoAuthMethod,
+    pub bao_auth_mount: Option<BaoMount>,
+    pub bao_role_id: Option<String>,
+    pub bao_secret_id: Option<SecretId>,
+    pub bao_secret_id_wrapped: bool,
+    pub bao_token: Option<AuthToken>,
+    pub bao_auth_role: Option<String>,
+    pub bao_jwt: Option<AuthToken>,
+    pub bao_client_cert: Option<AbsolutePath>,
+    pub bao_client_key: Option<AuthToken>,
+    pub bao_username: Option<String>,
+    pub bao_password: Option<AuthToken>,
+    pub bao_kv_mount: Vec<BaoKvMount>,
     pub bao_max_concurrent: ConcurrencyLimit,
+    pub bao_renew_at: RenewFraction,
 }
 
-impl HasReference for BaoConfig {
-    type Reference = BaoReference;
+impl BaoConfig {
+    /// The mount of the configured auth method.
+    pub fn auth_mount(&self) -> BaoMount {
+        self.bao_auth_mount
+            .clone()
+            .unwrap_or_else(|| self.bao_auth_method.default_mount())
+    }
+}
+
+impl ReferenceParser for BaoConfig {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        parse_bao_reference(raw)
+    }
 }
 
 #[async_trait]
 impl Signature for BaoConfig {
     async fn signature(&self) -> Result<u64, ProviderError> {
-        self.bao_secret_id.signature().await
+        // Only the credentials of the configured method are ever set, so
+        // watching all of them picks up rotation of whichever is in use.
+        // The client certificate is watched too, since it may be renewed
+        // for the same key.
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        if let Some(path) = &self.bao_client_cert {
+            tokio::fs::read(path.as_path())
+                .await
+                .map_err(|e| {
+                    ProviderError::InvalidConfig(format!(
+                        "failed to read client certificate {}: {e}",
+                        path.as_path().display()
+                    ))
+                })?
+                .hash(&mut hasher);
+        }
+        for credential in [
+            self.bao_secret_id.as_ref().map(SecretId::token),
+            self.bao_token.as_ref(),
+            self.bao_jwt.as_ref(),
+            self.bao_client_key.as_ref(),
+            self.bao_password.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            credential.signature().await?.hash(&mut hasher);
+        }
+        Ok(hasher.finish())
     }
 }
 
@@ -114,25 +424,118 @@ pub struct BaoArgs {
     #[locket(optional)]
     pub bao_namespace: Option<BaoNamespace>,
 
-    /// Auth mount path where the AppRole auth method is enabled
+    /// Auth method used to log in
+    #[arg(long, env = "BAO_AUTH_METHOD")]
+    #[locket(default = BaoAuthMethod::AppRole)]
+    pub bao_auth_method: Option<BaoAuthMethod>,
+
+    /// Optional: Auth mount path where the auth method is enabled
+    ///
+    /// Defaults to the name of the auth method (e.g. `approle` or `kubernetes`)
     #[arg(long, env = "BAO_AUTH_MOUNT")]
-    #[locket(default = "approle")]
+    #[locket(optional)]
     pub bao_auth_mount: Option<BaoMount>,
 
     /// AppRole Role ID
     #[arg(long, env = "BAO_ROLE_ID")]
+    #[locket(optional)]
     pub bao_role_id: Option<String>,
 
     /// AppRole Secret ID
     ///
     /// Either provide the value directly or via a file with `file:` prefix
     #[arg(long, env = "BAO_SECRET_ID", hide_env_values = true)]
+    #[locket(optional)]
     pub bao_secret_id: Option<AuthToken>,
 
+    /// Treat `--bao-secret-id` as a response-wrapping token
+    ///
+    /// The wrapped Secret ID is unwrapped once at startup, after checking
+    /// that it was created by the AppRole auth mount. A wrapping token which
+    /// was already used is rejected, since it may have been intercepted.
+    #[arg(
+        long,
+        env = "BAO_SECRET_ID_WRAPPED",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        require_equals = false
+    )]
+    #[locket(default = false)]
+    pub bao_secret_id_wrapped: Option<bool>,
+
+    /// Client token, for the `token` auth method
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix.
+    /// A token file is read again whenever the token expires or is rejected.
+    #[arg(long, env = "BAO_TOKEN", hide_env_values = true)]
+    #[locket(optional)]
+    pub bao_token: Option<AuthToken>,
+
+    /// Role to log in as, for the `kubernetes`, `jwt` and `cert` auth methods
+    #[arg(long, env = "BAO_AUTH_ROLE")]
+    #[locket(optional)]
+    pub bao_auth_role: Option<String>,
+
+    /// JWT to log in with, for the `kubernetes` and `jwt` auth methods
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix.
+    /// The file is read again on every login, so projected tokens can rotate.
+    /// The `kubernetes` method defaults to the pod's service account token.
+    #[arg(long, env = "BAO_JWT", hide_env_values = true)]
+    #[locket(optional)]
+    pub bao_jwt: Option<AuthToken>,
+
+    /// Path to a PEM client certificate (chain), for the `cert` auth method
+    #[arg(long, env = "BAO_CLIENT_CERT")]
+    #[locket(optional)]
+    pub bao_client_cert: Option<AbsolutePath>,
+
+    /// PEM private key for the client certificate, for the `cert` auth method
+    ///
+    /// Either provide the key directly or via a file with `file:` prefix
+    #[arg(long, env = "BAO_CLIENT_KEY", hide_env_values = true)]
+    #[locket(optional)]
+    pub bao_client_key: Option<AuthToken>,
+
+    /// Username, for the `userpass` auth method
+    #[arg(long, env = "BAO_USERNAME")]
+    #[locket(optional)]
+    pub bao_username: Option<String>,
+
+    /// Password, for the `userpass` auth method
+    ///
+    /// Either provide the value directly or via a file with `file:` prefix
+    #[arg(long, env = "BAO_PASSWORD", hide_env_values = true)]
+    #[locket(optional)]
+    pub bao_password: Option<AuthToken>,
+
+    /// KV secrets engine mounts and their versions, as `<mount>=<version>`
+    ///
+    /// Mounts which are not listed are detected from the server through
+    /// `sys/internal/ui/mounts`, falling back to a KV v2 mount at the first
+    /// segment of the reference if detection is not permitted.
+    ///
+    /// Example: `--bao-kv-mount legacy=1 --bao-kv-mount teams/payments/kv=2`
+    #[arg(
+        long,
+        env = "BAO_KV_MOUNT",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub bao_kv_mount: Vec<BaoKvMount>,
+
     /// Maximum allowed concurrent requests to the OpenBao/Vault API
     #[arg(long, env = "BAO_MAX_CONCURRENT")]
     #[locket(default = ConcurrencyLimit::new(20))]
     pub bao_max_concurrent: Option<ConcurrencyLimit>,
+
+    /// Fraction of a lease's duration, or a certificate's lifetime, after
+    /// which dynamic secrets are renewed and certificates are re-issued
+    #[arg(long, env = "BAO_RENEW_AT")]
+    #[locket(default = RenewFraction::default())]
+    pub bao_renew_at: Option<RenewFraction>,
 }
 
 #[cfg(test)]
@@ -146,6 +549,36 @@ mod tests {
         assert_eq!(ns, BaoNamespace::from_str("admin/team1").unwrap());
     }
 
+    #[test]
+    fn test_renew_fraction_bounds() {
+        assert_eq!(RenewFraction::from_str("0.5").unwrap().as_f64(), 0.5);
+        assert_eq!(RenewFraction::default().to_string(), "0.67");
+        for bad in ["0", "1", "1.5", "-0.2", "half"] {
+            assert!(
+                RenewFraction::from_str(bad).is_err(),
+                "'{bad}' should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_kv_mount_parse() {
+        let mount = BaoKvMount::from_str("teams/payments/kv=1").unwrap();
+        assert_eq!(mount.mount.as_str(), "teams/payments/kv");
+        assert_eq!(mount.version, KvVersion::V1);
+        assert_eq!(mount.to_string(), "teams/payments/kv=1");
+        assert_eq!(
+            BaoKvMount::from_str("secret/=2").unwrap().to_string(),
+            "secret=2"
+        );
+        for bad in ["secret", "secret=3", "=2", "secret=v2"] {
+            assert!(
+                BaoKvMount::from_str(bad).is_err(),
+                "'{bad}' should be rejected"
+            );
+        }
+    }
+
     #[test]
     fn test_namespace_rejects_malformed() {
         for bad in ["", "/admin", "admin//team1", "admin /team1", "admin\nteam1"] {
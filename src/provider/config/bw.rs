@@ -0,0 +1,54 @@
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, ServerUrl, Signature,
+    references::{BwReference, HasReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BwConfig {
+    pub bw_url: ServerUrl,
+    pub bw_password: Option<AuthToken>,
+    pub bw_max_concurrent: ConcurrencyLimit,
+}
+
+impl HasReference for BwConfig {
+    type Reference = BwReference;
+}
+
+#[async_trait]
+impl Signature for BwConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        match &self.bw_password {
+            Some(password) => password.signature().await,
+            None => Ok(0),
+        }
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "BwConfig")]
+pub struct BwArgs {
+    /// URL of the `bw serve` API
+    #[arg(long, env = "BW_URL")]
+    #[locket(default = "http://localhost:8087")]
+    pub bw_url: Option<ServerUrl>,
+
+    /// Optional: Master password used to unlock the vault when it is locked
+    ///
+    /// Either provide the password directly or via a file with `file:` prefix.
+    /// Without it, the vault must already be unlocked
+    #[arg(long, env = "BW_PASSWORD", hide_env_values = true)]
+    #[locket(optional)]
+    pub bw_password: Option<AuthToken>,
+
+    /// Maximum allowed concurrent requests to the `bw serve` API
+    #[arg(long, env = "BW_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub bw_max_concurrent: Option<ConcurrencyLimit>,
+}
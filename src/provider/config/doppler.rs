@@ -0,0 +1,49 @@
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, ServerUrl, Signature,
+    references::{DopplerReference, HasReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DopplerConfig {
+    pub doppler_token: AuthToken,
+    pub doppler_url: ServerUrl,
+    pub doppler_max_concurrent: ConcurrencyLimit,
+}
+
+impl HasReference for DopplerConfig {
+    type Reference = DopplerReference;
+}
+
+#[async_trait]
+impl Signature for DopplerConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        self.doppler_token.signature().await
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "DopplerConfig")]
+pub struct DopplerArgs {
+    /// Doppler service token
+    ///
+    /// Either provide the token directly or via a file with `file:` prefix
+    #[arg(long, env = "DOPPLER_TOKEN", hide_env_values = true)]
+    pub doppler_token: Option<AuthToken>,
+
+    /// Base URL of the Doppler API
+    #[arg(long, env = "DOPPLER_URL")]
+    #[locket(default = "https://api.doppler.com")]
+    pub doppler_url: Option<ServerUrl>,
+
+    /// Maximum allowed concurrent requests to the Doppler API
+    #[arg(long, env = "DOPPLER_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub doppler_max_concurrent: Option<ConcurrencyLimit>,
+}
@@ -0,0 +1,72 @@
+use crate::path::AbsolutePath;
+use crate::provider::{
+    AuthToken, ProviderError, Signature,
+    references::{ExecReference, ExecScheme, ReferenceParser, SecretReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecProviderConfig {
+    pub exec_command: AbsolutePath,
+    pub exec_scheme: ExecScheme,
+    pub exec_token: Option<AuthToken>,
+    pub exec_pass_env: Vec<String>,
+}
+
+impl ReferenceParser for ExecProviderConfig {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        ExecReference::parse(&self.exec_scheme, raw).map(SecretReference::from)
+    }
+}
+
+#[async_trait]
+impl Signature for ExecProviderConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        match &self.exec_token {
+            Some(token) => token.signature().await,
+            None => Ok(0),
+        }
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "ExecProviderConfig")]
+pub struct ExecProviderArgs {
+    /// Absolute path to the plugin executable
+    ///
+    /// The plugin must speak the locket plugin protocol on its stdin and
+    /// stdout. PATH is never searched.
+    #[arg(long, env = "LOCKET_EXEC_COMMAND")]
+    pub exec_command: Option<AbsolutePath>,
+
+    /// Reference scheme handled by the plugin, e.g. `pass` for `pass://...`
+    #[arg(long, env = "LOCKET_EXEC_SCHEME")]
+    pub exec_scheme: Option<ExecScheme>,
+
+    /// Optional: Token handed to the plugin during the init handshake
+    ///
+    /// Either provide the token directly or via a file with `file:` prefix.
+    /// It is sent over the plugin's stdin, never argv or env.
+    #[arg(long, env = "LOCKET_EXEC_TOKEN", hide_env_values = true)]
+    #[locket(optional)]
+    pub exec_token: Option<AuthToken>,
+
+    /// Environment variables passed through to the plugin
+    ///
+    /// The plugin otherwise only sees HOME, PATH and TMPDIR.
+    #[arg(
+        long,
+        env = "LOCKET_EXEC_PASS_ENV",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub exec_pass_env: Vec<String>,
+}
@@ -0,0 +1,57 @@
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, ServerUrl, Signature,
+    references::{GcpSmReference, HasReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GcpConfig {
+    pub gcp_credentials: AuthToken,
+    pub gcp_url: ServerUrl,
+    pub gcp_token_url: Option<ServerUrl>,
+    pub gcp_max_concurrent: ConcurrencyLimit,
+}
+
+impl HasReference for GcpConfig {
+    type Reference = GcpSmReference;
+}
+
+#[async_trait]
+impl Signature for GcpConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        self.gcp_credentials.signature().await
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "GcpConfig")]
+pub struct GcpArgs {
+    /// Service account JSON key used to authenticate with Google Cloud
+    ///
+    /// Either provide the key directly or via a file with `file:` prefix
+    #[arg(long, env = "GCP_CREDENTIALS", hide_env_values = true)]
+    pub gcp_credentials: Option<AuthToken>,
+
+    /// Base URL of the Secret Manager API
+    #[arg(long, env = "GCP_SECRET_MANAGER_URL")]
+    #[locket(default = "https://secretmanager.googleapis.com")]
+    pub gcp_url: Option<ServerUrl>,
+
+    /// Override the OAuth token endpoint used to exchange the service account key
+    ///
+    /// By default the `token_uri` from the service account key is used.
+    #[arg(long, env = "GCP_TOKEN_URL")]
+    #[locket(optional)]
+    pub gcp_token_url: Option<ServerUrl>,
+
+    /// Maximum allowed concurrent requests to the Secret Manager API
+    #[arg(long, env = "GCP_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub gcp_max_concurrent: Option<ConcurrencyLimit>,
+}
@@ -0,0 +1,93 @@
+use crate::path::AbsolutePath;
+use crate::provider::{
+    AuthToken, ProviderError, ServerUrl, Signature,
+    references::{HasReference, HttpSecretReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpConfig {
+    pub http_url: ServerUrl,
+    pub http_token: AuthToken,
+    pub http_client_cert: Option<AbsolutePath>,
+    pub http_client_key: Option<AuthToken>,
+    pub http_ca_cert: Option<AbsolutePath>,
+}
+
+impl HasReference for HttpConfig {
+    type Reference = HttpSecretReference;
+}
+
+#[async_trait]
+impl Signature for HttpConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        // A client certificate may be renewed for the same key, and the CA
+        // bundle may be rotated on its own, so their contents are watched
+        // along with the credentials.
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.http_token.signature().await?.hash(&mut hasher);
+        if let Some(key) = &self.http_client_key {
+            key.signature().await?.hash(&mut hasher);
+        }
+        for (what, path) in [
+            ("client certificate", &self.http_client_cert),
+            ("CA certificate", &self.http_ca_cert),
+        ] {
+            if let Some(path) = path {
+                tokio::fs::read(path.as_path())
+                    .await
+                    .map_err(|e| {
+                        ProviderError::InvalidConfig(format!(
+                            "failed to read {what} {}: {e}",
+                            path.as_path().display()
+                        ))
+                    })?
+                    .hash(&mut hasher);
+            }
+        }
+        Ok(hasher.finish())
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "HttpConfig")]
+pub struct HttpArgs {
+    /// HTTP(S) URL the batch of secret names is POSTed to
+    #[arg(long, env = "LOCKET_HTTP_URL")]
+    pub http_url: Option<ServerUrl>,
+
+    /// Bearer token sent with every request
+    ///
+    /// Either provide the token directly or via a file with `file:` prefix
+    #[arg(long, env = "LOCKET_HTTP_TOKEN", hide_env_values = true)]
+    pub http_token: Option<AuthToken>,
+
+    /// Optional: Path to a PEM client certificate (chain) for mutual TLS
+    ///
+    /// Requires `--http-client-key`
+    #[arg(long, env = "LOCKET_HTTP_CLIENT_CERT")]
+    #[locket(optional)]
+    pub http_client_cert: Option<AbsolutePath>,
+
+    /// Optional: PEM private key for the mutual TLS client certificate
+    ///
+    /// Either provide the key directly or via a file with `file:` prefix
+    #[arg(long, env = "LOCKET_HTTP_CLIENT_KEY", hide_env_values = true)]
+    #[locket(optional)]
+    pub http_client_key: Option<AuthToken>,
+
+    /// Optional: Path to a PEM CA certificate to trust, in addition to the
+    /// built-in roots
+    ///
+    /// Useful when the endpoint uses a certificate from a private CA
+    #[arg(long, env = "LOCKET_HTTP_CA_CERT")]
+    #[locket(optional)]
+    pub http_ca_cert: Option<AbsolutePath>,
+}
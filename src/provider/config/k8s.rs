@@ -0,0 +1,78 @@
+use crate::path::AbsolutePath;
+use crate::provider::{
+    AuthToken, ConcurrencyLimit, ProviderError, Signature,
+    references::{HasReference, K8sReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct K8sConfig {
+    pub k8s_kubeconfig: Option<AbsolutePath>,
+    pub k8s_context: Option<String>,
+    pub k8s_token: Option<AuthToken>,
+    pub k8s_max_concurrent: ConcurrencyLimit,
+}
+
+impl HasReference for K8sConfig {
+    type Reference = K8sReference;
+}
+
+#[async_trait]
+impl Signature for K8sConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        // Bearer tokens are read again for every batch, so only the
+        // kubeconfig (which may embed certificates) needs watching.
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        if let Some(path) = &self.k8s_kubeconfig {
+            tokio::fs::read(path.as_path())
+                .await
+                .map_err(|e| {
+                    ProviderError::InvalidConfig(format!(
+                        "failed to read kubeconfig {}: {e}",
+                        path.as_path().display()
+                    ))
+                })?
+                .hash(&mut hasher);
+        }
+        if let Some(token) = &self.k8s_token {
+            token.signature().await?.hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "K8sConfig")]
+pub struct K8sArgs {
+    /// Optional: Path to a kubeconfig file
+    ///
+    /// When not set, the in-cluster service account of the pod is used
+    #[arg(long, env = "KUBECONFIG")]
+    #[locket(optional)]
+    pub k8s_kubeconfig: Option<AbsolutePath>,
+
+    /// Optional: Kubeconfig context to use instead of the current context
+    #[arg(long, env = "K8S_CONTEXT")]
+    #[locket(optional)]
+    pub k8s_context: Option<String>,
+
+    /// Optional: Bearer token used instead of the service account or
+    /// kubeconfig credentials
+    ///
+    /// Either provide the token directly or via a file with `file:` prefix
+    #[arg(long, env = "K8S_TOKEN", hide_env_values = true)]
+    #[locket(optional)]
+    pub k8s_token: Option<AuthToken>,
+
+    /// Maximum allowed concurrent requests to the Kubernetes API server
+    #[arg(long, env = "K8S_MAX_CONCURRENT")]
+    #[locket(default = ConcurrencyLimit::new(20))]
+    pub k8s_max_concurrent: Option<ConcurrencyLimit>,
+}
@@ -0,0 +1,88 @@
+use crate::path::AbsolutePath;
+use crate::provider::{
+    AuthToken, ProviderError, Signature,
+    references::{HasReference, KeePassReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeePassConfig {
+    pub keepass_db: AbsolutePath,
+    pub keepass_password: Option<AuthToken>,
+    pub keepass_key_file: Option<AuthToken>,
+}
+
+impl HasReference for KeePassConfig {
+    type Reference = KeePassReference;
+}
+
+#[async_trait]
+impl Signature for KeePassConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        database_signature(self.keepass_db.as_path())
+            .await?
+            .hash(&mut hasher);
+        if let Some(password) = &self.keepass_password {
+            password.signature().await?.hash(&mut hasher);
+        }
+        if let Some(key_file) = &self.keepass_key_file {
+            key_file.signature().await?.hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+/// Hashes the content of the database file, so that edits to the database
+/// are detected like a rotated token.
+pub(crate) async fn database_signature(path: &Path) -> Result<u64, ProviderError> {
+    Ok(content_signature(&read_database(path).await?))
+}
+
+pub(crate) async fn read_database(path: &Path) -> Result<Vec<u8>, ProviderError> {
+    tokio::fs::read(path).await.map_err(|e| {
+        ProviderError::InvalidConfig(format!(
+            "failed to read KeePass database {}: {e}",
+            path.display()
+        ))
+    })
+}
+
+pub(crate) fn content_signature(content: &[u8]) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "KeePassConfig")]
+pub struct KeePassArgs {
+    /// Path to the KeePass (.kdbx) database file
+    #[arg(long, env = "KEEPASS_DB")]
+    pub keepass_db: Option<AbsolutePath>,
+
+    /// Optional: Master password of the database
+    ///
+    /// Either provide the password directly or via a file with `file:` prefix.
+    /// At least one of the password or key file is required
+    #[arg(long, env = "KEEPASS_PASSWORD", hide_env_values = true)]
+    #[locket(optional)]
+    pub keepass_password: Option<AuthToken>,
+
+    /// Optional: Key file of the database
+    ///
+    /// Either provide the key directly or via a file with `file:` prefix.
+    /// Only text key files are supported, i.e. the XML `.keyx` files
+    /// generated by KeePassXC, or hex encoded keys
+    #[arg(long, env = "KEEPASS_KEY_FILE", hide_env_values = true)]
+    #[locket(optional)]
+    pub keepass_key_file: Option<AuthToken>,
+}
@@ -0,0 +1,38 @@
+use crate::provider::{
+    AuthToken, ProviderError, Signature,
+    references::{HasReference, SopsReference},
+};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SopsConfig {
+    pub sops_age_key: AuthToken,
+}
+
+impl HasReference for SopsConfig {
+    type Reference = SopsReference;
+}
+
+#[async_trait]
+impl Signature for SopsConfig {
+    async fn signature(&self) -> Result<u64, ProviderError> {
+        self.sops_age_key.signature().await
+    }
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+#[locket(try_into = "SopsConfig")]
+pub struct SopsArgs {
+    /// age identities used to decrypt SOPS files, one `AGE-SECRET-KEY-1...` per line
+    ///
+    /// Either provide the keys directly or via a file with `file:` prefix,
+    /// e.g. a key file generated by `age-keygen`
+    #[arg(long, env = "SOPS_AGE_KEY", hide_env_values = true)]
+    pub sops_age_key: Option<AuthToken>,
+}
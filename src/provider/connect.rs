@@ -57,6 +57,9 @@ impl OpConnectProvider {
 
         let status = resp.status();
 
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(resp.headers()));
+        }
         if !status.is_success() {
             let error_msg = resp
                 .json::<ErrorResponse>()
@@ -69,6 +72,12 @@ impl OpConnectProvider {
                 StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
                     Err(ProviderError::Unauthorized(error_msg))
                 }
+                StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT => Err(ProviderError::Unavailable(format!(
+                    "connect error: {}",
+                    error_msg
+                ))),
                 _ => Err(ProviderError::Other(format!(
                     "connect error: {}",
                     error_msg
@@ -148,6 +157,9 @@ impl OpConnectProvider {
             .await
             .map_err(|e| ProviderError::Network(e.into()))?;
 
+        if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(resp.headers()));
+        }
         if !resp.status().is_success() {
             return Err(ProviderError::Other(format!(
                 "vault lookup failed: {}",
@@ -245,6 +257,17 @@ impl OpConnectProvider {
             StatusCode::UNAUTHORIZED => {
                 return Err(ProviderError::Unauthorized("invalid token".into()));
             }
+            StatusCode::TOO_MANY_REQUESTS => {
+                return Err(ProviderError::rate_limited(resp.headers()));
+            }
+            s @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => {
+                return Err(ProviderError::Unavailable(format!(
+                    "connect api returned {}",
+                    s
+                )));
+            }
             s => return Err(ProviderError::Other(format!("connect api error: {}", s))),
         }
 
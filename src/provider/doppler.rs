@@ -0,0 +1,216 @@
+//! Doppler provider implementation.
+//!
+//! Downloads all secrets of a project config in a single request through
+//! the `v3/configs/config/secrets/download` endpoint, and resolves every
+//! reference into that config from the downloaded set. Requests are
+//! authenticated with a service token.
+
+use super::{
+    ConcurrencyLimit, ProviderError, SecretsProvider, ServerUrl,
+    config::doppler::DopplerConfig,
+    references::{DopplerReference, Extract, HasReference, SecretReference},
+};
+use async_trait::async_trait;
+use futures::{StreamExt, stream};
+use reqwest::{Client, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::debug;
+
+pub struct DopplerProvider {
+    client: Client,
+    url: ServerUrl,
+    token: SecretString,
+    max_concurrent: ConcurrencyLimit,
+}
+
+impl DopplerProvider {
+    pub async fn new(config: DopplerConfig) -> Result<Self, ProviderError> {
+        let token = config.doppler_token.resolve().await?;
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            url: config.doppler_url,
+            token,
+            max_concurrent: config.doppler_max_concurrent,
+        })
+    }
+
+    /// Downloads every secret of a config as a name to value map.
+    async fn download(
+        &self,
+        project: &str,
+        config: &str,
+    ) -> Result<HashMap<String, SecretString>, ProviderError> {
+        let url = self
+            .url
+            .endpoint(["v3", "configs", "config", "secrets", "download"]);
+
+        let resp = self
+            .client
+            .get(url)
+            .query(&[("project", project), ("config", config), ("format", "json")])
+            .bearer_auth(self.token.expose_secret())
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        match resp.status() {
+            s if s.is_success() => resp
+                .json()
+                .await
+                .map_err(|e| ProviderError::Network(Box::new(e))),
+            StatusCode::NOT_FOUND => Err(ProviderError::NotFound(format!("{project}/{config}"))),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
+                format!("Access denied for {project}/{config}"),
+            )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(resp.headers())),
+            status @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => Err(ProviderError::Unavailable(format!(
+                "Doppler returned {}",
+                status
+            ))),
+            status => {
+                let txt = resp.text().await.unwrap_or_default();
+                Err(ProviderError::Other(format!(
+                    "Doppler error {}: {}",
+                    status, txt
+                )))
+            }
+        }
+    }
+}
+
+impl HasReference for DopplerProvider {
+    type Reference = DopplerReference;
+}
+
+#[async_trait]
+impl SecretsProvider for DopplerProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        // Group references by config so each config is downloaded once,
+        // instead of once per secret.
+        let mut groups: HashMap<(&str, &str), Vec<&DopplerReference>> = HashMap::new();
+        for r in references.iter().filter_map(DopplerReference::extract) {
+            groups.entry((r.project(), r.config())).or_default().push(r);
+        }
+
+        if groups.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let fetches: Vec<_> = groups
+            .into_iter()
+            .map(|((project, config), group_refs)| async move {
+                let data = self.download(project, config).await;
+                (group_refs, data)
+            })
+            .collect();
+
+        let results = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent.into_inner())
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut map = HashMap::new();
+        for (group_refs, data) in results {
+            let secrets = match data {
+                Ok(secrets) => secrets,
+                // Whole config not found: leave all of its secrets unresolved.
+                Err(ProviderError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            for r in group_refs {
+                match secrets.get(r.name()) {
+                    Some(value) => {
+                        map.insert(SecretReference::Doppler(r.clone()), value.clone());
+                    }
+                    None => debug!("{} not found", r),
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::test_server::TestServer;
+    use crate::provider::{AuthToken, ReferenceParser};
+
+    fn config(url: &str) -> DopplerConfig {
+        DopplerConfig {
+            doppler_token: AuthToken::new(SecretString::from("dp.st.prd.token")),
+            doppler_url: url.parse().unwrap(),
+            doppler_max_concurrent: ConcurrencyLimit::new(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_downloads_each_config_once() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/v3/configs/config/secrets/download?project=backend&config=prd&format=json" => (
+                200,
+                r#"{"DATABASE_URL":"postgres://db","API_KEY":"abc","DOPPLER_CONFIG":"prd"}"#.into(),
+            ),
+            _ => (
+                404,
+                r#"{"messages":["Could not find requested config"]}"#.into(),
+            ),
+        });
+        let provider = DopplerProvider::new(config(server.url())).await.unwrap();
+
+        let refs: Vec<SecretReference> = [
+            "doppler://backend/prd/DATABASE_URL",
+            "doppler://backend/prd/API_KEY",
+            "doppler://backend/prd/MISSING",
+            "doppler://backend/stg/API_KEY",
+        ]
+        .into_iter()
+        .map(|r| provider.parse(r).unwrap())
+        .collect();
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "postgres://db");
+        assert_eq!(map[&refs[1]].expose_secret(), "abc");
+        assert!(!map.contains_key(&refs[2]));
+        assert!(!map.contains_key(&refs[3]));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|r| r.method == "GET"
+            && r.body.is_empty()
+            && r.header("authorization") == Some("Bearer dp.st.prd.token")));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_unauthorized() {
+        let server = TestServer::start(|_| (401, r#"{"messages":["Invalid token"]}"#.into()));
+        let provider = DopplerProvider::new(config(server.url())).await.unwrap();
+        let refs = [provider.parse("doppler://backend/prd/API_KEY").unwrap()];
+        let err = provider.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_unavailable_is_transient() {
+        let server = TestServer::start(|_| (503, "upstream connect error".into()));
+        let provider = DopplerProvider::new(config(server.url())).await.unwrap();
+        let refs = [provider.parse("doppler://backend/prd/API_KEY").unwrap()];
+        let err = provider.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unavailable(_)), "{err}");
+        assert!(err.is_transient());
+    }
+}
@@ -0,0 +1,177 @@
+//! Exec provider: resolves references through an external plugin
+//! executable speaking the same JSON protocol as `locket-op-bridge`.
+//!
+//! The plugin answers for a single, configured reference scheme. Any
+//! token is sent once over the plugin's stdin during the init handshake.
+
+use super::plugin::{Plugin, ResolveResult};
+use super::references::{ExecReference, ExecScheme, Extract, ReferenceParser, SecretReference};
+use crate::provider::config::exec::ExecProviderConfig;
+use crate::provider::{ProviderError, SecretsProvider};
+use async_trait::async_trait;
+use secrecy::SecretString;
+use std::collections::HashMap;
+use tokio::process::Command;
+
+pub struct ExecProvider {
+    scheme: ExecScheme,
+    plugin: Plugin,
+}
+
+impl ExecProvider {
+    pub async fn new(cfg: ExecProviderConfig) -> Result<Self, ProviderError> {
+        let path = cfg.exec_command.as_path();
+        let command = cfg.exec_command.canonicalize().map_err(|e| {
+            ProviderError::InvalidConfig(format!(
+                "exec plugin not found at {}: {e}",
+                path.display()
+            ))
+        })?;
+        if !command.as_path().is_file() {
+            return Err(ProviderError::InvalidConfig(format!(
+                "exec plugin at {} is not a file",
+                command.as_path().display()
+            )));
+        }
+
+        let token = match &cfg.exec_token {
+            Some(token) => Some(token.resolve().await?),
+            None => None,
+        };
+
+        let name = format!("exec plugin '{}'", cfg.exec_scheme);
+        let plugin = Plugin::spawn(
+            &name,
+            Command::new(command.as_path()),
+            &cfg.exec_pass_env,
+            token.as_ref(),
+        )
+        .await?;
+
+        Ok(Self {
+            scheme: cfg.exec_scheme,
+            plugin,
+        })
+    }
+}
+
+impl ReferenceParser for ExecProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        ExecReference::parse(&self.scheme, raw).map(SecretReference::from)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for ExecProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        // Only claim references for this plugin's scheme, in case several
+        // exec providers are ever configured side by side.
+        let exec_refs: Vec<&ExecReference> = references
+            .iter()
+            .filter_map(ExecReference::extract)
+            .filter(|r| r.scheme() == self.scheme.as_str())
+            .collect();
+
+        if exec_refs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let refs: Vec<&str> = exec_refs.iter().map(|r| r.as_str()).collect();
+        let mut results = self.plugin.resolve(&refs).await?;
+
+        let mut map = HashMap::with_capacity(exec_refs.len());
+        for reference in exec_refs {
+            match results.remove(reference.as_str()) {
+                Some(ResolveResult::Resolved { secret }) => {
+                    map.insert(SecretReference::Exec(reference.clone()), secret);
+                }
+                Some(ResolveResult::Failed { error }) => {
+                    return Err(error.code.into_provider_error(format!(
+                        "{}: {}",
+                        reference.as_str(),
+                        error.message
+                    )));
+                }
+                None => {
+                    return Err(ProviderError::Other(format!(
+                        "exec plugin '{}' response missing reference {}",
+                        self.scheme,
+                        reference.as_str()
+                    )));
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+    use std::str::FromStr;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    /// Provider over a scripted plugin that answers every resolve
+    /// request from a fixed results payload.
+    fn scripted_provider(results_json: &'static str) -> ExecProvider {
+        let (locket_end, plugin_end) = tokio::io::duplex(64 * 1024);
+        let (l_read, l_write) = tokio::io::split(locket_end);
+        let (p_read, mut p_write) = tokio::io::split(plugin_end);
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(p_read).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+                let id = request["id"].as_u64().unwrap();
+                let response =
+                    format!(r#"{{"type":"resolve-ok","id":{id},"results":{results_json}}}"#);
+                p_write
+                    .write_all(format!("{response}\n").as_bytes())
+                    .await
+                    .unwrap();
+            }
+        });
+        ExecProvider {
+            scheme: ExecScheme::from_str("pass").unwrap(),
+            plugin: Plugin::from_pipes("test plugin", l_read, l_write),
+        }
+    }
+
+    #[tokio::test]
+    async fn fetch_map_stitches_results_to_references() {
+        let provider = scripted_provider(r#"{"pass://work/db":{"secret":"hunter2"}}"#);
+        let reference = provider.parse("pass://work/db").unwrap();
+        let map = provider
+            .fetch_map(&[reference.clone(), SecretReference::Mock("other".into())])
+            .await
+            .unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&reference].expose_secret(), "hunter2");
+    }
+
+    #[tokio::test]
+    async fn fetch_map_maps_plugin_error_codes() {
+        let provider = scripted_provider(
+            r#"{"pass://work/db":{"error":{"code":"rate_limited","message":"slow down"}}}"#,
+        );
+        let refs = [provider.parse("pass://work/db").unwrap()];
+        let err = provider.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(&err, ProviderError::RateLimit { .. }), "{err}");
+    }
+
+    #[tokio::test]
+    async fn new_rejects_missing_command() {
+        let cfg = ExecProviderConfig {
+            exec_command: crate::path::AbsolutePath::new("/nonexistent/locket-plugin"),
+            exec_scheme: ExecScheme::from_str("pass").unwrap(),
+            exec_token: None,
+            exec_pass_env: Vec::new(),
+        };
+        let err = ExecProvider::new(cfg).await.err().unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(_)), "{err}");
+    }
+}
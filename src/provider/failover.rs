@@ -0,0 +1,293 @@
+//! Fails over between equivalent configurations of a provider backend.
+//!
+//! A chain holds the same backend configured against different endpoints,
+//! such as two Connect servers or an OpenBao cluster and its DR replica.
+//! A batch is fetched from the first member, and only falls through to the
+//! next one when the failure suggests the member is unreachable. Every
+//! batch starts at the primary again, so a recovered primary is picked up
+//! without a restart.
+
+use super::{ProviderError, ReferenceParser, SecretReference, SecretsProvider};
+use async_trait::async_trait;
+use futures::future::{join_all, select_all};
+use secrecy::SecretString;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct FailoverProvider {
+    members: Vec<Arc<dyn SecretsProvider>>,
+}
+
+impl FailoverProvider {
+    pub fn new(members: Vec<Arc<dyn SecretsProvider>>) -> Self {
+        Self { members }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FailoverProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let mut last_err = None;
+        for (idx, member) in self.members.iter().enumerate() {
+            match member.fetch_map(references).await {
+                Ok(map) => {
+                    if idx > 0 {
+                        tracing::info!("resolved secrets from failover provider #{}", idx);
+                    }
+                    return Ok(map);
+                }
+                Err(e) if e.is_transient() => {
+                    tracing::warn!("provider #{} unavailable, failing over: {}", idx, e);
+                    last_err = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| ProviderError::InvalidConfig("empty failover chain".into())))
+    }
+
+    async fn expired(&self) {
+        if self.members.is_empty() {
+            return std::future::pending().await;
+        }
+        select_all(self.members.iter().map(|b| b.expired())).await;
+    }
+
+    async fn holds_leases(&self) -> bool {
+        join_all(self.members.iter().map(|b| b.holds_leases()))
+            .await
+            .into_iter()
+            .any(|held| held)
+    }
+
+    async fn shutdown(&self) {
+        join_all(self.members.iter().map(|b| b.shutdown())).await;
+    }
+}
+
+impl ReferenceParser for FailoverProvider {
+    /// All members are configurations of the same backend, so they share
+    /// a reference syntax.
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        self.members.first().and_then(|member| member.parse(raw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ProviderSet, Signature, managed::ProviderFactory, retry::RetryPolicy};
+    use secrecy::ExposeSecret;
+    use std::sync::Mutex;
+
+    /// Backend which answers every batch with a fixed outcome and counts
+    /// how often it was asked.
+    struct StubBackend {
+        name: &'static str,
+        fail: Option<fn() -> ProviderError>,
+        calls: Mutex<usize>,
+    }
+
+    impl StubBackend {
+        fn healthy(name: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                fail: None,
+                calls: Mutex::new(0),
+            })
+        }
+
+        fn failing(name: &'static str, err: fn() -> ProviderError) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                fail: Some(err),
+                calls: Mutex::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            *self.calls.lock().unwrap()
+        }
+    }
+
+    impl ReferenceParser for StubBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for StubBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            *self.calls.lock().unwrap() += 1;
+            if let Some(err) = self.fail {
+                return Err(err());
+            }
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::from(self.name)))
+                .collect())
+        }
+    }
+
+    fn refs() -> Vec<SecretReference> {
+        vec![SecretReference::Mock("a".into())]
+    }
+
+    #[tokio::test]
+    async fn uses_primary_when_healthy() {
+        let primary = StubBackend::healthy("primary");
+        let replica = StubBackend::healthy("replica");
+        let chain = FailoverProvider::new(vec![primary.clone(), replica.clone()]);
+
+        let map = chain.fetch_map(&refs()).await.unwrap();
+        assert_eq!(map[&refs()[0]].expose_secret(), "primary");
+        assert_eq!(replica.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn falls_through_on_transient_errors() {
+        let down = StubBackend::failing("down", || {
+            ProviderError::Network("connection refused".into())
+        });
+        let sealed = StubBackend::failing("sealed", || ProviderError::Unavailable("503".into()));
+        let limited =
+            StubBackend::failing("limited", || ProviderError::RateLimit { retry_after: None });
+        let replica = StubBackend::healthy("replica");
+        let chain = FailoverProvider::new(vec![down, sealed, limited, replica.clone()]);
+
+        let map = chain.fetch_map(&refs()).await.unwrap();
+        assert_eq!(map[&refs()[0]].expose_secret(), "replica");
+
+        // The next batch starts at the primary again.
+        chain.fetch_map(&refs()).await.unwrap();
+        assert_eq!(replica.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn stops_on_other_errors() {
+        let denied =
+            StubBackend::failing("denied", || ProviderError::Unauthorized("bad token".into()));
+        let replica = StubBackend::healthy("replica");
+        let chain = FailoverProvider::new(vec![denied, replica.clone()]);
+
+        let err = chain.fetch_map(&refs()).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+        assert_eq!(replica.calls(), 0);
+    }
+
+    #[tokio::test]
+    async fn returns_last_error_when_all_fail() {
+        let down = StubBackend::failing("down", || ProviderError::RateLimit { retry_after: None });
+        let sealed = StubBackend::failing("sealed", || ProviderError::Unavailable("503".into()));
+        let chain = FailoverProvider::new(vec![down, sealed]);
+
+        let err = chain.fetch_map(&refs()).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unavailable(_)), "{err}");
+    }
+
+    /// A backend configuration whose client can only be created while `up`.
+    #[derive(Clone)]
+    struct FlakyFactory {
+        name: &'static str,
+        up: Arc<std::sync::atomic::AtomicBool>,
+    }
+
+    struct NamedBackend(&'static str);
+
+    impl ReferenceParser for NamedBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for NamedBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::from(self.0)))
+                .collect())
+        }
+    }
+
+    #[async_trait]
+    impl Signature for FlakyFactory {
+        async fn signature(&self) -> Result<u64, ProviderError> {
+            Ok(0)
+        }
+    }
+
+    impl ReferenceParser for FlakyFactory {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ProviderFactory for FlakyFactory {
+        async fn create(&self) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+            if !self.up.load(std::sync::atomic::Ordering::SeqCst) {
+                return Err(ProviderError::Network("connection refused".into()));
+            }
+            Ok(Arc::new(NamedBackend(self.name)))
+        }
+    }
+
+    fn flaky(name: &'static str, up: bool) -> FlakyFactory {
+        FlakyFactory {
+            name,
+            up: Arc::new(up.into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn keeps_members_unreachable_at_startup() {
+        use std::sync::atomic::Ordering;
+
+        let primary = flaky("primary", false);
+        let replica = flaky("replica", true);
+        let chain =
+            ProviderSet::build_chain(vec![primary.clone(), replica], RetryPolicy::none(), false)
+                .await
+                .unwrap();
+
+        let refs = [SecretReference::Mock("a".into())];
+        let map = chain.fetch_map(&refs).await.unwrap();
+        assert_eq!(map[&refs[0]].expose_secret(), "replica");
+
+        // The primary comes back, and is used again without a restart.
+        primary.up.store(true, Ordering::SeqCst);
+        let map = chain.fetch_map(&refs).await.unwrap();
+        assert_eq!(map[&refs[0]].expose_secret(), "primary");
+
+        // Without a fallback cache, a chain with no reachable member fails.
+        let err = ProviderSet::build_chain(
+            vec![flaky("a", false), flaky("b", false)],
+            RetryPolicy::none(),
+            false,
+        )
+        .await
+        .err()
+        .unwrap();
+        assert!(err.is_transient(), "{err}");
+        assert!(
+            ProviderSet::build_chain(
+                vec![flaky("a", false), flaky("b", false)],
+                RetryPolicy::none(),
+                true,
+            )
+            .await
+            .is_ok()
+        );
+    }
+}
@@ -0,0 +1,609 @@
+//! Encrypted on-disk cache of the last known good secrets.
+//!
+//! Every successful fetch is persisted to a file encrypted with
+//! AES-256-GCM. The key is derived from a user supplied secret with
+//! HKDF-SHA256 and a random salt kept in the file header; the secret is
+//! expected to be a long random value, not a passphrase. Leased
+//! secrets, such as dynamic credentials and certificates, are never
+//! persisted, since they are revoked or expire on their own. When a
+//! backend later fails with a transient error (e.g. it is down while the
+//! container restarts), the batch is served from this file instead, as
+//! long as every reference in it is present and no older than the
+//! configured maximum staleness. Entries older than that are dropped from
+//! the file, and the file is only rewritten when its content changes.
+//!
+//! File layout: `version (1 byte) || salt (16 bytes) || nonce (12 bytes) ||
+//! ciphertext`, where the plaintext is a JSON map of reference to value and
+//! fetch time.
+
+use super::{
+    AuthToken, ProviderDuration, ProviderError, ReferenceParser, SecretReference, SecretsProvider,
+};
+use crate::path::AbsolutePath;
+use crate::write::{FileWriter, FsMode};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use async_trait::async_trait;
+use clap::Args;
+use hkdf::Hkdf;
+use locket_derive::LayeredConfig;
+use secrecy::zeroize::Zeroizing;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::Mutex;
+
+const FORMAT_VERSION: u8 = 2;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KDF_INFO: &[u8] = b"locket-fallback-cache-v2";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FallbackConfig {
+    pub path: AbsolutePath,
+    pub key: AuthToken,
+    pub max_staleness: Duration,
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub struct FallbackArgs {
+    /// Optional: Path of an encrypted file which keeps the last
+    /// successfully fetched secrets
+    ///
+    /// When a provider is unreachable, rate limited or unavailable, secrets
+    /// are served from this file instead, with a warning. Requires
+    /// `--provider-fallback-key`.
+    #[arg(long, env = "SECRETS_PROVIDER_FALLBACK_CACHE")]
+    pub provider_fallback_cache: Option<AbsolutePath>,
+
+    /// Secret the fallback cache encryption key is derived from
+    ///
+    /// Use a long random value, e.g. from `openssl rand -hex 32`.
+    /// Either provide the key directly or via a file with `file:` prefix
+    #[arg(long, env = "SECRETS_PROVIDER_FALLBACK_KEY", hide_env_values = true)]
+    pub provider_fallback_key: Option<AuthToken>,
+
+    /// Maximum age of a cached secret which may still be served
+    ///
+    /// Supports human-friendly durations like 12h or 7d. Plain numbers are seconds.
+    #[arg(long, env = "SECRETS_PROVIDER_FALLBACK_MAX_STALENESS")]
+    #[locket(default = ProviderDuration(Duration::from_secs(24 * 60 * 60)))]
+    pub provider_fallback_max_staleness: Option<ProviderDuration>,
+}
+
+impl TryFrom<FallbackArgs> for Option<FallbackConfig> {
+    type Error = crate::error::LocketError;
+
+    fn try_from(args: FallbackArgs) -> Result<Self, Self::Error> {
+        use crate::config::ApplyDefaults;
+        let args = args.apply_defaults();
+
+        let Some(path) = args.provider_fallback_cache else {
+            return Ok(None);
+        };
+        let key = args.provider_fallback_key.ok_or_else(|| {
+            crate::config::ConfigError::Validation(
+                "--provider-fallback-key is required with --provider-fallback-cache".into(),
+            )
+        })?;
+        let max_staleness = args
+            .provider_fallback_max_staleness
+            .map(|s| s.0)
+            .unwrap_or_default();
+
+        Ok(Some(FallbackConfig {
+            path,
+            key,
+            max_staleness,
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+struct StoredSecret {
+    value: SecretString,
+    fetched_at: u64,
+}
+
+/// Borrowed form of [`StoredSecret`] for serialization.
+#[derive(Serialize)]
+struct StoredSecretRef<'a> {
+    value: &'a str,
+    fetched_at: u64,
+}
+
+/// The last known good value of every secret fetched by any backend,
+/// shared by all backends of a provider set.
+pub struct FallbackStore {
+    config: FallbackConfig,
+    /// Loaded from disk on first use
+    state: Mutex<Option<StoreState>>,
+}
+
+struct StoreState {
+    salt: [u8; SALT_LEN],
+    cipher: Aes256Gcm,
+    entries: HashMap<String, StoredSecret>,
+}
+
+impl StoreState {
+    /// An empty cache with a fresh salt.
+    fn new(secret: &SecretString) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            cipher: cipher(secret, &salt),
+            salt,
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Drops the entries older than `max_age`, returning whether any were.
+    fn prune(&mut self, now: u64, max_age: u64) -> bool {
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, stored| now.saturating_sub(stored.fetched_at) <= max_age);
+        self.entries.len() != before
+    }
+
+    fn encrypt(&self) -> Result<Vec<u8>, ProviderError> {
+        let plain: HashMap<&str, StoredSecretRef> = self
+            .entries
+            .iter()
+            .map(|(k, v)| {
+                let entry = StoredSecretRef {
+                    value: v.value.expose_secret(),
+                    fetched_at: v.fetched_at,
+                };
+                (k.as_str(), entry)
+            })
+            .collect();
+        let json = Zeroizing::new(
+            serde_json::to_vec(&plain).map_err(|e| ProviderError::Other(e.to_string()))?,
+        );
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, json.as_slice())
+            .map_err(|_| ProviderError::Other("failed to encrypt fallback cache".into()))?;
+
+        let mut bytes = Vec::with_capacity(1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+        bytes.push(FORMAT_VERSION);
+        bytes.extend_from_slice(&self.salt);
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    fn decrypt(secret: &SecretString, bytes: &[u8]) -> Result<Self, String> {
+        let Some((&FORMAT_VERSION, rest)) = bytes.split_first() else {
+            return Err("unsupported format".into());
+        };
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err("file is truncated".into());
+        }
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let salt: [u8; SALT_LEN] = salt.try_into().map_err(|_| "file is truncated")?;
+        let cipher = cipher(secret, &salt);
+        let json = Zeroizing::new(
+            cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| "decryption failed, the key may have changed".to_string())?,
+        );
+        let entries = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+        Ok(Self {
+            salt,
+            cipher,
+            entries,
+        })
+    }
+}
+
+impl FallbackStore {
+    pub fn new(config: FallbackConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(None),
+        }
+    }
+
+    /// Returns the loaded state, reading the file on first use.
+    ///
+    /// A file which cannot be decrypted (e.g. after the key was rotated) is
+    /// treated as empty, and replaced on the next successful fetch.
+    async fn load<'a>(
+        &self,
+        state: &'a mut Option<StoreState>,
+    ) -> Result<&'a mut StoreState, ProviderError> {
+        if let Some(state) = state {
+            return Ok(state);
+        }
+
+        let secret = self.config.key.resolve().await?;
+        let loaded = match tokio::fs::read(&*self.config.path).await {
+            Ok(bytes) => StoreState::decrypt(&secret, &bytes).unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Ignoring fallback cache {}: {}",
+                    self.config.path.display(),
+                    e
+                );
+                StoreState::new(&secret)
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => StoreState::new(&secret),
+            Err(e) => return Err(ProviderError::Io(e)),
+        };
+        Ok(state.insert(loaded))
+    }
+
+    /// Records freshly fetched secrets, and persists the cache if it changed.
+    ///
+    /// The fetch time of an unchanged value is only refreshed once a quarter
+    /// of the maximum staleness has passed, so the file is not rewritten on
+    /// every fetch. Entries are then at most that much older than reported.
+    pub async fn remember(
+        &self,
+        secrets: &HashMap<SecretReference, SecretString>,
+    ) -> Result<(), ProviderError> {
+        self.remember_at(secrets, unix_now()).await
+    }
+
+    async fn remember_at(
+        &self,
+        secrets: &HashMap<SecretReference, SecretString>,
+        now: u64,
+    ) -> Result<(), ProviderError> {
+        let mut guard = self.state.lock().await;
+        let state = self.load(&mut guard).await?;
+        let max_age = self.config.max_staleness.as_secs();
+        let refresh = max_age / 4;
+
+        let mut changed = state.prune(now, max_age);
+        for (reference, value) in secrets.iter().filter(|(r, _)| r.is_cacheable()) {
+            let key = reference.to_string();
+            let current = state.entries.get(&key).is_some_and(|stored| {
+                stored.value.expose_secret() == value.expose_secret()
+                    && now.saturating_sub(stored.fetched_at) < refresh
+            });
+            if current {
+                continue;
+            }
+            state.entries.insert(
+                key,
+                StoredSecret {
+                    value: value.clone(),
+                    fetched_at: now,
+                },
+            );
+            changed = true;
+        }
+        if !changed {
+            return Ok(());
+        }
+
+        let bytes = state.encrypt()?;
+        let path = self.config.path.clone();
+        tokio::task::spawn_blocking(move || {
+            FileWriter::new(FsMode::new(0o600), FsMode::new(0o700)).atomic_write(&path, &bytes)
+        })
+        .await
+        .map_err(|e| ProviderError::Other(e.to_string()))?
+        .map_err(|e| ProviderError::Other(format!("failed to write fallback cache: {}", e)))
+    }
+
+    /// Looks up every reference of a batch.
+    ///
+    /// Returns `None` unless all of them are cached and fresh enough, so a
+    /// batch is never rendered from a mix of live and missing values. A
+    /// batch with leased secrets is never served.
+    pub async fn recall(
+        &self,
+        references: &[SecretReference],
+    ) -> Option<HashMap<SecretReference, SecretString>> {
+        self.recall_at(references, unix_now()).await
+    }
+
+    async fn recall_at(
+        &self,
+        references: &[SecretReference],
+        now: u64,
+    ) -> Option<HashMap<SecretReference, SecretString>> {
+        let mut guard = self.state.lock().await;
+        let state = match self.load(&mut guard).await {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("Failed to load fallback cache: {}", e);
+                return None;
+            }
+        };
+
+        let max_age = self.config.max_staleness.as_secs();
+        let mut map = HashMap::with_capacity(references.len());
+        for reference in references {
+            if !reference.is_cacheable() {
+                tracing::debug!("{} is never served from the fallback cache", reference);
+                return None;
+            }
+            let Some(stored) = state.entries.get(&reference.to_string()) else {
+                tracing::debug!("{} is not in the fallback cache", reference);
+                return None;
+            };
+            if now.saturating_sub(stored.fetched_at) > max_age {
+                tracing::debug!("{} is too stale in the fallback cache", reference);
+                return None;
+            }
+            map.insert(reference.clone(), stored.value.clone());
+        }
+        Some(map)
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Derives the AES-256 key from the user supplied secret and a file's salt.
+fn cipher(secret: &SecretString, salt: &[u8]) -> Aes256Gcm {
+    let mut key = Zeroizing::new([0u8; 32]);
+    Hkdf::<Sha256>::new(Some(salt), secret.expose_secret().as_bytes())
+        .expand(KDF_INFO, key.as_mut_slice())
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_slice()))
+}
+
+/// Serves the last known good secrets of a backend while it is unreachable.
+pub struct FallbackProvider {
+    inner: Arc<dyn SecretsProvider>,
+    store: Arc<FallbackStore>,
+}
+
+impl FallbackProvider {
+    pub fn new(inner: Arc<dyn SecretsProvider>, store: Arc<FallbackStore>) -> Self {
+        Self { inner, store }
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for FallbackProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        match self.inner.fetch_map(references).await {
+            Ok(map) => {
+                if let Err(e) = self.store.remember(&map).await {
+                    tracing::warn!("Failed to update fallback cache: {}", e);
+                }
+                Ok(map)
+            }
+            Err(e) if e.is_transient() => match self.store.recall(references).await {
+                Some(map) => {
+                    tracing::warn!(
+                        "PROVIDER UNAVAILABLE ({}). Serving {} secret(s) from the fallback cache; \
+                         they may be outdated",
+                        e,
+                        map.len()
+                    );
+                    Ok(map)
+                }
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn expired(&self) {
+        self.inner.expired().await
+    }
+
+    async fn holds_leases(&self) -> bool {
+        self.inner.holds_leases().await
+    }
+
+    async fn shutdown(&self) {
+        self.inner.shutdown().await
+    }
+}
+
+impl ReferenceParser for FallbackProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        self.inner.parse(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Backend which resolves every reference to `<reference>-live`, or
+    /// fails with the configured error while `down` is set.
+    struct FlakyBackend {
+        down: AtomicBool,
+        err: fn() -> ProviderError,
+    }
+
+    impl ReferenceParser for FlakyBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for FlakyBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err((self.err)());
+            }
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::from(format!("{r}-live"))))
+                .collect())
+        }
+    }
+
+    fn mock(raw: &str) -> SecretReference {
+        SecretReference::Mock(raw.to_string())
+    }
+
+    fn config(dir: &tempfile::TempDir, key: &str) -> FallbackConfig {
+        FallbackConfig {
+            path: AbsolutePath::new(dir.path().join("fallback.bin")),
+            key: AuthToken::new(SecretString::from(key)),
+            max_staleness: Duration::from_secs(3600),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serves_last_known_good_when_unreachable() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(FlakyBackend {
+            down: AtomicBool::new(false),
+            err: || ProviderError::Network("connection refused".into()),
+        });
+        let store = Arc::new(FallbackStore::new(config(&dir, "key")));
+        let provider = FallbackProvider::new(backend.clone(), store);
+        provider.fetch_map(&[mock("a"), mock("b")]).await.unwrap();
+
+        // A fresh store, as after a container restart.
+        backend.down.store(true, Ordering::SeqCst);
+        let store = Arc::new(FallbackStore::new(config(&dir, "key")));
+        let provider = FallbackProvider::new(backend.clone(), store);
+
+        let map = provider.fetch_map(&[mock("a")]).await.unwrap();
+        assert_eq!(map[&mock("a")].expose_secret(), "a-live");
+
+        // A batch is only served if every reference is cached.
+        let err = provider
+            .fetch_map(&[mock("a"), mock("c")])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::Network(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_other_errors_are_not_masked() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = Arc::new(FlakyBackend {
+            down: AtomicBool::new(false),
+            err: || ProviderError::Unauthorized("revoked".into()),
+        });
+        let store = Arc::new(FallbackStore::new(config(&dir, "key")));
+        let provider = FallbackProvider::new(backend.clone(), store);
+        provider.fetch_map(&[mock("a")]).await.unwrap();
+
+        backend.down.store(true, Ordering::SeqCst);
+        let err = provider.fetch_map(&[mock("a")]).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_staleness_and_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = HashMap::from([(mock("a"), SecretString::from("value"))]);
+        let store = FallbackStore::new(config(&dir, "key"));
+        store.remember_at(&secrets, 1_000).await.unwrap();
+
+        let raw = std::fs::read(dir.path().join("fallback.bin")).unwrap();
+        assert!(!raw.windows(5).any(|w| w == b"value"));
+
+        assert!(store.recall_at(&[mock("a")], 1_000 + 3600).await.is_some());
+        assert!(store.recall_at(&[mock("a")], 1_000 + 3601).await.is_none());
+
+        let rotated = FallbackStore::new(config(&dir, "other-key"));
+        assert!(rotated.recall_at(&[mock("a")], 1_000).await.is_none());
+
+        // Each file gets its own salt, even under the same key.
+        let other = tempfile::tempdir().unwrap();
+        let store = FallbackStore::new(config(&other, "key"));
+        store.remember_at(&secrets, 1_000).await.unwrap();
+        let other_raw = std::fs::read(other.path().join("fallback.bin")).unwrap();
+        assert_ne!(raw[1..1 + SALT_LEN], other_raw[1..1 + SALT_LEN]);
+    }
+
+    #[tokio::test]
+    async fn test_only_writes_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let read = || std::fs::read(dir.path().join("fallback.bin")).unwrap();
+        let secrets = HashMap::from([(mock("a"), SecretString::from("value"))]);
+        let store = FallbackStore::new(config(&dir, "key"));
+        store.remember_at(&secrets, 1_000).await.unwrap();
+        let written = read();
+
+        // Every write uses a fresh nonce, so equal bytes mean no write.
+        store.remember_at(&secrets, 1_000 + 899).await.unwrap();
+        assert_eq!(read(), written);
+
+        // Unchanged values are refreshed after a quarter of the staleness.
+        store.remember_at(&secrets, 1_000 + 900).await.unwrap();
+        let refreshed = read();
+        assert_ne!(refreshed, written);
+
+        let rotated = HashMap::from([(mock("a"), SecretString::from("rotated"))]);
+        store.remember_at(&rotated, 1_000 + 901).await.unwrap();
+        assert_ne!(read(), refreshed);
+    }
+
+    #[tokio::test]
+    async fn test_prunes_entries_older_than_max_staleness() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FallbackStore::new(config(&dir, "key"));
+        let a = HashMap::from([(mock("a"), SecretString::from("value"))]);
+        let b = HashMap::from([(mock("b"), SecretString::from("value"))]);
+        store.remember_at(&a, 1_000).await.unwrap();
+        store.remember_at(&b, 1_000 + 3601).await.unwrap();
+
+        let fresh = FallbackStore::new(config(&dir, "key"));
+        let mut guard = fresh.state.lock().await;
+        let entries = &fresh.load(&mut guard).await.unwrap().entries;
+        assert!(!entries.contains_key(&mock("a").to_string()));
+        assert!(entries.contains_key(&mock("b").to_string()));
+    }
+
+    #[cfg(feature = "bao")]
+    #[tokio::test]
+    async fn test_leased_secrets_are_not_cached() {
+        use crate::provider::references::parse_bao_reference;
+
+        let dir = tempfile::tempdir().unwrap();
+        let leased = parse_bao_reference("bao+dyn://database/creds/app#password").unwrap();
+        let secrets = HashMap::from([
+            (mock("a"), SecretString::from("value")),
+            (leased.clone(), SecretString::from("leased")),
+        ]);
+        let store = FallbackStore::new(config(&dir, "key"));
+        store.remember_at(&secrets, 1_000).await.unwrap();
+
+        // Not even on disk, so a fresh store can't serve it either.
+        let fresh = FallbackStore::new(config(&dir, "key"));
+        assert!(fresh.recall_at(&[mock("a")], 1_000).await.is_some());
+        assert!(
+            fresh
+                .recall_at(std::slice::from_ref(&leased), 1_000)
+                .await
+                .is_none()
+        );
+        assert!(
+            !fresh
+                .load(&mut *fresh.state.lock().await)
+                .await
+                .unwrap()
+                .entries
+                .contains_key(&leased.to_string())
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! HTTP webhook provider implementation.
+//!
+//! Resolves `http-secret://` references by POSTing the names of a whole
+//! batch to a single configured endpoint, which answers with a JSON map of
+//! names to values. This is the shape many internal secret brokers expose.
+//!
+//! Requests carry a bearer token, and can additionally authenticate with a
+//! client certificate for mutual TLS.
+
+use super::{
+    ProviderError, SecretsProvider, ServerUrl,
+    config::http::HttpConfig,
+    references::{Extract, HasReference, HttpSecretReference, SecretReference},
+};
+use async_trait::async_trait;
+use reqwest::{Certificate, Client, Identity, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+pub struct HttpProvider {
+    client: Client,
+    url: ServerUrl,
+    token: SecretString,
+}
+
+#[derive(Serialize)]
+struct ResolveRequest<'a> {
+    names: Vec<&'a str>,
+}
+
+impl HttpProvider {
+    pub async fn new(config: HttpConfig) -> Result<Self, ProviderError> {
+        let token = config.http_token.resolve().await?;
+
+        let mut builder = Client::builder().timeout(Duration::from_secs(10));
+
+        match (&config.http_client_cert, &config.http_client_key) {
+            (Some(cert), Some(key)) => {
+                let mut pem = read_pem(cert.as_path()).await?;
+                pem.push(b'\n');
+                pem.extend_from_slice(key.resolve().await?.expose_secret().as_bytes());
+                let identity = Identity::from_pem(&pem).map_err(|e| {
+                    ProviderError::InvalidConfig(format!("invalid client certificate or key: {e}"))
+                })?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(ProviderError::InvalidConfig(
+                    "--http-client-cert and --http-client-key must be provided together".into(),
+                ));
+            }
+        }
+
+        if let Some(ca) = &config.http_ca_cert {
+            let cert = Certificate::from_pem(&read_pem(ca.as_path()).await?).map_err(|e| {
+                ProviderError::InvalidConfig(format!(
+                    "invalid CA certificate {}: {e}",
+                    ca.as_path().display()
+                ))
+            })?;
+            builder = builder.add_root_certificate(cert);
+        }
+
+        let client = builder
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            url: config.http_url,
+            token,
+        })
+    }
+
+    /// Fetches every name in one round trip. Names the endpoint does not
+    /// return (or returns as `null`) are omitted.
+    async fn resolve(
+        &self,
+        names: Vec<&str>,
+    ) -> Result<HashMap<String, SecretString>, ProviderError> {
+        let resp = self
+            .client
+            .post(self.url.endpoint([]))
+            .bearer_auth(self.token.expose_secret())
+            .json(&ResolveRequest { names })
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        match resp.status() {
+            s if s.is_success() => {
+                let values: HashMap<String, Option<SecretString>> = resp
+                    .json()
+                    .await
+                    .map_err(|e| ProviderError::Network(Box::new(e)))?;
+                Ok(values
+                    .into_iter()
+                    .filter_map(|(name, value)| Some((name, value?)))
+                    .collect())
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
+                format!("Access denied by {}", self.url),
+            )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(resp.headers())),
+            status @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => Err(ProviderError::Unavailable(format!(
+                "HTTP secret endpoint returned {}",
+                status
+            ))),
+            status => {
+                let txt = resp.text().await.unwrap_or_default();
+                Err(ProviderError::Other(format!(
+                    "HTTP secret endpoint error {}: {}",
+                    status, txt
+                )))
+            }
+        }
+    }
+}
+
+async fn read_pem(path: &Path) -> Result<Vec<u8>, ProviderError> {
+    tokio::fs::read(path).await.map_err(|e| {
+        ProviderError::InvalidConfig(format!("failed to read {}: {e}", path.display()))
+    })
+}
+
+impl HasReference for HttpProvider {
+    type Reference = HttpSecretReference;
+}
+
+#[async_trait]
+impl SecretsProvider for HttpProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let refs: HashSet<&HttpSecretReference> = references
+            .iter()
+            .filter_map(HttpSecretReference::extract)
+            .collect();
+
+        if refs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let mut values = self
+            .resolve(refs.iter().map(|r| r.name()).collect())
+            .await?;
+
+        let mut map = HashMap::with_capacity(refs.len());
+        for r in refs {
+            if let Some(value) = values.remove(r.name()) {
+                map.insert(SecretReference::HttpSecret(r.clone()), value);
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::AbsolutePath;
+    use crate::provider::test_server::TestServer;
+    use crate::provider::{AuthToken, ReferenceParser};
+    use std::str::FromStr;
+
+    fn config(url: &str) -> HttpConfig {
+        HttpConfig {
+            http_url: ServerUrl::from_str(url).unwrap(),
+            http_token: AuthToken::new(SecretString::from("broker-token")),
+            http_client_cert: None,
+            http_client_key: None,
+            http_ca_cert: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_batch() {
+        let server = TestServer::start(|_| {
+            (
+                200,
+                r#"{"db/password":"hunter2","api-key":"abc","missing":null}"#.into(),
+            )
+        });
+        let url = format!("{}/v1/resolve", server.url());
+        let provider = HttpProvider::new(config(&url)).await.unwrap();
+
+        let refs: Vec<SecretReference> = [
+            "http-secret://db/password",
+            "http-secret://api-key",
+            "http-secret://missing",
+        ]
+        .into_iter()
+        .map(|r| provider.parse(r).unwrap())
+        .collect();
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "hunter2");
+        assert_eq!(map[&refs[1]].expose_secret(), "abc");
+        assert!(!map.contains_key(&refs[2]));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/v1/resolve");
+        assert_eq!(request.header("authorization"), Some("Bearer broker-token"));
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        let mut names: Vec<&str> = body["names"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n.as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["api-key", "db/password", "missing"]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_unauthorized() {
+        let server = TestServer::start(|_| (401, "{}".into()));
+        let provider = HttpProvider::new(config(server.url())).await.unwrap();
+        let refs = [provider.parse("http-secret://a").unwrap()];
+        let err = provider.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_client_identity_config() {
+        let mut cfg = config("https://broker.internal");
+        cfg.http_client_key = Some(AuthToken::new(SecretString::from("not a key")));
+        let err = HttpProvider::new(cfg.clone()).await.err().unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(m) if m.contains("together")));
+
+        let cert = tempfile::NamedTempFile::new().unwrap();
+        cfg.http_client_cert = Some(AbsolutePath::new(cert.path()));
+        let err = HttpProvider::new(cfg).await.err().unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(m) if m.contains("client certificate")));
+    }
+
+    #[tokio::test]
+    async fn test_signature_follows_certificates() {
+        use crate::provider::Signature;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("client.crt");
+        let ca = dir.path().join("ca.crt");
+        std::fs::write(&cert, "cert-1").unwrap();
+        std::fs::write(&ca, "ca-1").unwrap();
+
+        let mut cfg = config("http://127.0.0.1:8080");
+        cfg.http_client_cert = Some(AbsolutePath::new(&cert));
+        cfg.http_ca_cert = Some(AbsolutePath::new(&ca));
+        let before = cfg.signature().await.unwrap();
+
+        // A renewed certificate may keep its key.
+        std::fs::write(&cert, "cert-2").unwrap();
+        let renewed = cfg.signature().await.unwrap();
+        assert_ne!(renewed, before);
+
+        std::fs::write(&ca, "ca-2").unwrap();
+        assert_ne!(cfg.signature().await.unwrap(), renewed);
+    }
+}
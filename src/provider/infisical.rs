@@ -153,6 +153,17 @@ impl InfisicalProvider {
                         reference
                     )));
                 }
+                StatusCode::TOO_MANY_REQUESTS => {
+                    return Err(ProviderError::rate_limited(resp.headers()));
+                }
+                status @ (StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT) => {
+                    return Err(ProviderError::Unavailable(format!(
+                        "Infisical returned {}",
+                        status
+                    )));
+                }
                 status => {
                     let txt = resp.text().await.unwrap_or_default();
                     return Err(ProviderError::Other(format!(
@@ -238,6 +249,9 @@ impl TokenExchange for UniversalAuthLogin {
             .await
             .map_err(|e| ProviderError::Network(Box::new(e)))?;
 
+        if resp.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ProviderError::rate_limited(resp.headers()));
+        }
         if !resp.status().is_success() {
             let status = resp.status();
             let text = resp.text().await.unwrap_or_default();
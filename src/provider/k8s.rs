@@ -0,0 +1,586 @@
+//! Kubernetes Secrets provider implementation.
+//!
+//! Reads `Secret` objects from the Kubernetes API server. When running in a
+//! pod, the mounted service account token and CA are used. Otherwise the
+//! cluster, credentials and CA are taken from a kubeconfig file.
+//!
+//! Each referenced Secret is fetched once per batch, no matter how many of
+//! its keys are referenced. Bearer tokens are read again for every batch, so
+//! projected service account tokens are rotated without a rebuild.
+
+use super::{
+    AuthToken, ConcurrencyLimit, ProviderError, SecretsProvider, ServerUrl,
+    config::k8s::K8sConfig,
+    references::{Extract, HasReference, K8sReference, SecretReference},
+};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use futures::{StreamExt, stream};
+use reqwest::{Certificate, Client, Identity, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Where the kubelet mounts the service account credentials of a pod.
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+pub struct K8sProvider {
+    client: Client,
+    server: ServerUrl,
+    token: Option<AuthToken>,
+    max_concurrent: ConcurrencyLimit,
+}
+
+/// Everything needed to talk to an API server, from either the in-cluster
+/// environment or a kubeconfig.
+struct Connection {
+    server: ServerUrl,
+    token: Option<AuthToken>,
+    ca: Option<Vec<u8>>,
+    /// PEM client certificate followed by its key
+    identity: Option<Vec<u8>>,
+    insecure: bool,
+}
+
+impl K8sProvider {
+    pub async fn new(config: K8sConfig) -> Result<Self, ProviderError> {
+        let mut conn = match &config.k8s_kubeconfig {
+            Some(path) => from_kubeconfig(path.as_path(), config.k8s_context.as_deref()).await?,
+            None => {
+                if config.k8s_context.is_some() {
+                    return Err(ProviderError::InvalidConfig(
+                        "--k8s-context requires --k8s-kubeconfig".into(),
+                    ));
+                }
+                in_cluster().await?
+            }
+        };
+        if let Some(token) = config.k8s_token {
+            conn.token = Some(token);
+        }
+        if conn.token.is_none() && conn.identity.is_none() {
+            return Err(ProviderError::InvalidConfig(
+                "no Kubernetes credentials found: provide a token or client certificate".into(),
+            ));
+        }
+
+        let mut builder = Client::builder().timeout(Duration::from_secs(10));
+        if let Some(ca) = &conn.ca {
+            let cert = Certificate::from_pem(ca).map_err(|e| {
+                ProviderError::InvalidConfig(format!("invalid cluster CA certificate: {e}"))
+            })?;
+            builder = builder.add_root_certificate(cert);
+        }
+        if let Some(pem) = &conn.identity {
+            let identity = Identity::from_pem(pem).map_err(|e| {
+                ProviderError::InvalidConfig(format!("invalid client certificate or key: {e}"))
+            })?;
+            builder = builder.identity(identity);
+        }
+        if conn.insecure {
+            warn!(
+                "TLS verification is disabled for {} (insecure-skip-tls-verify)",
+                conn.server
+            );
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            server: conn.server,
+            token: conn.token,
+            max_concurrent: config.k8s_max_concurrent,
+        })
+    }
+
+    /// Fetches the `data` of a Secret, with values still base64 encoded.
+    async fn fetch_secret(
+        &self,
+        namespace: &str,
+        name: &str,
+        token: Option<&SecretString>,
+    ) -> Result<HashMap<String, SecretString>, ProviderError> {
+        let url = self
+            .server
+            .endpoint(["api", "v1", "namespaces", namespace, "secrets", name]);
+        let mut req = self.client.get(url);
+        if let Some(token) = token {
+            req = req.bearer_auth(token.expose_secret());
+        }
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(Box::new(e)))?;
+
+        match resp.status() {
+            s if s.is_success() => {
+                let secret: SecretObject = resp
+                    .json()
+                    .await
+                    .map_err(|e| ProviderError::Network(Box::new(e)))?;
+                Ok(secret.data)
+            }
+            StatusCode::NOT_FOUND => Err(ProviderError::NotFound(format!("{namespace}/{name}"))),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ProviderError::Unauthorized(
+                format!("Access denied for secret {namespace}/{name}"),
+            )),
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::rate_limited(resp.headers())),
+            status @ (StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT) => Err(ProviderError::Unavailable(format!(
+                "Kubernetes API returned {}",
+                status
+            ))),
+            status => {
+                let txt = resp.text().await.unwrap_or_default();
+                Err(ProviderError::Other(format!(
+                    "Kubernetes API error {}: {}",
+                    status, txt
+                )))
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SecretObject {
+    #[serde(default)]
+    data: HashMap<String, SecretString>,
+}
+
+impl HasReference for K8sProvider {
+    type Reference = K8sReference;
+}
+
+#[async_trait]
+impl SecretsProvider for K8sProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        // Group references by Secret so a Secret with multiple referenced
+        // keys is only fetched once, instead of once per key.
+        let mut groups: HashMap<(&str, &str), Vec<&K8sReference>> = HashMap::new();
+        for r in references.iter().filter_map(K8sReference::extract) {
+            groups
+                .entry((r.namespace(), r.secret()))
+                .or_default()
+                .push(r);
+        }
+
+        if groups.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let token = match &self.token {
+            Some(token) => Some(token.resolve().await?),
+            None => None,
+        };
+        let token = token.as_ref();
+
+        let fetches: Vec<_> = groups
+            .into_iter()
+            .map(|((namespace, name), group_refs)| async move {
+                let data = self.fetch_secret(namespace, name, token).await;
+                (group_refs, data)
+            })
+            .collect();
+
+        let results = stream::iter(fetches)
+            .buffer_unordered(self.max_concurrent.into_inner())
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut map = HashMap::new();
+        for (group_refs, data) in results {
+            let data = match data {
+                Ok(data) => data,
+                // Whole Secret not found: leave all of its keys unresolved.
+                Err(ProviderError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            for r in group_refs {
+                let Some(encoded) = data.get(r.key()) else {
+                    debug!("{} not found", r);
+                    continue;
+                };
+                let bytes = STANDARD.decode(encoded.expose_secret()).map_err(|e| {
+                    ProviderError::Other(format!("Invalid base64 value for {}: {}", r, e))
+                })?;
+                match String::from_utf8(bytes) {
+                    Ok(value) => {
+                        map.insert(SecretReference::K8s(r.clone()), SecretString::from(value));
+                    }
+                    Err(_) => warn!(
+                        "Secret {} is not valid UTF-8; only text secrets can be injected",
+                        r
+                    ),
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Builds the connection from the environment the kubelet provides to
+/// every pod.
+async fn in_cluster() -> Result<Connection, ProviderError> {
+    let (Ok(host), Ok(port)) = (
+        std::env::var("KUBERNETES_SERVICE_HOST"),
+        std::env::var("KUBERNETES_SERVICE_PORT"),
+    ) else {
+        return Err(ProviderError::InvalidConfig(
+            "not running inside a Kubernetes cluster; set --k8s-kubeconfig".into(),
+        ));
+    };
+    let host = if host.contains(':') {
+        format!("[{host}]")
+    } else {
+        host
+    };
+    let server = format!("https://{host}:{port}")
+        .parse()
+        .map_err(|e| ProviderError::InvalidConfig(format!("invalid API server address: {e}")))?;
+
+    let dir = Path::new(SERVICE_ACCOUNT_DIR);
+    let token = format!("file:{}", dir.join("token").display()).parse()?;
+    let ca = read_file(&dir.join("ca.crt")).await?;
+
+    Ok(Connection {
+        server,
+        token: Some(token),
+        ca: Some(ca),
+        identity: None,
+        insecure: false,
+    })
+}
+
+#[derive(Deserialize)]
+struct Kubeconfig {
+    #[serde(rename = "current-context")]
+    current_context: Option<String>,
+    #[serde(default)]
+    clusters: Vec<NamedCluster>,
+    #[serde(default)]
+    contexts: Vec<NamedContext>,
+    #[serde(default)]
+    users: Vec<NamedUser>,
+}
+
+#[derive(Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: Cluster,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Cluster {
+    server: String,
+    certificate_authority: Option<PathBuf>,
+    certificate_authority_data: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Deserialize)]
+struct NamedContext {
+    name: String,
+    context: Context,
+}
+
+#[derive(Deserialize)]
+struct Context {
+    cluster: String,
+    user: String,
+}
+
+#[derive(Deserialize)]
+struct NamedUser {
+    name: String,
+    #[serde(default)]
+    user: User,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "kebab-case")]
+struct User {
+    token: Option<SecretString>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<PathBuf>,
+    client_certificate: Option<PathBuf>,
+    client_certificate_data: Option<String>,
+    client_key: Option<PathBuf>,
+    client_key_data: Option<SecretString>,
+    exec: Option<serde_yaml::Value>,
+    #[serde(rename = "auth-provider")]
+    auth_provider: Option<serde_yaml::Value>,
+}
+
+/// Builds the connection from the selected (or current) context of a
+/// kubeconfig. Relative paths are resolved against the kubeconfig's
+/// directory, like kubectl does.
+async fn from_kubeconfig(path: &Path, context: Option<&str>) -> Result<Connection, ProviderError> {
+    let content = tokio::fs::read_to_string(path).await.map_err(|e| {
+        ProviderError::InvalidConfig(format!("failed to read kubeconfig {}: {e}", path.display()))
+    })?;
+    let kubeconfig: Kubeconfig = serde_yaml::from_str(&content).map_err(|e| {
+        ProviderError::InvalidConfig(format!("invalid kubeconfig {}: {e}", path.display()))
+    })?;
+    let base = path.parent().unwrap_or(Path::new("/"));
+
+    let context_name = context
+        .or(kubeconfig.current_context.as_deref())
+        .ok_or_else(|| {
+            ProviderError::InvalidConfig(
+                "kubeconfig has no current-context; set --k8s-context".into(),
+            )
+        })?;
+    let context = kubeconfig
+        .contexts
+        .iter()
+        .find(|c| c.name == context_name)
+        .map(|c| &c.context)
+        .ok_or_else(|| {
+            ProviderError::InvalidConfig(format!(
+                "context '{context_name}' not found in kubeconfig"
+            ))
+        })?;
+    let cluster = kubeconfig
+        .clusters
+        .iter()
+        .find(|c| c.name == context.cluster)
+        .map(|c| &c.cluster)
+        .ok_or_else(|| {
+            ProviderError::InvalidConfig(format!(
+                "cluster '{}' not found in kubeconfig",
+                context.cluster
+            ))
+        })?;
+    let user = kubeconfig
+        .users
+        .iter()
+        .find(|u| u.name == context.user)
+        .map(|u| &u.user)
+        .ok_or_else(|| {
+            ProviderError::InvalidConfig(format!("user '{}' not found in kubeconfig", context.user))
+        })?;
+
+    let server = cluster.server.parse().map_err(|e| {
+        ProviderError::InvalidConfig(format!("invalid server '{}': {e}", cluster.server))
+    })?;
+
+    let ca = match (
+        &cluster.certificate_authority_data,
+        &cluster.certificate_authority,
+    ) {
+        (Some(data), _) => Some(decode_data(data, "certificate-authority-data")?),
+        (None, Some(file)) => Some(read_file(&base.join(file)).await?),
+        (None, None) => None,
+    };
+
+    let token = match (&user.token, &user.token_file) {
+        (Some(token), _) => Some(AuthToken::new(token.clone())),
+        (None, Some(file)) => Some(format!("file:{}", base.join(file).display()).parse()?),
+        (None, None) => None,
+    };
+
+    let cert = match (&user.client_certificate_data, &user.client_certificate) {
+        (Some(data), _) => Some(decode_data(data, "client-certificate-data")?),
+        (None, Some(file)) => Some(read_file(&base.join(file)).await?),
+        (None, None) => None,
+    };
+    let key = match (&user.client_key_data, &user.client_key) {
+        (Some(data), _) => Some(decode_data(data.expose_secret(), "client-key-data")?),
+        (None, Some(file)) => Some(read_file(&base.join(file)).await?),
+        (None, None) => None,
+    };
+    let identity = match (cert, key) {
+        (Some(mut pem), Some(key)) => {
+            pem.push(b'\n');
+            pem.extend_from_slice(&key);
+            Some(pem)
+        }
+        (None, None) => None,
+        _ => {
+            return Err(ProviderError::InvalidConfig(format!(
+                "user '{}' must have both a client certificate and key",
+                context.user
+            )));
+        }
+    };
+
+    if token.is_none()
+        && identity.is_none()
+        && (user.exec.is_some() || user.auth_provider.is_some())
+    {
+        return Err(ProviderError::InvalidConfig(format!(
+            "user '{}' uses an exec or auth-provider plugin, which is not supported; \
+             provide a token with --k8s-token instead",
+            context.user
+        )));
+    }
+
+    Ok(Connection {
+        server,
+        token,
+        ca,
+        identity,
+        insecure: cluster.insecure_skip_tls_verify,
+    })
+}
+
+fn decode_data(data: &str, field: &str) -> Result<Vec<u8>, ProviderError> {
+    STANDARD
+        .decode(data.trim())
+        .map_err(|e| ProviderError::InvalidConfig(format!("invalid {field} in kubeconfig: {e}")))
+}
+
+async fn read_file(path: &Path) -> Result<Vec<u8>, ProviderError> {
+    tokio::fs::read(path).await.map_err(|e| {
+        ProviderError::InvalidConfig(format!("failed to read {}: {e}", path.display()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::AbsolutePath;
+    use crate::provider::ReferenceParser;
+    use crate::provider::test_server::TestServer;
+
+    fn write_kubeconfig(dir: &Path, server: &str) -> PathBuf {
+        std::fs::write(dir.join("ci-token"), "ci-token\n").unwrap();
+        let path = dir.join("config");
+        std::fs::write(
+            &path,
+            format!(
+                r#"apiVersion: v1
+kind: Config
+current-context: dev
+clusters:
+  - name: local
+    cluster:
+      server: {server}
+contexts:
+  - name: dev
+    context:
+      cluster: local
+      user: developer
+  - name: ci
+    context:
+      cluster: local
+      user: ci
+users:
+  - name: developer
+    user:
+      token: dev-token
+  - name: ci
+    user:
+      tokenFile: ci-token
+"#
+            ),
+        )
+        .unwrap();
+        path
+    }
+
+    fn config(kubeconfig: &Path, context: Option<&str>) -> K8sConfig {
+        K8sConfig {
+            k8s_kubeconfig: Some(AbsolutePath::new(kubeconfig)),
+            k8s_context: context.map(String::from),
+            k8s_token: None,
+            k8s_max_concurrent: ConcurrencyLimit::new(4),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_groups_by_secret() {
+        let server = TestServer::start(|req| match req.path.as_str() {
+            "/api/v1/namespaces/default/secrets/db" => (
+                200,
+                format!(
+                    r#"{{"kind":"Secret","data":{{"username":"{}","password":"{}"}}}}"#,
+                    STANDARD.encode("admin"),
+                    STANDARD.encode("hunter2")
+                ),
+            ),
+            _ => (404, r#"{"kind":"Status","code":404}"#.into()),
+        });
+        let dir = tempfile::tempdir().unwrap();
+        let kubeconfig = write_kubeconfig(dir.path(), server.url());
+        let provider = K8sProvider::new(config(&kubeconfig, None)).await.unwrap();
+
+        let refs: Vec<SecretReference> = [
+            "k8s://default/db/username",
+            "k8s://default/db/password",
+            "k8s://default/db/missing",
+            "k8s://other/api/token",
+        ]
+        .into_iter()
+        .map(|r| provider.parse(r).unwrap())
+        .collect();
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "admin");
+        assert_eq!(map[&refs[1]].expose_secret(), "hunter2");
+        assert!(!map.contains_key(&refs[2]));
+        assert!(!map.contains_key(&refs[3]));
+
+        let mut paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "/api/v1/namespaces/default/secrets/db",
+                "/api/v1/namespaces/other/secrets/api"
+            ]
+        );
+        assert!(server.requests().iter().all(|r| r.method == "GET"
+            && r.body.is_empty()
+            && r.header("authorization") == Some("Bearer dev-token")));
+    }
+
+    #[tokio::test]
+    async fn test_kubeconfig_context_and_token_file() {
+        let server = TestServer::start(|_| (200, r#"{"data":{}}"#.into()));
+        let dir = tempfile::tempdir().unwrap();
+        let kubeconfig = write_kubeconfig(dir.path(), server.url());
+        let provider = K8sProvider::new(config(&kubeconfig, Some("ci")))
+            .await
+            .unwrap();
+
+        let refs = [provider.parse("k8s://default/db/password").unwrap()];
+        provider.fetch_map(&refs).await.unwrap();
+        assert_eq!(
+            server.requests()[0].header("authorization"),
+            Some("Bearer ci-token")
+        );
+
+        let err = K8sProvider::new(config(&kubeconfig, Some("prod")))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::InvalidConfig(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_forbidden() {
+        let server = TestServer::start(|_| (403, r#"{"kind":"Status","code":403}"#.into()));
+        let dir = tempfile::tempdir().unwrap();
+        let kubeconfig = write_kubeconfig(dir.path(), server.url());
+        let provider = K8sProvider::new(config(&kubeconfig, None)).await.unwrap();
+
+        let refs = [provider.parse("k8s://default/db/password").unwrap()];
+        let err = provider.fetch_map(&refs).await.unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+}
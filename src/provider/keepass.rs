@@ -0,0 +1,254 @@
+//! KeePass (.kdbx) database provider implementation.
+//!
+//! Opens a local KDBX database with a master password and/or key file,
+//! and resolves references against its group tree, including custom string
+//! fields and attachments.
+//!
+//! The database is decrypted once when the provider is built. Before every
+//! batch, the file is checked for changes. A changed file fails the batch,
+//! which makes `ManagedProvider` rebuild the provider from the new content
+//! (its `Signature` includes the database hash) and retry.
+
+use super::{
+    ProviderError, SecretsProvider,
+    config::keepass::{KeePassConfig, content_signature, database_signature, read_database},
+    references::{Extract, HasReference, KeePassField, KeePassReference, SecretReference},
+};
+use async_trait::async_trait;
+use keepass::db::{DatabaseOpenError, fields};
+use keepass::{Database, DatabaseKey, error::DatabaseKeyError};
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tracing::debug;
+
+pub struct KeePassProvider {
+    path: PathBuf,
+    signature: u64,
+    db: Database,
+}
+
+impl KeePassProvider {
+    pub async fn new(config: KeePassConfig) -> Result<Self, ProviderError> {
+        if config.keepass_password.is_none() && config.keepass_key_file.is_none() {
+            return Err(ProviderError::InvalidConfig(
+                "KeePass requires --keepass-password, --keepass-key-file, or both".into(),
+            ));
+        }
+
+        let mut key = DatabaseKey::new();
+        if let Some(password) = &config.keepass_password {
+            key = key.with_password(password.resolve().await?.expose_secret());
+        }
+        if let Some(key_file) = &config.keepass_key_file {
+            key = key.with_keyfile(&mut key_file.resolve().await?.expose_secret().as_bytes())?;
+        }
+
+        let path = config.keepass_db.into_inner();
+        let content = read_database(&path).await?;
+        let signature = content_signature(&content);
+
+        // Key derivation is deliberately expensive, so keep it off the runtime.
+        let db = tokio::task::spawn_blocking(move || Database::parse(&content, key))
+            .await
+            .map_err(|e| ProviderError::Other(e.to_string()))?
+            .map_err(|e| match e {
+                DatabaseOpenError::Key(DatabaseKeyError::IncorrectKey) => {
+                    ProviderError::Unauthorized(format!(
+                        "incorrect password or key file for {}",
+                        path.display()
+                    ))
+                }
+                DatabaseOpenError::Key(e) => ProviderError::InvalidConfig(e.to_string()),
+                e => ProviderError::Other(format!(
+                    "failed to open KeePass database {}: {e}",
+                    path.display()
+                )),
+            })?;
+
+        Ok(Self {
+            path,
+            signature,
+            db,
+        })
+    }
+
+    /// Resolves a single reference. Returns `None` if any part of the path
+    /// does not exist.
+    fn lookup(&self, reference: &KeePassReference) -> Result<Option<SecretString>, ProviderError> {
+        let groups: Vec<&str> = reference.groups().iter().map(String::as_str).collect();
+        let root = self.db.root();
+        let Some(group) = root.group_by_path(&groups) else {
+            return Ok(None);
+        };
+        let Some(entry) = group.entry_by_name(reference.entry()) else {
+            return Ok(None);
+        };
+
+        let key = match reference.field() {
+            KeePassField::Title => fields::TITLE,
+            KeePassField::UserName => fields::USERNAME,
+            KeePassField::Password => fields::PASSWORD,
+            KeePassField::Url => fields::URL,
+            KeePassField::Notes => fields::NOTES,
+            KeePassField::Custom(name) => name,
+            KeePassField::Attachment(name) => {
+                let Some(attachment) = entry.attachment_by_name(name) else {
+                    return Ok(None);
+                };
+                let value = String::from_utf8(attachment.get().clone()).map_err(|_| {
+                    ProviderError::Other(format!("attachment of {} is not valid UTF-8", reference))
+                })?;
+                return Ok(Some(SecretString::from(value)));
+            }
+        };
+
+        Ok(entry.get(key).map(SecretString::from))
+    }
+}
+
+impl HasReference for KeePassProvider {
+    type Reference = KeePassReference;
+}
+
+#[async_trait]
+impl SecretsProvider for KeePassProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let refs: HashSet<&KeePassReference> = references
+            .iter()
+            .filter_map(KeePassReference::extract)
+            .collect();
+
+        if refs.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        if database_signature(&self.path).await? != self.signature {
+            return Err(ProviderError::Other(format!(
+                "KeePass database {} changed on disk",
+                self.path.display()
+            )));
+        }
+
+        let mut map = HashMap::new();
+        for r in refs {
+            match self.lookup(r)? {
+                Some(value) => {
+                    map.insert(SecretReference::KeePass(r.clone()), value);
+                }
+                None => debug!("{} not found", r),
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::AbsolutePath;
+    use crate::provider::managed::ManagedProvider;
+    use crate::provider::{AuthToken, Provider, ReferenceParser};
+    use keepass::config::KdfConfig;
+    use keepass::db::Value;
+
+    /// Writes a small database protected by `password`, with a cheap KDF to
+    /// keep the tests fast.
+    fn write_database(path: &std::path::Path, password: &str, db_password: &str) {
+        let mut db = Database::new();
+        db.config.kdf_config = KdfConfig::Aes { rounds: 10 };
+
+        let mut root = db.root_mut();
+        root.add_entry().edit(|e| {
+            e.set_unprotected(fields::TITLE, "GitHub");
+            e.set_unprotected(fields::USERNAME, "octocat");
+            e.set_protected(fields::PASSWORD, "gh-pass");
+        });
+        let mut work = root.add_group();
+        work.name = "Work".into();
+        let mut servers = work.add_group();
+        servers.name = "Servers".into();
+        servers.add_entry().edit(|e| {
+            e.set_unprotected(fields::TITLE, "db01");
+            e.set_protected(fields::PASSWORD, db_password);
+            e.set_protected("Deploy Key", "custom-value");
+            e.add_attachment("id_ed25519", Value::protected(b"PRIVATE KEY".to_vec()));
+        });
+
+        let mut file = std::fs::File::create(path).unwrap();
+        db.save(&mut file, DatabaseKey::new().with_password(password))
+            .unwrap();
+    }
+
+    fn config(path: &std::path::Path, password: &str) -> KeePassConfig {
+        KeePassConfig {
+            keepass_db: AbsolutePath::new(path),
+            keepass_password: Some(AuthToken::new(SecretString::from(password.to_string()))),
+            keepass_key_file: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_fields_and_attachments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.kdbx");
+        write_database(&path, "master", "db-pass");
+
+        let provider = KeePassProvider::new(config(&path, "master")).await.unwrap();
+        let refs: Vec<SecretReference> = [
+            "kp://GitHub/username",
+            "kp://GitHub/password",
+            "kp://work/servers/db01/password",
+            "kp://Work/Servers/db01/Deploy%20Key",
+            "kp://Work/Servers/db01/attachment:id_ed25519",
+            "kp://Work/Servers/missing/password",
+            "kp://Work/Servers/db01/attachment:missing",
+        ]
+        .into_iter()
+        .map(|r| provider.parse(r).unwrap())
+        .collect();
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "octocat");
+        assert_eq!(map[&refs[1]].expose_secret(), "gh-pass");
+        assert_eq!(map[&refs[2]].expose_secret(), "db-pass");
+        assert_eq!(map[&refs[3]].expose_secret(), "custom-value");
+        assert_eq!(map[&refs[4]].expose_secret(), "PRIVATE KEY");
+        assert!(!map.contains_key(&refs[5]));
+        assert!(!map.contains_key(&refs[6]));
+    }
+
+    #[tokio::test]
+    async fn test_incorrect_password() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.kdbx");
+        write_database(&path, "master", "db-pass");
+
+        let err = KeePassProvider::new(config(&path, "wrong"))
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_edits_are_picked_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vault.kdbx");
+        write_database(&path, "master", "old-pass");
+
+        let provider = ManagedProvider::new(Provider::KeePass(config(&path, "master")))
+            .await
+            .unwrap();
+        let refs = [provider.parse("kp://Work/Servers/db01/password").unwrap()];
+        let map = provider.fetch_map(&refs).await.unwrap();
+        assert_eq!(map[&refs[0]].expose_secret(), "old-pass");
+
+        write_database(&path, "master", "new-pass");
+        let map = provider.fetch_map(&refs).await.unwrap();
+        assert_eq!(map[&refs[0]].expose_secret(), "new-pass");
+    }
+}
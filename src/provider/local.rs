@@ -0,0 +1,412 @@
+//! Local reference resolution.
+//!
+//! Resolves `file://`, `creds://` and `env://` references from locket's own
+//! filesystem and process environment. This backend is always available
+//! next to the configured providers, so mounted secrets, service
+//! credentials and CI variables can be mixed into the same templates.
+//!
+//! Since templates may come from less trusted sources than locket's own
+//! environment, `file://` and `env://` are opt-in: they only read files
+//! below the allowed directories and variables with an allowed prefix.
+//! `creds://` is confined to the credentials directories by design.
+
+use super::{
+    ProviderError, ReferenceParser, SecretsProvider,
+    references::{
+        CredsReference, EnvReference, Extract, FileReference, SecretReference,
+        parse_local_reference,
+    },
+};
+use crate::path::AbsolutePath;
+use crate::secrets::{MemSize, SecretError, SecretSource};
+use async_trait::async_trait;
+use clap::Args;
+use locket_derive::LayeredConfig;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::env::VarError;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Where Docker and Swarm mount secrets into a container.
+const DOCKER_SECRETS_DIR: &str = "/run/secrets";
+
+/// What local references may read. Nothing by default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LocalConfig {
+    pub env_prefixes: Vec<String>,
+    pub file_dirs: Vec<AbsolutePath>,
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub struct LocalArgs {
+    /// Allow `env://` references to variables whose name starts with one of
+    /// these prefixes
+    ///
+    /// `env://` references are not resolved unless a prefix is given.
+    ///
+    /// Example: `--provider-local-env-allow CI_,DEPLOY_`
+    #[arg(
+        long,
+        env = "SECRETS_PROVIDER_LOCAL_ENV_ALLOW",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub provider_local_env_allow: Vec<String>,
+
+    /// Allow `file://` references to files below these directories
+    ///
+    /// `file://` references are not resolved unless a directory is given.
+    /// Links are resolved before the check, so a file can't be reached
+    /// through a link from an allowed directory.
+    ///
+    /// Example: `--provider-local-file-allow /run/secrets`
+    #[arg(
+        long,
+        env = "SECRETS_PROVIDER_LOCAL_FILE_ALLOW",
+        value_delimiter = ',',
+        action = clap::ArgAction::Append
+    )]
+    #[serde(default, deserialize_with = "crate::config::parsers::delimited_vec")]
+    #[locket(overlay = "crate::config::parsers::vec_replace")]
+    pub provider_local_file_allow: Vec<AbsolutePath>,
+}
+
+impl TryFrom<LocalArgs> for LocalConfig {
+    type Error = crate::error::LocketError;
+
+    fn try_from(args: LocalArgs) -> Result<Self, Self::Error> {
+        if args.provider_local_env_allow.iter().any(String::is_empty) {
+            return Err(crate::config::ConfigError::Validation(
+                "--provider-local-env-allow prefixes must not be empty".into(),
+            )
+            .into());
+        }
+        Ok(Self {
+            env_prefixes: args.provider_local_env_allow,
+            file_dirs: args.provider_local_file_allow,
+        })
+    }
+}
+
+pub struct LocalProvider {
+    config: LocalConfig,
+}
+
+/// Removes the single trailing newline most tools add.
+fn trim_newline(mut content: String) -> String {
+    if content.ends_with('\n') {
+        content.pop();
+        if content.ends_with('\r') {
+            content.pop();
+        }
+    }
+    content
+}
+
+/// The directories `creds://` references are looked up in, in order.
+///
+/// The systemd credentials directory of the current unit comes first, so a
+/// unit can shadow a secret which is also mounted into the container.
+fn credentials_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::with_capacity(2);
+    if let Some(dir) = std::env::var_os("CREDENTIALS_DIRECTORY") {
+        dirs.push(PathBuf::from(dir));
+    }
+    dirs.push(PathBuf::from(DOCKER_SECRETS_DIR));
+    dirs
+}
+
+/// Reads a file through a `SourceReader`, so an unexpectedly large file is
+/// rejected instead of buffered. Returns `None` if it was removed before
+/// the read.
+async fn read_bounded(source: SecretSource) -> Result<Option<SecretString>, ProviderError> {
+    let content = tokio::task::spawn_blocking(move || {
+        source
+            .read()
+            .limit(MemSize::default())
+            .fetch()
+            .map(|c| c.map(|c| c.into_owned()))
+    })
+    .await
+    .map_err(|e| ProviderError::Other(e.to_string()))?;
+
+    match content {
+        Ok(content) => Ok(content.map(|c| SecretString::from(trim_newline(c)))),
+        Err(SecretError::Io(e)) => Err(ProviderError::Io(e)),
+        Err(e) => Err(ProviderError::Other(e.to_string())),
+    }
+}
+
+impl LocalProvider {
+    pub fn new(config: LocalConfig) -> Self {
+        Self { config }
+    }
+
+    fn is_env_allowed(&self, name: &str) -> bool {
+        self.config
+            .env_prefixes
+            .iter()
+            .any(|prefix| name.starts_with(prefix.as_str()))
+    }
+
+    /// Whether a canonical path is below one of the allowed directories.
+    ///
+    /// The directories are resolved on every check, since they may be
+    /// mounted after locket started.
+    fn is_file_allowed(&self, path: &Path) -> bool {
+        self.config
+            .file_dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| path.starts_with(&*dir))
+    }
+
+    /// Reads a file, without the single trailing newline most tools add.
+    /// Returns `None` if it does not exist or is not allowed.
+    async fn read_file(
+        &self,
+        reference: &FileReference,
+    ) -> Result<Option<SecretString>, ProviderError> {
+        let source = match SecretSource::file(reference.path()) {
+            Ok(source) => source,
+            Err(SecretError::SourceMissing(_)) => {
+                debug!("file {} not found", reference.path().display());
+                return Ok(None);
+            }
+            Err(e) => return Err(ProviderError::Other(e.to_string())),
+        };
+        let allowed = source.path().is_some_and(|p| self.is_file_allowed(p));
+        if !allowed {
+            warn!(
+                "{} is not below a directory allowed by --provider-local-file-allow",
+                reference
+            );
+            return Ok(None);
+        }
+        read_bounded(source).await
+    }
+
+    /// Reads a credential from the first directory which contains it.
+    /// Returns `None` if none of them do.
+    async fn read_credential(
+        dirs: &[PathBuf],
+        reference: &CredsReference,
+    ) -> Result<Option<SecretString>, ProviderError> {
+        for dir in dirs {
+            let path = dir.join(reference.name());
+            let source = match SecretSource::file(&path) {
+                Ok(source) => source,
+                Err(SecretError::SourceMissing(_)) => continue,
+                Err(e) => return Err(ProviderError::Other(e.to_string())),
+            };
+            // `None` if removed between the lookup and the read
+            if let Some(value) = read_bounded(source).await? {
+                return Ok(Some(value));
+            }
+        }
+        debug!("credential {} not found", reference.name());
+        Ok(None)
+    }
+
+    fn read_env(&self, reference: &EnvReference) -> Option<SecretString> {
+        if !self.is_env_allowed(reference.name()) {
+            warn!(
+                "{} does not match a prefix allowed by --provider-local-env-allow",
+                reference
+            );
+            return None;
+        }
+        match std::env::var(reference.name()) {
+            Ok(value) => Some(SecretString::from(value)),
+            Err(VarError::NotPresent) => None,
+            Err(VarError::NotUnicode(_)) => {
+                warn!(
+                    "environment variable {} is not valid UTF-8",
+                    reference.name()
+                );
+                None
+            }
+        }
+    }
+}
+
+impl ReferenceParser for LocalProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        parse_local_reference(raw)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for LocalProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let mut map = HashMap::new();
+
+        let files: HashSet<&FileReference> = references
+            .iter()
+            .filter_map(FileReference::extract)
+            .collect();
+        for r in files {
+            if let Some(value) = self.read_file(r).await? {
+                map.insert(SecretReference::File(r.clone()), value);
+            }
+        }
+
+        let creds: HashSet<&CredsReference> = references
+            .iter()
+            .filter_map(CredsReference::extract)
+            .collect();
+        if !creds.is_empty() {
+            let dirs = credentials_dirs();
+            for r in creds {
+                if let Some(value) = Self::read_credential(&dirs, r).await? {
+                    map.insert(SecretReference::Creds(r.clone()), value);
+                }
+            }
+        }
+
+        for r in references.iter().filter_map(EnvReference::extract) {
+            if let Some(value) = self.read_env(r) {
+                map.insert(SecretReference::Env(r.clone()), value);
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+    use std::io::Write;
+
+    fn reference(raw: &str) -> SecretReference {
+        parse_local_reference(raw).unwrap()
+    }
+
+    fn allowing(dir: &Path, prefixes: &[&str]) -> LocalProvider {
+        LocalProvider::new(LocalConfig {
+            env_prefixes: prefixes.iter().map(|p| p.to_string()).collect(),
+            file_dirs: vec![AbsolutePath::new(dir)],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fetch_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut file = tempfile::NamedTempFile::new_in(dir.path()).unwrap();
+        file.write_all(b"s3cr3t\n").unwrap();
+
+        let refs = [
+            reference(&format!("file://{}", file.path().display())),
+            reference(&format!("file://{}/missing", dir.path().display())),
+            reference("env://PATH"),
+            reference("env://LOCKET_TEST_SURELY_UNSET"),
+        ];
+        let provider = allowing(dir.path(), &["PATH", "LOCKET_TEST_"]);
+        let map = provider.fetch_map(&refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]].expose_secret(), "s3cr3t");
+        assert!(!map.contains_key(&refs[1]));
+        assert_eq!(
+            map[&refs[2]].expose_secret(),
+            std::env::var("PATH").unwrap()
+        );
+        assert!(!map.contains_key(&refs[3]));
+    }
+
+    #[tokio::test]
+    async fn test_local_references_are_opt_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let allowed = dir.path().join("allowed");
+        std::fs::create_dir(&allowed).unwrap();
+        std::fs::write(allowed.join("token"), "in").unwrap();
+        std::fs::write(dir.path().join("secret"), "out").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("secret"), allowed.join("link")).unwrap();
+        let large = vec![b'a'; MemSize::default().bytes as usize + 1];
+        std::fs::write(allowed.join("large"), large).unwrap();
+
+        let inside = reference(&format!("file://{}/token", allowed.display()));
+        let refs = [
+            reference(&format!("file://{}/secret", dir.path().display())),
+            reference(&format!("file://{}/../secret", allowed.display())),
+            reference(&format!("file://{}/link", allowed.display())),
+            reference("env://PATH"),
+        ];
+
+        let disabled = LocalProvider::new(LocalConfig::default());
+        let map = disabled.fetch_map(&refs).await.unwrap();
+        assert!(map.is_empty());
+        let map = disabled
+            .fetch_map(std::slice::from_ref(&inside))
+            .await
+            .unwrap();
+        assert!(map.is_empty());
+
+        let provider = allowing(&allowed, &["LOCKET_"]);
+        let map = provider.fetch_map(&refs).await.unwrap();
+        assert!(map.is_empty(), "{:?}", map.keys().collect::<Vec<_>>());
+        let map = provider
+            .fetch_map(std::slice::from_ref(&inside))
+            .await
+            .unwrap();
+        assert_eq!(map[&inside].expose_secret(), "in");
+
+        let large = reference(&format!("file://{}/large", allowed.display()));
+        assert!(provider.fetch_map(&[large]).await.is_err());
+    }
+
+    #[test]
+    fn test_empty_env_prefix_is_rejected() {
+        let args = LocalArgs {
+            provider_local_env_allow: vec![String::new()],
+            ..Default::default()
+        };
+        assert!(LocalConfig::try_from(args).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_credential() {
+        let systemd = tempfile::tempdir().unwrap();
+        let docker = tempfile::tempdir().unwrap();
+        std::fs::write(systemd.path().join("db_password"), "from-systemd\n").unwrap();
+        std::fs::write(docker.path().join("db_password"), "from-docker").unwrap();
+        std::fs::write(docker.path().join("api_key"), "abc").unwrap();
+        let dirs = [systemd.path().to_path_buf(), docker.path().to_path_buf()];
+
+        let read = |name: &str| {
+            let r = CredsReference::extract(&reference(&format!("creds://{name}")))
+                .unwrap()
+                .clone();
+            let dirs = dirs.clone();
+            async move { LocalProvider::read_credential(&dirs, &r).await }
+        };
+
+        let value = read("db_password").await.unwrap().unwrap();
+        assert_eq!(value.expose_secret(), "from-systemd");
+        let value = read("api_key").await.unwrap().unwrap();
+        assert_eq!(value.expose_secret(), "abc");
+        assert!(read("missing").await.unwrap().is_none());
+
+        let large = vec![b'a'; MemSize::default().bytes as usize + 1];
+        std::fs::write(docker.path().join("large"), large).unwrap();
+        assert!(read("large").await.is_err());
+    }
+
+    #[test]
+    fn test_only_local_references_are_recognized() {
+        let provider = LocalProvider::new(LocalConfig::default());
+        assert!(provider.parse("op://vault/item/field").is_none());
+        assert!(provider.parse("file://relative/path").is_none());
+    }
+}
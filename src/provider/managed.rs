@@ -1,9 +1,12 @@
-use super::{ProviderError, ReferenceParser, SecretReference, SecretsProvider, Signature};
+use super::{
+    ProviderError, ReferenceParser, SecretReference, SecretsProvider, Signature, retry::RetryPolicy,
+};
 use async_trait::async_trait;
 use secrecy::SecretString;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Instant;
+use tokio::sync::{RwLock, watch};
 
 /// A factory trait that creates specific backend clients from configuration.
 #[async_trait]
@@ -11,14 +14,21 @@ pub trait ProviderFactory: Signature + ReferenceParser + Send + Sync + Sized + C
     async fn create(&self) -> Result<Arc<dyn SecretsProvider>, ProviderError>;
 }
 
-/// A wrapper that handles automatic rotation of the underlying provider.
+/// A wrapper that handles automatic rotation of the underlying provider,
+/// and retries transient failures according to its [`RetryPolicy`].
 pub struct ManagedProvider<C> {
     config: C,
+    retry: RetryPolicy,
     state: RwLock<ProviderState>,
+    /// Bumped whenever the inner provider is replaced
+    rebuilt: watch::Sender<u64>,
+    /// Bumped whenever a replaced provider released its leases
+    revoked: watch::Sender<u64>,
 }
 
 struct ProviderState {
-    inner: Arc<dyn SecretsProvider>,
+    /// `None` until a deferred client could be created
+    inner: Option<Arc<dyn SecretsProvider>>,
     signature: u64,
 }
 
@@ -31,30 +41,67 @@ where
         let inner = config.create().await?;
         Ok(Self {
             config,
+            retry: RetryPolicy::default(),
+            state: RwLock::new(ProviderState {
+                inner: Some(inner),
+                signature,
+            }),
+            rebuilt: watch::Sender::new(0),
+            revoked: watch::Sender::new(0),
+        })
+    }
+
+    /// Like [`ManagedProvider::new`], but tolerates a backend which is
+    /// unreachable at startup. Creating the client is then retried on
+    /// every fetch until it succeeds.
+    pub async fn new_deferred(config: C) -> Result<Self, ProviderError> {
+        let signature = config.signature().await?;
+        let inner = match config.create().await {
+            Ok(inner) => Some(inner),
+            Err(e) if e.is_transient() => {
+                tracing::warn!("Provider is unreachable, deferring its setup: {}", e);
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            config,
+            retry: RetryPolicy::default(),
             state: RwLock::new(ProviderState { inner, signature }),
+            rebuilt: watch::Sender::new(0),
+            revoked: watch::Sender::new(0),
         })
     }
-}
 
-#[async_trait]
-impl<C> SecretsProvider for ManagedProvider<C>
-where
-    C: ProviderFactory + 'static,
-{
-    async fn fetch_map(
+    /// Whether the inner client exists, rather than being deferred.
+    pub async fn is_ready(&self) -> bool {
+        self.state.read().await.inner.is_some()
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fetches a batch once. If that fails, and the configuration changed
+    /// since the inner provider was built, it is rebuilt and the batch is
+    /// fetched once more.
+    async fn fetch_once(
         &self,
         references: &[SecretReference],
     ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
-        {
+        let failed = {
             let state = self.state.read().await;
-            match state.inner.fetch_map(references).await {
-                Ok(res) => return Ok(res),
-                Err(_) => {
-                    // Fallthrough to rotation logic
-                    // don't propagate the error yet.
-                }
+            match &state.inner {
+                Some(inner) => match inner.fetch_map(references).await {
+                    Ok(res) => return Ok(res),
+                    // The failure may be caused by a changed configuration,
+                    // so don't propagate it yet.
+                    Err(e) => Some((inner.clone(), e)),
+                },
+                None => None,
             }
-        }
+        };
 
         // Check signature of the config to see if it has changed
         let new_signature = match self.config.signature().await {
@@ -66,7 +113,8 @@ where
         let mut state = self.state.write().await;
 
         // Check again in case another task already rotated
-        if state.signature != new_signature {
+        let mut replaced = None;
+        if state.inner.is_none() || state.signature != new_signature {
             // The config has changed, which may be the cause of the prior failure.
             // Rebuild the inner provider and swap it in.
             let new_inner = match self.config.create().await {
@@ -74,16 +122,109 @@ where
                 Err(e) => return Err(e), // Failed to rebuild
             };
 
-            state.inner = new_inner;
+            replaced = state.inner.replace(new_inner);
             state.signature = new_signature;
+            self.rebuilt.send_modify(|generation| *generation += 1);
+        } else if let Some((failed, e)) = failed
+            && state
+                .inner
+                .as_ref()
+                .is_some_and(|inner| Arc::ptr_eq(inner, &failed))
+        {
+            // Nothing changed, so fetching again would fail the same way.
+            return Err(e);
         }
 
         // Retry the fetch with the (possibly) new inner provider
 
-        let inner = state.inner.clone();
+        let inner = state
+            .inner
+            .clone()
+            .expect("inner provider is created above");
         drop(state);
 
-        inner.fetch_map(references).await
+        let result = inner.fetch_map(references).await;
+
+        // Release whatever the replaced provider holds, such as leases,
+        // which would otherwise outlive it. Secrets fetched under them by
+        // other consumers are no longer valid, so they must refresh.
+        if let Some(old) = replaced {
+            let leased = old.holds_leases().await;
+            old.shutdown().await;
+            if leased {
+                self.revoked.send_modify(|generation| *generation += 1);
+            }
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl<C> SecretsProvider for ManagedProvider<C>
+where
+    C: ProviderFactory + 'static,
+{
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let start = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let err = match self.fetch_once(references).await {
+                Ok(res) => return Ok(res),
+                Err(e) => e,
+            };
+            let Some(delay) = self.retry.next_delay(attempt, start.elapsed(), &err) else {
+                return Err(err);
+            };
+            tracing::warn!(
+                "Attempt {} of {} failed: {}. Retrying in {:?}",
+                attempt,
+                self.retry.max_attempts,
+                err,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn expired(&self) {
+        // Subscribe before reading the inner provider, so a rebuild in
+        // between is not missed.
+        let mut rebuilt = self.rebuilt.subscribe();
+        let mut revoked = self.revoked.subscribe();
+        loop {
+            let inner = self.state.read().await.inner.clone();
+            let expired = async {
+                match inner {
+                    Some(inner) => inner.expired().await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = expired => return,
+                _ = revoked.changed() => return,
+                // Watch the expiry of the replacement instead.
+                _ = rebuilt.changed() => continue,
+            }
+        }
+    }
+
+    async fn holds_leases(&self) -> bool {
+        let inner = self.state.read().await.inner.clone();
+        match inner {
+            Some(inner) => inner.holds_leases().await,
+            None => false,
+        }
+    }
+
+    async fn shutdown(&self) {
+        let inner = self.state.read().await.inner.clone();
+        if let Some(inner) = inner {
+            inner.shutdown().await;
+        }
     }
 }
 
@@ -95,3 +236,170 @@ where
         self.config.parse(raw)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::time::Duration;
+    use tokio::sync::Notify;
+
+    /// A client built for one generation of a configuration, which is
+    /// denied access once the configuration moved on.
+    struct Client {
+        generation: u64,
+        current: Arc<AtomicU64>,
+        down: Arc<AtomicBool>,
+        fetches: AtomicU64,
+        leased: bool,
+        expire: Notify,
+        shut_down: AtomicBool,
+    }
+
+    impl ReferenceParser for Client {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for Client {
+        async fn fetch_map(
+            &self,
+            _references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                return Err(ProviderError::Unavailable("down".into()));
+            }
+            if self.current.load(Ordering::SeqCst) != self.generation {
+                return Err(ProviderError::Unauthorized("rotated".into()));
+            }
+            Ok(HashMap::new())
+        }
+
+        async fn expired(&self) {
+            self.expire.notified().await
+        }
+
+        async fn holds_leases(&self) -> bool {
+            self.leased
+        }
+
+        async fn shutdown(&self) {
+            self.shut_down.store(true, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct RotatingConfig {
+        generation: Arc<AtomicU64>,
+        down: Arc<AtomicBool>,
+        leased: bool,
+        built: Arc<Mutex<Vec<Arc<Client>>>>,
+    }
+
+    impl RotatingConfig {
+        fn client(&self, idx: usize) -> Arc<Client> {
+            self.built.lock().unwrap()[idx].clone()
+        }
+    }
+
+    #[async_trait]
+    impl Signature for RotatingConfig {
+        async fn signature(&self) -> Result<u64, ProviderError> {
+            Ok(self.generation.load(Ordering::SeqCst))
+        }
+    }
+
+    impl ReferenceParser for RotatingConfig {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl ProviderFactory for RotatingConfig {
+        async fn create(&self) -> Result<Arc<dyn SecretsProvider>, ProviderError> {
+            let client = Arc::new(Client {
+                generation: self.generation.load(Ordering::SeqCst),
+                current: self.generation.clone(),
+                down: self.down.clone(),
+                fetches: AtomicU64::new(0),
+                leased: self.leased,
+                expire: Notify::new(),
+                shut_down: AtomicBool::new(false),
+            });
+            self.built.lock().unwrap().push(client.clone());
+            Ok(client)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_shuts_down_replaced_provider_and_follows_expiry() {
+        let config = RotatingConfig::default();
+        let managed = Arc::new(ManagedProvider::new(config.clone()).await.unwrap());
+
+        let watcher = tokio::spawn({
+            let managed = managed.clone();
+            async move { managed.expired().await }
+        });
+        tokio::task::yield_now().await;
+
+        // Rotate the configuration, so the next fetch rebuilds the client.
+        config.generation.store(1, Ordering::SeqCst);
+        managed.fetch_map(&[]).await.unwrap();
+        assert!(config.client(0).shut_down.load(Ordering::SeqCst));
+        assert!(!config.client(1).shut_down.load(Ordering::SeqCst));
+
+        // The watcher started on the old client sees the new one expire.
+        config.client(1).expire.notify_one();
+        tokio::time::timeout(Duration::from_secs(5), watcher)
+            .await
+            .expect("expiry of the rebuilt provider is observed")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_revoking_leases_expires_other_consumers() {
+        let config = RotatingConfig {
+            leased: true,
+            ..Default::default()
+        };
+        let managed = Arc::new(ManagedProvider::new(config.clone()).await.unwrap());
+
+        // Consumer B waits for the expiry of what it rendered.
+        let watcher = tokio::spawn({
+            let managed = managed.clone();
+            async move { managed.expired().await }
+        });
+        tokio::task::yield_now().await;
+
+        // Consumer A's fetch rebuilds the client, revoking B's leases.
+        config.generation.store(1, Ordering::SeqCst);
+        managed.fetch_map(&[]).await.unwrap();
+        assert!(config.client(0).shut_down.load(Ordering::SeqCst));
+
+        tokio::time::timeout(Duration::from_secs(5), watcher)
+            .await
+            .expect("revoked leases expire every consumer")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failure_without_rebuild_fetches_once() {
+        let config = RotatingConfig::default();
+        let managed = ManagedProvider::new(config.clone())
+            .await
+            .unwrap()
+            .with_retry(RetryPolicy::none());
+
+        config.down.store(true, Ordering::SeqCst);
+        let err = managed.fetch_map(&[]).await.unwrap_err();
+
+        assert!(matches!(err, ProviderError::Unavailable(_)), "{err}");
+        assert_eq!(config.client(0).fetches.load(Ordering::SeqCst), 1);
+        assert_eq!(config.built.lock().unwrap().len(), 1);
+    }
+}
@@ -1,6 +1,7 @@
 //! Everything required to run and talk to the bundled
-//! `locket-op-bridge` process: executable discovery, process spawning,
-//! and the JSON pipe protocol.
+//! `locket-op-bridge` process: executable discovery and process
+//! spawning. The JSON pipe protocol itself is shared with other
+//! provider plugins in [`crate::provider::plugin`].
 //!
 //! This module is deliberately self-contained behind the [`Bridge`]
 //! facade so that a future backend (e.g. an official 1Password Rust
@@ -9,23 +10,21 @@
 mod discover;
 #[cfg(locket_embed_op_bridge)]
 mod embedded;
-mod protocol;
-mod transport;
 
-pub(super) use protocol::ResolveResult;
+pub(super) use crate::provider::plugin::ResolveResult;
 
 use crate::path::AbsolutePath;
 use crate::provider::ProviderError;
+use crate::provider::plugin::Plugin;
 use secrecy::SecretString;
 use std::collections::HashMap;
 
+/// Name of the bridge in logs and errors.
+const BRIDGE_NAME: &str = "op bridge";
+
 /// A running, authenticated bridge process.
 pub(super) struct Bridge {
-    // Declared before the child so the transport (and its reader task)
-    // shuts down first; kill_on_drop then reaps the process, which also
-    // exits on its own once its stdin pipe closes.
-    transport: transport::BridgeTransport,
-    _child: Option<tokio::process::Child>,
+    plugin: Plugin,
 }
 
 impl Bridge {
@@ -36,13 +35,8 @@ impl Bridge {
         token: &SecretString,
     ) -> Result<Self, ProviderError> {
         let exec = discover::BridgeExec::discover(explicit)?;
-        let (child, transport) = transport::BridgeTransport::spawn(exec.command())?;
-        let info = transport.init(token).await?;
-        tracing::debug!(bridge_version = %info.bridge_version, "locket-op-bridge ready");
-        Ok(Self {
-            transport,
-            _child: Some(child),
-        })
+        let plugin = Plugin::spawn(BRIDGE_NAME, exec.command(), &[], Some(token)).await?;
+        Ok(Self { plugin })
     }
 
     /// Resolve a batch of raw `op://` references in one authenticated
@@ -51,7 +45,7 @@ impl Bridge {
         &self,
         refs: &[&str],
     ) -> Result<HashMap<String, ResolveResult>, ProviderError> {
-        self.transport.resolve(refs).await
+        self.plugin.resolve(refs).await
     }
 
     /// A bridge speaking over arbitrary pipes instead of a child process
@@ -61,8 +55,7 @@ impl Bridge {
         writer: impl tokio::io::AsyncWrite + Send + Unpin + 'static,
     ) -> Self {
         Self {
-            transport: transport::BridgeTransport::new(reader, writer),
-            _child: None,
+            plugin: Plugin::from_pipes(BRIDGE_NAME, reader, writer),
         }
     }
 }
@@ -0,0 +1,67 @@
+//! Provider plugins: external executables that resolve references over a
+//! versioned JSON protocol on their stdin and stdout.
+//!
+//! This is the protocol `locket-op-bridge` speaks, and any other
+//! executable can implement it to be used through the exec provider.
+
+mod protocol;
+mod transport;
+
+pub(super) use protocol::ResolveResult;
+
+use crate::provider::ProviderError;
+use secrecy::SecretString;
+use std::collections::HashMap;
+use tokio::process::Command;
+
+/// A running, initialized plugin process.
+pub(super) struct Plugin {
+    // Declared before the child so the transport (and its reader task)
+    // shuts down first; kill_on_drop then reaps the process, which also
+    // exits on its own once its stdin pipe closes.
+    transport: transport::PluginTransport,
+    _child: Option<tokio::process::Child>,
+}
+
+impl Plugin {
+    /// Spawn the plugin and complete the init handshake, handing it the
+    /// token (if any) over its private stdin pipe.
+    ///
+    /// `name` identifies the plugin in logs and errors.
+    pub(super) async fn spawn(
+        name: &str,
+        command: Command,
+        pass_env: &[String],
+        token: Option<&SecretString>,
+    ) -> Result<Self, ProviderError> {
+        let (child, transport) = transport::PluginTransport::spawn(name.into(), command, pass_env)?;
+        let info = transport.init(token).await?;
+        tracing::debug!(version = %info.bridge_version, "{name} ready");
+        Ok(Self {
+            transport,
+            _child: Some(child),
+        })
+    }
+
+    /// Resolve a batch of raw references in one round trip. Results are
+    /// keyed by the exact request reference.
+    pub(super) async fn resolve(
+        &self,
+        refs: &[&str],
+    ) -> Result<HashMap<String, ResolveResult>, ProviderError> {
+        self.transport.resolve(refs).await
+    }
+
+    /// A plugin speaking over arbitrary pipes instead of a child process
+    #[cfg(test)]
+    pub(super) fn from_pipes(
+        name: &str,
+        reader: impl tokio::io::AsyncRead + Send + Unpin + 'static,
+        writer: impl tokio::io::AsyncWrite + Send + Unpin + 'static,
+    ) -> Self {
+        Self {
+            transport: transport::PluginTransport::new(name.into(), reader, writer),
+            _child: None,
+        }
+    }
+}
@@ -1,4 +1,8 @@
-//! Wire types for protocol between locket and op bridge.
+//! Wire types for the protocol between locket and a plugin process.
+//!
+//! Every message is a single line of JSON. locket sends an `init` request
+//! once after spawning the plugin, then any number of `resolve` requests,
+//! each answered by a response carrying the same `id`.
 
 use crate::provider::ProviderError;
 use secrecy::SecretString;
@@ -13,7 +17,8 @@ pub(super) enum Request<'a> {
     Init {
         id: u64,
         protocol: u32,
-        token: &'a str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        token: Option<&'a str>,
     },
     Resolve {
         id: u64,
@@ -81,7 +86,7 @@ impl ErrorCode {
     pub fn into_provider_error(self, message: String) -> ProviderError {
         match self {
             ErrorCode::NotFound => ProviderError::NotFound(message),
-            ErrorCode::RateLimited => ProviderError::RateLimit,
+            ErrorCode::RateLimited => ProviderError::RateLimit { retry_after: None },
             ErrorCode::UnsupportedProtocol => ProviderError::InvalidConfig(message),
             ErrorCode::InvalidReference
             | ErrorCode::BadRequest
@@ -101,13 +106,21 @@ mod tests {
         let json = serde_json::to_string(&Request::Init {
             id: 1,
             protocol: 1,
-            token: "ops_abc",
+            token: Some("ops_abc"),
         })
         .unwrap();
         assert_eq!(
             json,
             r#"{"type":"init","id":1,"protocol":1,"token":"ops_abc"}"#
         );
+
+        let json = serde_json::to_string(&Request::Init {
+            id: 1,
+            protocol: 1,
+            token: None,
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"type":"init","id":1,"protocol":1}"#);
     }
 
     #[test]
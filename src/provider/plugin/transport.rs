@@ -1,10 +1,10 @@
-//! Pipe transport to a spawned `locket-op-bridge` child.
+//! Pipe transport to a spawned plugin child.
 //!
-//! Requests are written to the bridge's stdin behind a mutex. a reader
+//! Requests are written to the plugin's stdin behind a mutex. a reader
 //! task demuxes stdout responses back to callers by request id, so
 //! overlapping `fetch_map` calls can share the one pipe. The child is
-//! reaped by `kill_on_drop`, and the bridge itself exits on stdin EOF,
-//! so its lifetime can never exceed locket's.
+//! reaped by `kill_on_drop`, and the plugin itself is expected to exit
+//! on stdin EOF, so its lifetime can never exceed locket's.
 
 use super::protocol::{PROTOCOL_VERSION, Request, ResolveResult, Response};
 use crate::provider::ProviderError;
@@ -22,7 +22,7 @@ const INIT_TIMEOUT: Duration = Duration::from_secs(30);
 const RESOLVE_TIMEOUT: Duration = Duration::from_secs(120);
 
 #[derive(Debug)]
-pub(super) struct BridgeInfo {
+pub(super) struct PluginInfo {
     pub bridge_version: String,
 }
 
@@ -31,21 +31,27 @@ pub(super) struct BridgeInfo {
 /// The table is `None` once the bridge connection has failed; from
 /// then on every registration fails fast instead of queueing on a
 /// dead pipe.
-struct Pending(StdMutex<Option<HashMap<u64, oneshot::Sender<Response>>>>);
+struct Pending {
+    name: Arc<str>,
+    waiters: StdMutex<Option<HashMap<u64, oneshot::Sender<Response>>>>,
+}
 
 type Waiters<'a> = MutexGuard<'a, Option<HashMap<u64, oneshot::Sender<Response>>>>;
 
 impl Pending {
-    fn new() -> Arc<Self> {
-        Arc::new(Self(StdMutex::new(Some(HashMap::new()))))
+    fn new(name: Arc<str>) -> Arc<Self> {
+        Arc::new(Self {
+            name,
+            waiters: StdMutex::new(Some(HashMap::new())),
+        })
     }
 
-    fn closed_error() -> ProviderError {
-        ProviderError::Other("op bridge connection closed".into())
+    fn closed_error(&self) -> ProviderError {
+        ProviderError::Other(format!("{} connection closed", self.name))
     }
 
     fn waiters(&self) -> Waiters<'_> {
-        self.0.lock().expect("lock poisoned")
+        self.waiters.lock().expect("lock poisoned")
     }
 
     fn register(&self, id: u64) -> Result<oneshot::Receiver<Response>, ProviderError> {
@@ -55,7 +61,7 @@ impl Pending {
                 waiters.insert(id, tx);
                 Ok(rx)
             }
-            None => Err(Self::closed_error()),
+            None => Err(self.closed_error()),
         }
     }
 
@@ -90,7 +96,7 @@ impl Pending {
             let _ = tx.send(Response::Error {
                 id,
                 code: super::protocol::ErrorCode::Internal,
-                message: "op bridge connection closed".into(),
+                message: format!("{} connection closed", self.name),
             });
         }
     }
@@ -101,21 +107,24 @@ impl Pending {
     }
 }
 
-pub(super) struct BridgeTransport {
+pub(super) struct PluginTransport {
+    name: Arc<str>,
     writer: Mutex<Box<dyn AsyncWrite + Send + Unpin>>,
     pending: Arc<Pending>,
     next_id: AtomicU64,
     reader: tokio::task::JoinHandle<()>,
 }
 
-impl BridgeTransport {
+impl PluginTransport {
     pub(super) fn new(
+        name: Arc<str>,
         reader: impl AsyncRead + Send + Unpin + 'static,
         writer: impl AsyncWrite + Send + Unpin + 'static,
     ) -> Self {
-        let pending = Pending::new();
+        let pending = Pending::new(Arc::clone(&name));
         let reader = tokio::spawn(Self::demux(reader, Arc::clone(&pending)));
         Self {
+            name,
             writer: Mutex::new(Box::new(writer)),
             pending,
             next_id: AtomicU64::new(1),
@@ -123,11 +132,21 @@ impl BridgeTransport {
         }
     }
 
-    /// Spawn the bridge from a prepared command and connect a transport
+    /// Spawn the plugin from a prepared command and connect a transport
     /// to its pipes.
-    pub(super) fn spawn(mut command: Command) -> Result<(Child, Self), ProviderError> {
+    ///
+    /// The plugin starts from an empty environment. Only `HOME`, `PATH`,
+    /// `TMPDIR` and the variables named in `pass_env` are passed through.
+    pub(super) fn spawn(
+        name: Arc<str>,
+        mut command: Command,
+        pass_env: &[String],
+    ) -> Result<(Child, Self), ProviderError> {
         command.env_clear();
-        for var in ["HOME", "PATH", "TMPDIR"] {
+        for var in ["HOME", "PATH", "TMPDIR"]
+            .into_iter()
+            .chain(pass_env.iter().map(String::as_str))
+        {
             if let Ok(value) = std::env::var(var) {
                 command.env(var, value);
             }
@@ -139,19 +158,22 @@ impl BridgeTransport {
             .kill_on_drop(true);
 
         let mut child = command.spawn().map_err(ProviderError::Io)?;
-        let stdin = child.stdin.take().expect("bridge stdin was piped above");
-        let stdout = child.stdout.take().expect("bridge stdout was piped above");
-        let stderr = child.stderr.take().expect("bridge stderr was piped above");
-        tokio::spawn(Self::forward_stderr(stderr));
-        Ok((child, Self::new(stdout, stdin)))
+        let stdin = child.stdin.take().expect("plugin stdin was piped above");
+        let stdout = child.stdout.take().expect("plugin stdout was piped above");
+        let stderr = child.stderr.take().expect("plugin stderr was piped above");
+        tokio::spawn(Self::forward_stderr(Arc::clone(&name), stderr));
+        Ok((child, Self::new(name, stdout, stdin)))
     }
 
-    pub(super) async fn init(&self, token: &SecretString) -> Result<BridgeInfo, ProviderError> {
+    pub(super) async fn init(
+        &self,
+        token: Option<&SecretString>,
+    ) -> Result<PluginInfo, ProviderError> {
         let id = self.next_id();
         let request = Request::Init {
             id,
             protocol: PROTOCOL_VERSION,
-            token: token.expose_secret(),
+            token: token.map(|t| t.expose_secret()),
         };
         match self.request(id, &request, INIT_TIMEOUT).await? {
             Response::InitOk {
@@ -161,10 +183,11 @@ impl BridgeTransport {
             } => {
                 if protocol != PROTOCOL_VERSION {
                     return Err(ProviderError::InvalidConfig(format!(
-                        "op bridge speaks protocol {protocol}, locket requires {PROTOCOL_VERSION}"
+                        "{} speaks protocol {protocol}, locket requires {PROTOCOL_VERSION}",
+                        self.name
                     )));
                 }
-                Ok(BridgeInfo { bridge_version })
+                Ok(PluginInfo { bridge_version })
             }
             Response::Error { code, message, .. } => Err(match code {
                 super::protocol::ErrorCode::UnsupportedProtocol => {
@@ -173,9 +196,10 @@ impl BridgeTransport {
                 super::protocol::ErrorCode::Internal => ProviderError::Other(message),
                 _ => ProviderError::Unauthorized(message),
             }),
-            Response::ResolveOk { .. } => Err(ProviderError::Other(
-                "op bridge sent an unexpected response to init".into(),
-            )),
+            Response::ResolveOk { .. } => Err(ProviderError::Other(format!(
+                "{} sent an unexpected response to init",
+                self.name
+            ))),
         }
     }
 
@@ -188,9 +212,10 @@ impl BridgeTransport {
         match self.request(id, &request, RESOLVE_TIMEOUT).await? {
             Response::ResolveOk { results, .. } => Ok(results),
             Response::Error { code, message, .. } => Err(code.into_provider_error(message)),
-            Response::InitOk { .. } => Err(ProviderError::Other(
-                "op bridge sent an unexpected response to resolve".into(),
-            )),
+            Response::InitOk { .. } => Err(ProviderError::Other(format!(
+                "{} sent an unexpected response to resolve",
+                self.name
+            ))),
         }
     }
 
@@ -205,7 +230,7 @@ impl BridgeTransport {
         timeout: Duration,
     ) -> Result<Response, ProviderError> {
         let mut line = serde_json::to_vec(request)
-            .map_err(|e| ProviderError::Other(format!("failed to encode bridge request: {e}")))?;
+            .map_err(|e| ProviderError::Other(format!("failed to encode plugin request: {e}")))?;
         line.push(b'\n');
 
         let rx = self.pending.register(id)?;
@@ -224,11 +249,12 @@ impl BridgeTransport {
 
         match tokio::time::timeout(timeout, rx).await {
             Ok(Ok(response)) => Ok(response),
-            Ok(Err(_)) => Err(Pending::closed_error()),
+            Ok(Err(_)) => Err(self.pending.closed_error()),
             Err(_) => {
                 self.pending.forget(id);
                 Err(ProviderError::Other(format!(
-                    "op bridge did not respond within {timeout:?}"
+                    "{} did not respond within {timeout:?}",
+                    self.name
                 )))
             }
         }
@@ -246,24 +272,30 @@ impl BridgeTransport {
                         let id = response.id();
                         if !pending.dispatch(response) {
                             tracing::warn!(
-                                target: "locket::op_bridge",
+                                target: "locket::plugin",
                                 id,
-                                "dropping bridge response with no matching request"
+                                "dropping {} response with no matching request",
+                                pending.name
                             );
                         }
                     }
                     // Only the serde error is logged: the line may hold secrets.
                     Err(e) => {
                         tracing::error!(
-                            target: "locket::op_bridge",
-                            "bridge sent a malformed response, closing connection: {e}"
+                            target: "locket::plugin",
+                            "{} sent a malformed response, closing connection: {e}",
+                            pending.name
                         );
                         break;
                     }
                 },
                 Ok(None) => break,
                 Err(e) => {
-                    tracing::error!(target: "locket::op_bridge", "bridge pipe read failed: {e}");
+                    tracing::error!(
+                        target: "locket::plugin",
+                        "{} pipe read failed: {e}",
+                        pending.name
+                    );
                     break;
                 }
             }
@@ -271,15 +303,15 @@ impl BridgeTransport {
         pending.close();
     }
 
-    async fn forward_stderr(stderr: ChildStderr) {
+    async fn forward_stderr(name: Arc<str>, stderr: ChildStderr) {
         let mut lines = BufReader::new(stderr).lines();
         while let Ok(Some(line)) = lines.next_line().await {
-            tracing::warn!(target: "locket::op_bridge", "{line}");
+            tracing::warn!(target: "locket::plugin", "{name}: {line}");
         }
     }
 }
 
-impl Drop for BridgeTransport {
+impl Drop for PluginTransport {
     fn drop(&mut self) {
         self.reader.abort();
     }
@@ -291,17 +323,21 @@ mod tests {
     use secrecy::ExposeSecret;
     use tokio::io::{ReadHalf, WriteHalf};
 
-    type BridgeEnd = tokio::io::DuplexStream;
+    type PluginEnd = tokio::io::DuplexStream;
 
-    fn pair() -> (BridgeTransport, ReadHalf<BridgeEnd>, WriteHalf<BridgeEnd>) {
+    fn pair() -> (PluginTransport, ReadHalf<PluginEnd>, WriteHalf<PluginEnd>) {
         let (locket_end, bridge_end) = tokio::io::duplex(64 * 1024);
         let (l_read, l_write) = tokio::io::split(locket_end);
         let (b_read, b_write) = tokio::io::split(bridge_end);
-        (BridgeTransport::new(l_read, l_write), b_read, b_write)
+        (
+            PluginTransport::new("test plugin".into(), l_read, l_write),
+            b_read,
+            b_write,
+        )
     }
 
     async fn read_request(
-        lines: &mut tokio::io::Lines<BufReader<ReadHalf<BridgeEnd>>>,
+        lines: &mut tokio::io::Lines<BufReader<ReadHalf<PluginEnd>>>,
     ) -> serde_json::Value {
         let line = lines
             .next_line()
@@ -311,7 +347,7 @@ mod tests {
         serde_json::from_str(&line).expect("request is valid JSON")
     }
 
-    async fn respond(writer: &mut WriteHalf<BridgeEnd>, response: &str) {
+    async fn respond(writer: &mut WriteHalf<PluginEnd>, response: &str) {
         writer
             .write_all(format!("{response}\n").as_bytes())
             .await
@@ -334,7 +370,7 @@ mod tests {
         });
 
         let info = transport
-            .init(&SecretString::from("ops_test"))
+            .init(Some(&SecretString::from("ops_test")))
             .await
             .unwrap();
         assert_eq!(info.bridge_version, "9.9.9");
@@ -360,7 +396,7 @@ mod tests {
         });
 
         let err = transport
-            .init(&SecretString::from("ops_bad"))
+            .init(Some(&SecretString::from("ops_bad")))
             .await
             .unwrap_err();
         assert!(matches!(err, ProviderError::Unauthorized(m) if m == "bad token"));
@@ -383,7 +419,7 @@ mod tests {
         });
 
         let err = transport
-            .init(&SecretString::from("ops_test"))
+            .init(Some(&SecretString::from("ops_test")))
             .await
             .unwrap_err();
         assert!(matches!(err, ProviderError::InvalidConfig(_)));
@@ -2,25 +2,76 @@
 use std::str::FromStr;
 use thiserror::Error;
 
+#[cfg(feature = "aws")]
+mod aws;
+#[cfg(feature = "azure")]
+mod azure;
 #[cfg(feature = "bao")]
 mod bao;
+#[cfg(feature = "bw")]
+mod bw;
 #[cfg(feature = "bws")]
 mod bws;
+#[cfg(feature = "doppler")]
+mod doppler;
+#[cfg(feature = "exec-provider")]
+mod exec;
+#[cfg(feature = "gcp")]
+mod gcp;
+#[cfg(feature = "http")]
+mod http;
 #[cfg(feature = "infisical")]
 mod infisical;
+#[cfg(feature = "k8s")]
+mod k8s;
+#[cfg(feature = "keepass")]
+mod keepass;
+mod local;
 #[cfg(any(feature = "op", feature = "connect"))]
 mod op;
+#[cfg(feature = "sops")]
+mod sops;
+#[cfg(feature = "aws")]
+pub use aws::{
+    AwsSmLocation, AwsSmParseError, AwsSmReference, SsmParseError, SsmReference,
+    parse_aws_reference,
+};
+#[cfg(feature = "azure")]
+pub use azure::{AkvParseError, AkvReference};
 #[cfg(feature = "bao")]
-pub use bao::{BaoMount, BaoParseError, BaoReference, BaoSecretLocation};
+pub use bao::{
+    BaoDynamicReference, BaoField, BaoMount, BaoParseError, BaoPath, BaoPkiReference,
+    BaoPkiRequest, BaoReference, BaoSecretLocation, parse_bao_reference,
+};
+#[cfg(feature = "bw")]
+pub use bw::{BwField, BwItem, BwParseError, BwReference};
 #[cfg(feature = "bws")]
 pub use bws::BwsReference;
+#[cfg(feature = "doppler")]
+pub use doppler::{DopplerParseError, DopplerReference};
+#[cfg(feature = "exec-provider")]
+pub use exec::{ExecReference, ExecScheme};
+#[cfg(feature = "gcp")]
+pub use gcp::{GcpSmParseError, GcpSmReference};
+#[cfg(feature = "http")]
+pub use http::{HttpSecretParseError, HttpSecretReference};
 #[cfg(feature = "infisical")]
 pub use infisical::{
     InfisicalParseError, InfisicalPath, InfisicalProjectId, InfisicalReference,
     InfisicalSecretType, InfisicalSlug,
 };
+#[cfg(feature = "k8s")]
+pub use k8s::{K8sParseError, K8sReference};
+#[cfg(feature = "keepass")]
+pub use keepass::{KeePassField, KeePassParseError, KeePassReference};
+pub use local::{
+    CredsParseError, CredsReference, EnvParseError, EnvReference, FileParseError, FileReference,
+    parse_local_reference,
+};
 #[cfg(any(feature = "op", feature = "connect"))]
 pub use op::{OpParseError, OpReference};
+#[cfg(feature = "sops")]
+pub use sops::{SopsParseError, SopsReference};
 
 /// Errors that can occur when parsing a specific Secret Reference string.
 #[derive(Debug, Error)]
@@ -28,6 +79,15 @@ pub enum ReferenceParseError {
     #[error("unknown or invalid secret format: {0}")]
     UnknownFormat(String),
 
+    #[error(transparent)]
+    File(#[from] FileParseError),
+
+    #[error(transparent)]
+    Creds(#[from] CredsParseError),
+
+    #[error(transparent)]
+    Env(#[from] EnvParseError),
+
     #[cfg(any(feature = "op", feature = "connect"))]
     #[error(transparent)]
     Op(#[from] OpParseError),
@@ -43,6 +103,46 @@ pub enum ReferenceParseError {
     #[cfg(feature = "bao")]
     #[error(transparent)]
     Bao(#[from] BaoParseError),
+
+    #[cfg(feature = "aws")]
+    #[error(transparent)]
+    AwsSecretsManager(#[from] AwsSmParseError),
+
+    #[cfg(feature = "aws")]
+    #[error(transparent)]
+    AwsParameterStore(#[from] SsmParseError),
+
+    #[cfg(feature = "gcp")]
+    #[error(transparent)]
+    GcpSecretManager(#[from] GcpSmParseError),
+
+    #[cfg(feature = "azure")]
+    #[error(transparent)]
+    AzureKeyVault(#[from] AkvParseError),
+
+    #[cfg(feature = "sops")]
+    #[error(transparent)]
+    Sops(#[from] SopsParseError),
+
+    #[cfg(feature = "http")]
+    #[error(transparent)]
+    HttpSecret(#[from] HttpSecretParseError),
+
+    #[cfg(feature = "keepass")]
+    #[error(transparent)]
+    KeePass(#[from] KeePassParseError),
+
+    #[cfg(feature = "k8s")]
+    #[error(transparent)]
+    K8s(#[from] K8sParseError),
+
+    #[cfg(feature = "bw")]
+    #[error(transparent)]
+    Bw(#[from] BwParseError),
+
+    #[cfg(feature = "doppler")]
+    #[error(transparent)]
+    Doppler(#[from] DopplerParseError),
 }
 
 /// A parsed reference to a secret.
@@ -51,6 +151,15 @@ pub enum ReferenceParseError {
 /// syntax matches the requirements of the specific provider.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SecretReference {
+    /// A local file reference
+    File(FileReference),
+
+    /// A credentials directory reference
+    Creds(CredsReference),
+
+    /// A process environment variable reference
+    Env(EnvReference),
+
     #[cfg(any(feature = "op", feature = "connect"))]
     /// A 1Password reference
     OnePassword(OpReference),
@@ -67,6 +176,58 @@ pub enum SecretReference {
     /// An OpenBao / Vault reference
     Bao(BaoReference),
 
+    #[cfg(feature = "bao")]
+    /// An OpenBao / Vault dynamic secret reference
+    BaoDynamic(BaoDynamicReference),
+
+    #[cfg(feature = "bao")]
+    /// An OpenBao / Vault PKI certificate reference
+    BaoPki(BaoPkiReference),
+
+    #[cfg(feature = "aws")]
+    /// An AWS Secrets Manager reference
+    AwsSecretsManager(AwsSmReference),
+
+    #[cfg(feature = "aws")]
+    /// An AWS SSM Parameter Store reference
+    AwsParameterStore(SsmReference),
+
+    #[cfg(feature = "gcp")]
+    /// A Google Cloud Secret Manager reference
+    GcpSecretManager(GcpSmReference),
+
+    #[cfg(feature = "azure")]
+    /// An Azure Key Vault reference
+    AzureKeyVault(AkvReference),
+
+    #[cfg(feature = "sops")]
+    /// A SOPS encrypted file reference
+    Sops(SopsReference),
+
+    #[cfg(feature = "exec-provider")]
+    /// A reference handled by an external exec provider plugin
+    Exec(ExecReference),
+
+    #[cfg(feature = "http")]
+    /// An HTTP webhook reference
+    HttpSecret(HttpSecretReference),
+
+    #[cfg(feature = "keepass")]
+    /// A KeePass database reference
+    KeePass(KeePassReference),
+
+    #[cfg(feature = "k8s")]
+    /// A Kubernetes Secret reference
+    K8s(K8sReference),
+
+    #[cfg(feature = "bw")]
+    /// A Bitwarden vault reference
+    Bw(BwReference),
+
+    #[cfg(feature = "doppler")]
+    /// A Doppler reference
+    Doppler(DopplerReference),
+
     #[cfg(any(test, doctest, feature = "testing"))]
     /// A mock reference for testing purposes
     Mock(String),
@@ -110,9 +271,33 @@ where
     }
 }
 
+impl SecretReference {
+    /// Whether the value is only valid for a limited time, such as dynamic
+    /// secrets and certificates. Their leases are revoked when locket shuts
+    /// down.
+    pub fn is_leased(&self) -> bool {
+        #[cfg(feature = "bao")]
+        if matches!(self, Self::BaoDynamic(_) | Self::BaoPki(_)) {
+            return true;
+        }
+        false
+    }
+
+    /// Whether the value may be kept beyond the render it was fetched for,
+    /// e.g. in the fallback cache. Leased values may not.
+    pub fn is_cacheable(&self) -> bool {
+        !self.is_leased()
+    }
+}
+
 impl std::fmt::Display for SecretReference {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Self::File(reference) => write!(f, "{}", reference),
+
+            Self::Creds(reference) => write!(f, "{}", reference),
+            Self::Env(reference) => write!(f, "{}", reference),
+
             #[cfg(any(feature = "op", feature = "connect"))]
             Self::OnePassword(reference) => write!(f, "{}", reference),
 
@@ -125,6 +310,45 @@ impl std::fmt::Display for SecretReference {
             #[cfg(feature = "bao")]
             Self::Bao(reference) => write!(f, "{}", reference),
 
+            #[cfg(feature = "bao")]
+            Self::BaoDynamic(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "bao")]
+            Self::BaoPki(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "aws")]
+            Self::AwsSecretsManager(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "aws")]
+            Self::AwsParameterStore(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "gcp")]
+            Self::GcpSecretManager(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "azure")]
+            Self::AzureKeyVault(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "sops")]
+            Self::Sops(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "exec-provider")]
+            Self::Exec(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "http")]
+            Self::HttpSecret(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "keepass")]
+            Self::KeePass(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "k8s")]
+            Self::K8s(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "bw")]
+            Self::Bw(reference) => write!(f, "{}", reference),
+
+            #[cfg(feature = "doppler")]
+            Self::Doppler(reference) => write!(f, "{}", reference),
+
             #[cfg(any(test, doctest, feature = "testing"))]
             Self::Mock(reference) => write!(f, "{}", reference),
         }
@@ -135,6 +359,24 @@ impl FromStr for SecretReference {
     type Err = ReferenceParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Check local file
+        if s.starts_with("file://") {
+            let file_ref = FileReference::from_str(s)?;
+            return Ok(Self::File(file_ref));
+        }
+
+        // Check credentials directory
+        if s.starts_with("creds://") {
+            let creds_ref = CredsReference::from_str(s)?;
+            return Ok(Self::Creds(creds_ref));
+        }
+
+        // Check environment variable
+        if s.starts_with("env://") {
+            let env_ref = EnvReference::from_str(s)?;
+            return Ok(Self::Env(env_ref));
+        }
+
         // Check 1Password
         #[cfg(any(feature = "op", feature = "connect"))]
         if s.starts_with("op://") {
@@ -162,6 +404,93 @@ impl FromStr for SecretReference {
             return Ok(Self::Bao(bao_ref));
         }
 
+        // Check OpenBao / Vault dynamic secrets
+        #[cfg(feature = "bao")]
+        if s.starts_with("bao+dyn://") {
+            let bao_ref = BaoDynamicReference::from_str(s)?;
+            return Ok(Self::BaoDynamic(bao_ref));
+        }
+
+        // Check OpenBao / Vault PKI certificates
+        #[cfg(feature = "bao")]
+        if s.starts_with("bao+pki://") {
+            let bao_ref = BaoPkiReference::from_str(s)?;
+            return Ok(Self::BaoPki(bao_ref));
+        }
+
+        // Check AWS Secrets Manager
+        #[cfg(feature = "aws")]
+        if s.starts_with("aws-sm://") {
+            let aws_ref = AwsSmReference::from_str(s)?;
+            return Ok(Self::AwsSecretsManager(aws_ref));
+        }
+
+        // Check AWS SSM Parameter Store
+        #[cfg(feature = "aws")]
+        if s.starts_with("ssm://") {
+            let ssm_ref = SsmReference::from_str(s)?;
+            return Ok(Self::AwsParameterStore(ssm_ref));
+        }
+
+        // Check Google Cloud Secret Manager
+        #[cfg(feature = "gcp")]
+        if s.starts_with("gcpsm://") {
+            let gcp_ref = GcpSmReference::from_str(s)?;
+            return Ok(Self::GcpSecretManager(gcp_ref));
+        }
+
+        // Check Azure Key Vault
+        #[cfg(feature = "azure")]
+        if s.starts_with("akv://") {
+            let akv_ref = AkvReference::from_str(s)?;
+            return Ok(Self::AzureKeyVault(akv_ref));
+        }
+
+        // Check SOPS
+        #[cfg(feature = "sops")]
+        if s.starts_with("sops://") {
+            let sops_ref = SopsReference::from_str(s)?;
+            return Ok(Self::Sops(sops_ref));
+        }
+
+        // Check HTTP webhook
+        #[cfg(feature = "http")]
+        if s.starts_with("http-secret://") {
+            let http_ref = HttpSecretReference::from_str(s)?;
+            return Ok(Self::HttpSecret(http_ref));
+        }
+
+        // Check KeePass
+        #[cfg(feature = "keepass")]
+        if s.starts_with("kp://") {
+            let kp_ref = KeePassReference::from_str(s)?;
+            return Ok(Self::KeePass(kp_ref));
+        }
+
+        // Check Kubernetes
+        #[cfg(feature = "k8s")]
+        if s.starts_with("k8s://") {
+            let k8s_ref = K8sReference::from_str(s)?;
+            return Ok(Self::K8s(k8s_ref));
+        }
+
+        // Check Bitwarden vault
+        #[cfg(feature = "bw")]
+        if s.starts_with("bw://") {
+            let bw_ref = BwReference::from_str(s)?;
+            return Ok(Self::Bw(bw_ref));
+        }
+
+        // Check Doppler
+        #[cfg(feature = "doppler")]
+        if s.starts_with("doppler://") {
+            let doppler_ref = DopplerReference::from_str(s)?;
+            return Ok(Self::Doppler(doppler_ref));
+        }
+
+        // Exec provider references are not checked here, because their
+        // scheme is only known from the runtime configuration.
+
         // Fallback
         Err(ReferenceParseError::UnknownFormat(s.to_string()))
     }
@@ -0,0 +1,430 @@
+//! Defines the AWS secret reference types and their parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Escapes only what would be ambiguous inside a reference, so secret
+/// names and ARNs (which may contain `/`, `:`, `+`, `=`, `@`) stay readable.
+const COMPONENT: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'?');
+
+/// Query values additionally escape the pair separators.
+const QUERY_VALUE: &AsciiSet = &COMPONENT.add(b'&').add(b'=').add(b'+');
+
+#[derive(Debug, Error)]
+pub enum AwsSmParseError {
+    #[error("reference must start with 'aws-sm://'")]
+    InvalidScheme,
+
+    #[error("missing secret id")]
+    MissingSecretId,
+
+    #[error("json key cannot be empty")]
+    EmptyKey,
+
+    #[error("'{0}' may only be specified once")]
+    Duplicate(&'static str),
+
+    #[error("unknown query parameter '{0}': expected 'version-stage' or 'version-id'")]
+    UnknownParameter(String),
+
+    #[error("query parameter '{0}' cannot be empty")]
+    EmptyParameter(String),
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+/// A specific version of a Secrets Manager secret.
+///
+/// Every referenced JSON key of that version lives in the same secret
+/// string, so references are grouped and fetched by location.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AwsSmLocation {
+    /// Secret name or full ARN.
+    pub secret_id: String,
+    /// Staging label (e.g. `AWSCURRENT`, `AWSPREVIOUS`).
+    pub version_stage: Option<String>,
+    /// Explicit version UUID.
+    pub version_id: Option<String>,
+}
+
+impl fmt::Display for AwsSmLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.secret_id)?;
+        if let Some(stage) = &self.version_stage {
+            write!(f, " (stage {})", stage)?;
+        }
+        if let Some(id) = &self.version_id {
+            write!(f, " (version {})", id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a syntactically valid AWS Secrets Manager reference.
+/// Syntax: `aws-sm://<secret-id>[#json-key][?version-stage=<stage>&version-id=<id>]`
+///
+/// * `secret-id` is the secret name (e.g. `prod/db`) or its full ARN
+/// * `json-key` selects a single key when the secret string is a JSON object.
+///   Without it, the whole secret string is used as the value.
+/// * `version-stage` / `version-id` select a version other than `AWSCURRENT`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AwsSmReference {
+    pub location: AwsSmLocation,
+    pub key: Option<String>,
+}
+
+impl FromStr for AwsSmReference {
+    type Err = AwsSmParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s
+            .strip_prefix("aws-sm://")
+            .ok_or(AwsSmParseError::InvalidScheme)?;
+
+        // The json key and the query may appear in either order, each
+        // running until the start of the other.
+        let id_end = rest.find(['#', '?']).unwrap_or(rest.len());
+        let (raw_id, mut tail) = rest.split_at(id_end);
+        let mut raw_key = None;
+        let mut raw_query = None;
+        while let Some(marker) = tail.chars().next() {
+            let body = &tail[1..];
+            let (terminator, slot, name) = match marker {
+                '#' => ('?', &mut raw_key, "json key"),
+                _ => ('#', &mut raw_query, "query"),
+            };
+            let end = body.find(terminator).unwrap_or(body.len());
+            if slot.replace(&body[..end]).is_some() {
+                return Err(AwsSmParseError::Duplicate(name));
+            }
+            tail = &body[end..];
+        }
+
+        let secret_id = percent_decode_str(raw_id).decode_utf8()?.into_owned();
+        if secret_id.is_empty() {
+            return Err(AwsSmParseError::MissingSecretId);
+        }
+
+        let key = match raw_key {
+            Some(k) => {
+                let k = percent_decode_str(k).decode_utf8()?.into_owned();
+                if k.is_empty() {
+                    return Err(AwsSmParseError::EmptyKey);
+                }
+                Some(k)
+            }
+            None => None,
+        };
+
+        let mut location = AwsSmLocation {
+            secret_id,
+            version_stage: None,
+            version_id: None,
+        };
+        for (name, value) in url::form_urlencoded::parse(raw_query.unwrap_or("").as_bytes()) {
+            let (slot, param) = match name.as_ref() {
+                "version-stage" => (&mut location.version_stage, "version-stage"),
+                "version-id" => (&mut location.version_id, "version-id"),
+                _ => return Err(AwsSmParseError::UnknownParameter(name.into_owned())),
+            };
+            if value.is_empty() {
+                return Err(AwsSmParseError::EmptyParameter(param.to_string()));
+            }
+            if slot.replace(value.into_owned()).is_some() {
+                return Err(AwsSmParseError::Duplicate(param));
+            }
+        }
+
+        Ok(Self { location, key })
+    }
+}
+
+impl From<AwsSmReference> for SecretReference {
+    fn from(r: AwsSmReference) -> Self {
+        Self::AwsSecretsManager(r)
+    }
+}
+
+impl ReferenceSyntax for AwsSmReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, AwsSmParseError::InvalidScheme) {
+                    tracing::warn!("Invalid AWS Secrets Manager reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for AwsSmReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::AwsSecretsManager(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AwsSmReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "aws-sm://{}",
+            utf8_percent_encode(&self.location.secret_id, COMPONENT)
+        )?;
+        if let Some(key) = &self.key {
+            write!(f, "#{}", utf8_percent_encode(key, COMPONENT))?;
+        }
+        let params = [
+            ("version-stage", &self.location.version_stage),
+            ("version-id", &self.location.version_id),
+        ];
+        let mut sep = '?';
+        for (name, value) in params {
+            if let Some(value) = value {
+                write!(
+                    f,
+                    "{}{}={}",
+                    sep,
+                    name,
+                    utf8_percent_encode(value, QUERY_VALUE)
+                )?;
+                sep = '&';
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SsmParseError {
+    #[error("reference must start with 'ssm://'")]
+    InvalidScheme,
+
+    #[error("missing parameter name")]
+    MissingName,
+
+    #[error("invalid parameter name '{0}': cannot contain whitespace, '#' or '?'")]
+    InvalidName(String),
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+/// Represents a syntactically valid AWS SSM Parameter Store reference.
+/// Syntax: `ssm://<parameter-name>[:<version-or-label>]`
+///
+/// * `parameter-name` is the full parameter name. Hierarchical names keep
+///   their leading slash, e.g. `ssm:///app/prod/db-password`
+/// * An optional `:<version>` or `:<label>` selector pins a specific version
+///
+/// SecureString parameters are always decrypted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SsmReference {
+    name: String,
+}
+
+impl SsmReference {
+    /// The parameter name, including any version or label selector, exactly
+    /// as accepted by the `GetParameters` API.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for SsmReference {
+    type Err = SsmParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s
+            .strip_prefix("ssm://")
+            .ok_or(SsmParseError::InvalidScheme)?;
+        let name = percent_decode_str(raw).decode_utf8()?.into_owned();
+        if name.is_empty() || name == "/" {
+            return Err(SsmParseError::MissingName);
+        }
+        if name
+            .chars()
+            .any(|c| c.is_whitespace() || c == '#' || c == '?')
+        {
+            return Err(SsmParseError::InvalidName(name));
+        }
+        Ok(Self { name })
+    }
+}
+
+impl From<SsmReference> for SecretReference {
+    fn from(r: SsmReference) -> Self {
+        Self::AwsParameterStore(r)
+    }
+}
+
+impl ReferenceSyntax for SsmReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, SsmParseError::InvalidScheme) {
+                    tracing::warn!("Invalid AWS SSM parameter reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for SsmReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::AwsParameterStore(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SsmReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ssm://{}", utf8_percent_encode(&self.name, COMPONENT))
+    }
+}
+
+/// Parses any of the AWS reference types.
+///
+/// A single AWS provider serves both Secrets Manager and Parameter Store,
+/// so its parser accepts either syntax.
+pub fn parse_aws_reference(raw: &str) -> Option<SecretReference> {
+    AwsSmReference::try_parse(raw)
+        .map(SecretReference::from)
+        .or_else(|| SsmReference::try_parse(raw).map(SecretReference::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_aws_sm_simple() {
+        let r = AwsSmReference::from_str("aws-sm://prod/db").unwrap();
+        assert_eq!(r.location.secret_id, "prod/db");
+        assert_eq!(r.key, None);
+        assert_eq!(r.location.version_stage, None);
+    }
+
+    #[test]
+    fn test_parse_aws_sm_key_and_stage() {
+        let r = AwsSmReference::from_str("aws-sm://prod/db#password?version-stage=AWSPREVIOUS")
+            .unwrap();
+        assert_eq!(r.location.secret_id, "prod/db");
+        assert_eq!(r.key.as_deref(), Some("password"));
+        assert_eq!(r.location.version_stage.as_deref(), Some("AWSPREVIOUS"));
+
+        // Query first, then key, is equivalent
+        let swapped =
+            AwsSmReference::from_str("aws-sm://prod/db?version-stage=AWSPREVIOUS#password")
+                .unwrap();
+        assert_eq!(swapped, r);
+    }
+
+    #[test]
+    fn test_parse_aws_sm_arn() {
+        let raw =
+            "aws-sm://arn:aws:secretsmanager:us-east-1:123456789012:secret:prod/db-AbCdEf#user";
+        let r = AwsSmReference::from_str(raw).unwrap();
+        assert_eq!(
+            r.location.secret_id,
+            "arn:aws:secretsmanager:us-east-1:123456789012:secret:prod/db-AbCdEf"
+        );
+        assert_eq!(r.key.as_deref(), Some("user"));
+        assert_eq!(r.to_string(), raw);
+    }
+
+    #[test]
+    fn test_parse_aws_sm_errors() {
+        assert!(matches!(
+            AwsSmReference::from_str("bao://secret/app/password"),
+            Err(AwsSmParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            AwsSmReference::from_str("aws-sm://#key"),
+            Err(AwsSmParseError::MissingSecretId)
+        ));
+        assert!(matches!(
+            AwsSmReference::from_str("aws-sm://prod/db#"),
+            Err(AwsSmParseError::EmptyKey)
+        ));
+        assert!(matches!(
+            AwsSmReference::from_str("aws-sm://prod/db?stage=x"),
+            Err(AwsSmParseError::UnknownParameter(_))
+        ));
+        assert!(matches!(
+            AwsSmReference::from_str("aws-sm://prod/db#a?version-stage=x#b"),
+            Err(AwsSmParseError::Duplicate(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "aws-sm://prod/db",
+            "aws-sm://prod/db#password",
+            "aws-sm://prod/db#password?version-stage=AWSPREVIOUS",
+            "aws-sm://prod/db?version-id=01234567-89ab-cdef-0123-456789abcdef",
+            "aws-sm://my%20secret#key%23with%3Fmarks",
+        ] {
+            let r = AwsSmReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+            assert_eq!(AwsSmReference::from_str(&r.to_string()).unwrap(), r);
+        }
+    }
+
+    #[test]
+    fn test_parse_ssm() {
+        let r = SsmReference::from_str("ssm:///app/prod/db-password").unwrap();
+        assert_eq!(r.name(), "/app/prod/db-password");
+
+        let r = SsmReference::from_str("ssm://plain-name:3").unwrap();
+        assert_eq!(r.name(), "plain-name:3");
+    }
+
+    #[test]
+    fn test_parse_ssm_errors() {
+        assert!(matches!(
+            SsmReference::from_str("aws-sm://prod/db"),
+            Err(SsmParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            SsmReference::from_str("ssm://"),
+            Err(SsmParseError::MissingName)
+        ));
+        assert!(matches!(
+            SsmReference::from_str("ssm:///app/db#password"),
+            Err(SsmParseError::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn test_ssm_display_round_trip() {
+        for raw in ["ssm:///app/prod/db-password", "ssm://name:label"] {
+            let r = SsmReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+            assert_eq!(SsmReference::from_str(&r.to_string()).unwrap(), r);
+        }
+    }
+
+    #[test]
+    fn test_parse_aws_reference_either_syntax() {
+        assert!(matches!(
+            parse_aws_reference("aws-sm://prod/db#password"),
+            Some(SecretReference::AwsSecretsManager(_))
+        ));
+        assert!(matches!(
+            parse_aws_reference("ssm:///app/db"),
+            Some(SecretReference::AwsParameterStore(_))
+        ));
+        assert!(parse_aws_reference("bao://secret/app/password").is_none());
+    }
+}
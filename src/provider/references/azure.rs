@@ -0,0 +1,201 @@
+//! Defines the Azure Key Vault secret reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AkvParseError {
+    #[error("reference must start with 'akv://'")]
+    InvalidScheme,
+
+    #[error("invalid reference '{0}': expected '<vault-name>/<secret-name>[/<version>]'")]
+    Malformed(String),
+
+    #[error(
+        "invalid vault name '{0}': expected 3-24 letters, digits and '-', starting with a letter"
+    )]
+    Vault(String),
+
+    #[error("invalid secret name '{0}': expected 1-127 letters, digits and '-'")]
+    Secret(String),
+
+    #[error("invalid version '{0}': expected letters and digits")]
+    Version(String),
+}
+
+/// Represents a syntactically valid Azure Key Vault reference.
+/// Syntax: `akv://<vault-name>/<secret-name>[/<version>]`
+///
+/// * `vault-name` is the name of the vault, i.e. the first label of its
+///   `<vault-name>.vault.azure.net` hostname
+/// * `secret-name` is the name of the secret within the vault
+/// * `version` pins a specific secret version. Defaults to the current version
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AkvReference {
+    vault: String,
+    secret: String,
+    version: Option<String>,
+}
+
+impl AkvReference {
+    pub fn vault(&self) -> &str {
+        &self.vault
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+}
+
+fn is_valid_vault(s: &str) -> bool {
+    (3..=24).contains(&s.len())
+        && s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+fn is_valid_secret(s: &str) -> bool {
+    (1..=127).contains(&s.len()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+impl FromStr for AkvReference {
+    type Err = AkvParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s
+            .strip_prefix("akv://")
+            .ok_or(AkvParseError::InvalidScheme)?;
+
+        let segments: Vec<&str> = raw.split('/').collect();
+        let (vault, secret, version) = match segments.as_slice() {
+            [vault, secret] => (*vault, *secret, None),
+            [vault, secret, version] => (*vault, *secret, Some(*version)),
+            _ => return Err(AkvParseError::Malformed(raw.to_string())),
+        };
+
+        if !is_valid_vault(vault) {
+            return Err(AkvParseError::Vault(vault.to_string()));
+        }
+        if !is_valid_secret(secret) {
+            return Err(AkvParseError::Secret(secret.to_string()));
+        }
+        if let Some(version) = version
+            && (version.is_empty() || !version.chars().all(|c| c.is_ascii_alphanumeric()))
+        {
+            return Err(AkvParseError::Version(version.to_string()));
+        }
+
+        Ok(Self {
+            vault: vault.to_string(),
+            secret: secret.to_string(),
+            version: version.map(str::to_string),
+        })
+    }
+}
+
+impl From<AkvReference> for SecretReference {
+    fn from(r: AkvReference) -> Self {
+        Self::AzureKeyVault(r)
+    }
+}
+
+impl ReferenceSyntax for AkvReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, AkvParseError::InvalidScheme) {
+                    tracing::warn!("Invalid Azure Key Vault reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for AkvReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::AzureKeyVault(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AkvReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "akv://{}/{}", self.vault, self.secret)?;
+        if let Some(version) = &self.version {
+            write!(f, "/{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_akv() {
+        let r = AkvReference::from_str("akv://my-vault/db-password").unwrap();
+        assert_eq!(r.vault(), "my-vault");
+        assert_eq!(r.secret(), "db-password");
+        assert_eq!(r.version(), None);
+
+        let r =
+            AkvReference::from_str("akv://my-vault/db-password/0123456789abcdef0123456789abcdef")
+                .unwrap();
+        assert_eq!(r.version(), Some("0123456789abcdef0123456789abcdef"));
+    }
+
+    #[test]
+    fn test_parse_akv_errors() {
+        assert!(matches!(
+            AkvReference::from_str("gcpsm://projects/p/secrets/s"),
+            Err(AkvParseError::InvalidScheme)
+        ));
+        for bad in ["akv://my-vault", "akv://my-vault/a/b/c"] {
+            assert!(
+                matches!(
+                    AkvReference::from_str(bad),
+                    Err(AkvParseError::Malformed(_))
+                ),
+                "'{bad}' should be rejected"
+            );
+        }
+        for bad in [
+            "akv://kv/secret",
+            "akv://1vault/secret",
+            "akv://my.vault/secret",
+        ] {
+            assert!(
+                matches!(AkvReference::from_str(bad), Err(AkvParseError::Vault(_))),
+                "'{bad}' should be rejected"
+            );
+        }
+        assert!(matches!(
+            AkvReference::from_str("akv://my-vault/db_password"),
+            Err(AkvParseError::Secret(_))
+        ));
+        assert!(matches!(
+            AkvReference::from_str("akv://my-vault/db-password/"),
+            Err(AkvParseError::Version(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "akv://my-vault/db-password",
+            "akv://my-vault/db-password/0123456789abcdef0123456789abcdef",
+        ] {
+            let r = AkvReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+            assert_eq!(AkvReference::from_str(&r.to_string()).unwrap(), r);
+        }
+    }
+}
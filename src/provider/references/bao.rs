@@ -2,7 +2,9 @@
 use super::{Extract, ReferenceSyntax, SecretReference};
 use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fmt;
+use std::num::NonZeroU64;
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -16,7 +18,7 @@ const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
 
 #[derive(Debug, Error)]
 pub enum BaoParseError {
-    #[error("reference must start with 'bao://'")]
+    #[error("reference must start with 'bao://', 'bao+dyn://' or 'bao+pki://'")]
     InvalidScheme,
 
     #[error("invalid URL structure: {0}")]
@@ -28,6 +30,21 @@ pub enum BaoParseError {
     #[error("invalid path segments: expected at least 2 (path/field), got {0}")]
     InvalidSegments(usize),
 
+    #[error("missing field: expected '#<field>' after the path")]
+    MissingField,
+
+    #[error("invalid PKI path '{0}': expected '<mount>/issue/<role>'")]
+    PkiPath(String),
+
+    #[error("invalid version '{0}': expected a positive integer")]
+    Version(String),
+
+    #[error("unsupported query parameter '{0}': only 'version' is supported")]
+    Query(String),
+
+    #[error("duplicate query parameter '{0}': give multiple values comma separated instead")]
+    DuplicateQuery(String),
+
     #[error("validation error: {0}")]
     Validation(#[from] ValidationError),
 
@@ -37,7 +54,7 @@ pub enum BaoParseError {
 
 #[derive(Debug, Error)]
 pub enum ValidationError {
-    #[error("invalid mount '{0}': cannot be empty or contain '/'")]
+    #[error("invalid mount '{0}': expected non-empty '/' separated segments")]
     Mount(String),
 
     #[error("invalid path '{0}': segments cannot be empty")]
@@ -47,10 +64,11 @@ pub enum ValidationError {
     Field,
 }
 
-/// The path where a KV v2 secrets engine is mounted (e.g. `secret`).
+/// The path where a secrets engine or auth method is mounted (e.g. `secret`).
 ///
-/// Restricted to a single path segment: the mount occupies the host position
-/// of the `bao://` syntax, so mounts nested under a `/` cannot be expressed.
+/// Mounts may be nested (e.g. `teams/payments/kv`). Surrounding slashes,
+/// as reported by the mounts API, are normalized away so equal mounts
+/// compare equal.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(try_from = "String", into = "String")]
 pub struct BaoMount(String);
@@ -59,16 +77,21 @@ impl BaoMount {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/')
+    }
 }
 
 impl TryFrom<String> for BaoMount {
     type Error = ValidationError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        if value.is_empty() || value.contains('/') {
+        let trimmed = value.trim_matches('/');
+        if trimmed.is_empty() || trimmed.split('/').any(str::is_empty) {
             return Err(ValidationError::Mount(value));
         }
-        Ok(Self(value))
+        Ok(Self(trimmed.to_string()))
     }
 }
 
@@ -113,6 +136,17 @@ impl BaoPath {
     pub fn segments(&self) -> impl Iterator<Item = &str> {
         self.0.iter().map(String::as_str)
     }
+
+    /// The remainder of the path within `mount`, if the mount contains it.
+    pub fn strip_mount(&self, mount: &BaoMount) -> Option<BaoPath> {
+        let mut segments = self.segments();
+        for expected in mount.segments() {
+            if segments.next() != Some(expected) {
+                return None;
+            }
+        }
+        Self::new(segments.map(str::to_string).collect()).ok()
+    }
 }
 
 impl fmt::Display for BaoPath {
@@ -152,28 +186,39 @@ impl fmt::Display for BaoField {
     }
 }
 
-/// The (mount, path) pair addressing a single KV v2 secret.
+/// The full path of a single KV secret, including its engine's mount, and
+/// the version to read (KV v2 only, the latest if unset).
+///
+/// Where the mount ends and the secret's path begins is not known from the
+/// reference alone, since mounts may be nested. The provider resolves it
+/// from its configured or detected mounts when fetching.
 ///
 /// Every referenced field of that secret lives in the same data map, so
 /// references are grouped and fetched by location.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BaoSecretLocation {
-    pub mount: BaoMount,
     pub path: BaoPath,
+    pub version: Option<NonZeroU64>,
 }
 
 impl fmt::Display for BaoSecretLocation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}/{}", self.mount, self.path)
+        write!(f, "{}", self.path)?;
+        if let Some(version) = self.version {
+            write!(f, "?version={}", version)?;
+        }
+        Ok(())
     }
 }
 
 /// Represents a syntactically valid OpenBao / Vault secret reference.
-/// Syntax: `bao://<mount>/<path>/<field>`
+/// Syntax: `bao://<mount>/<path>/<field>[?version=<n>]`
 ///
-/// * `mount` is the path where the KV v2 secrets engine is mounted (e.g. `secret`)
+/// * `mount` is the path where the KV secrets engine is mounted (e.g. `secret`),
+///   may contain nested segments (e.g. `teams/payments/kv`)
 /// * `path` is the secret's path within that engine, may contain nested segments (e.g. `app/prod`)
 /// * `field` is the specific key within the secret's data map
+/// * `version` pins a version of a KV v2 secret, instead of reading the latest
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct BaoReference {
     pub location: BaoSecretLocation,
@@ -191,27 +236,47 @@ impl FromStr for BaoReference {
         let url = url::Url::parse(s)?;
 
         let host = url.host_str().ok_or(BaoParseError::MissingMount)?;
-        let mount = BaoMount::try_from(percent_decode_str(host).decode_utf8()?.into_owned())?;
+        let mount = percent_decode_str(host).decode_utf8()?.into_owned();
+        if mount.is_empty() {
+            return Err(BaoParseError::MissingMount);
+        }
 
         let raw_segments = url
             .path_segments()
             .ok_or(BaoParseError::InvalidSegments(0))?;
 
-        let mut segments = Vec::new();
+        let mut segments = vec![mount];
         for segment in raw_segments {
             segments.push(percent_decode_str(segment).decode_utf8()?.into_owned());
         }
 
-        if segments.len() < 2 {
-            return Err(BaoParseError::InvalidSegments(segments.len()));
+        // The host holds the first segment of the mount, which must be
+        // followed by at least a path and a field.
+        if segments.len() < 3 {
+            return Err(BaoParseError::InvalidSegments(segments.len() - 1));
         }
 
         let field = segments.pop().ok_or(BaoParseError::InvalidSegments(0))?;
         let field = BaoField::new(field)?;
         let path = BaoPath::new(segments)?;
 
+        let mut version = None;
+        for (key, value) in url.query_pairs() {
+            if key != "version" {
+                return Err(BaoParseError::Query(key.into_owned()));
+            }
+            if version.is_some() {
+                return Err(BaoParseError::DuplicateQuery(key.into_owned()));
+            }
+            version = Some(
+                value
+                    .parse::<NonZeroU64>()
+                    .map_err(|_| BaoParseError::Version(value.into_owned()))?,
+            );
+        }
+
         Ok(Self {
-            location: BaoSecretLocation { mount, path },
+            location: BaoSecretLocation { path, version },
             field,
         })
     }
@@ -247,22 +312,254 @@ impl Extract for BaoReference {
 
 impl fmt::Display for BaoReference {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bao:/")?;
+        for segment in self.location.path.segments() {
+            write!(f, "/{}", utf8_percent_encode(segment, COMPONENT))?;
+        }
         write!(
             f,
-            "bao://{}",
-            utf8_percent_encode(self.location.mount.as_str(), COMPONENT)
+            "/{}",
+            utf8_percent_encode(self.field.as_str(), COMPONENT)
         )?;
-        for segment in self.location.path.segments() {
+        if let Some(version) = self.location.version {
+            write!(f, "?version={}", version)?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a syntactically valid reference to a field of a dynamic secret.
+/// Syntax: `bao+dyn://<path>#<field>`
+///
+/// * `path` is the full API path which issues the credentials, including the
+///   engine's mount (e.g. `database/creds/app` or `aws/creds/deploy`)
+/// * `field` is the specific key within the issued data (e.g. `username`)
+///
+/// Every read of a dynamic secret issues new credentials under a new lease,
+/// so all fields of the same path are resolved from a single lease.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BaoDynamicReference {
+    pub path: BaoPath,
+    pub field: BaoField,
+}
+
+impl FromStr for BaoDynamicReference {
+    type Err = BaoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("bao+dyn://") {
+            return Err(BaoParseError::InvalidScheme);
+        }
+
+        let url = url::Url::parse(s)?;
+        let segments = api_segments(&url)?;
+        if segments.len() < 2 {
+            return Err(BaoParseError::InvalidSegments(segments.len()));
+        }
+
+        Ok(Self {
+            path: BaoPath::new(segments)?,
+            field: fragment_field(&url)?,
+        })
+    }
+}
+
+/// Decodes the host and path of a `bao+<engine>://` URL into the segments
+/// of an API path. The host holds the first segment, usually the mount.
+fn api_segments(url: &url::Url) -> Result<Vec<String>, BaoParseError> {
+    let host = url.host_str().ok_or(BaoParseError::MissingMount)?;
+    let mut segments = vec![percent_decode_str(host).decode_utf8()?.into_owned()];
+    if let Some(raw_segments) = url.path_segments() {
+        for segment in raw_segments.filter(|s| !s.is_empty()) {
+            segments.push(percent_decode_str(segment).decode_utf8()?.into_owned());
+        }
+    }
+    Ok(segments)
+}
+
+/// Decodes the `#<field>` fragment of a `bao+<engine>://` URL.
+fn fragment_field(url: &url::Url) -> Result<BaoField, BaoParseError> {
+    let field = url.fragment().ok_or(BaoParseError::MissingField)?;
+    Ok(BaoField::new(
+        percent_decode_str(field).decode_utf8()?.into_owned(),
+    )?)
+}
+
+impl From<BaoDynamicReference> for SecretReference {
+    fn from(r: BaoDynamicReference) -> Self {
+        Self::BaoDynamic(r)
+    }
+}
+
+impl ReferenceSyntax for BaoDynamicReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, BaoParseError::InvalidScheme) {
+                    tracing::warn!("Invalid OpenBao dynamic reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for BaoDynamicReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::BaoDynamic(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BaoDynamicReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bao+dyn:/")?;
+        for segment in self.path.segments() {
             write!(f, "/{}", utf8_percent_encode(segment, COMPONENT))?;
         }
         write!(
             f,
-            "/{}",
+            "#{}",
+            utf8_percent_encode(self.field.as_str(), COMPONENT)
+        )
+    }
+}
+
+/// A certificate issuance request against a PKI secrets engine: the
+/// `<mount>/issue/<role>` path, and the parameters sent with it.
+///
+/// Parameters are kept sorted, so requests which only differ in the order
+/// of their parameters compare equal and share one issued certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BaoPkiRequest {
+    pub path: BaoPath,
+    pub params: BTreeMap<String, String>,
+}
+
+impl fmt::Display for BaoPkiRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut segments = self.path.segments();
+        if let Some(first) = segments.next() {
+            write!(f, "{}", utf8_percent_encode(first, COMPONENT))?;
+        }
+        for segment in segments {
+            write!(f, "/{}", utf8_percent_encode(segment, COMPONENT))?;
+        }
+        for (i, (key, value)) in self.params.iter().enumerate() {
+            write!(
+                f,
+                "{}{}={}",
+                if i == 0 { '?' } else { '&' },
+                utf8_percent_encode(key, COMPONENT),
+                utf8_percent_encode(value, COMPONENT)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Represents a syntactically valid reference to a part of an issued certificate.
+/// Syntax: `bao+pki://<mount>/issue/<role>?<param>=<value>&...#<field>`
+///
+/// * `mount` is the path where the PKI secrets engine is mounted (e.g. `pki`)
+/// * `role` is the PKI role to issue the certificate against
+/// * `param`s are sent with the issue request (e.g. `common_name`, `ttl`, `alt_names`)
+/// * `field` selects the part of the issued certificate: `certificate`,
+///   `private_key`, `ca_chain`, `issuing_ca` or `serial_number`
+///
+/// All fields of the same request are resolved from a single issuance, so
+/// a certificate and its private key always match.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BaoPkiReference {
+    pub request: BaoPkiRequest,
+    pub field: BaoField,
+}
+
+impl FromStr for BaoPkiReference {
+    type Err = BaoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("bao+pki://") {
+            return Err(BaoParseError::InvalidScheme);
+        }
+
+        let url = url::Url::parse(s)?;
+        let segments = api_segments(&url)?;
+        let n = segments.len();
+        if n < 3 || segments[n - 2] != "issue" {
+            return Err(BaoParseError::PkiPath(segments.join("/")));
+        }
+
+        let mut params = BTreeMap::new();
+        for (key, value) in url.query_pairs().into_owned() {
+            if params.contains_key(&key) {
+                return Err(BaoParseError::DuplicateQuery(key));
+            }
+            params.insert(key, value);
+        }
+
+        Ok(Self {
+            request: BaoPkiRequest {
+                path: BaoPath::new(segments)?,
+                params,
+            },
+            field: fragment_field(&url)?,
+        })
+    }
+}
+
+impl From<BaoPkiReference> for SecretReference {
+    fn from(r: BaoPkiReference) -> Self {
+        Self::BaoPki(r)
+    }
+}
+
+impl ReferenceSyntax for BaoPkiReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, BaoParseError::InvalidScheme) {
+                    tracing::warn!("Invalid OpenBao PKI reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for BaoPkiReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::BaoPki(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BaoPkiReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "bao+pki://{}#{}",
+            self.request,
             utf8_percent_encode(self.field.as_str(), COMPONENT)
         )
     }
 }
 
+/// Parses any OpenBao / Vault reference syntax.
+///
+/// A single OpenBao provider serves KV secrets, dynamic secrets and PKI
+/// certificates, so its parser accepts every syntax.
+pub fn parse_bao_reference(raw: &str) -> Option<SecretReference> {
+    BaoReference::try_parse(raw)
+        .map(SecretReference::from)
+        .or_else(|| BaoDynamicReference::try_parse(raw).map(SecretReference::from))
+        .or_else(|| BaoPkiReference::try_parse(raw).map(SecretReference::from))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -271,8 +568,8 @@ mod tests {
     fn test_parse_bao_simple() {
         let raw = "bao://secret/app/password";
         let r = BaoReference::from_str(raw).unwrap();
-        assert_eq!(r.location.mount.as_str(), "secret");
-        assert_eq!(r.location.path.to_string(), "app");
+        assert_eq!(r.location.path.to_string(), "secret/app");
+        assert_eq!(r.location.version, None);
         assert_eq!(r.field.as_str(), "password");
     }
 
@@ -280,8 +577,7 @@ mod tests {
     fn test_parse_bao_nested_path() {
         let raw = "bao://secret/app/prod/db/password";
         let r = BaoReference::from_str(raw).unwrap();
-        assert_eq!(r.location.mount.as_str(), "secret");
-        assert_eq!(r.location.path.to_string(), "app/prod/db");
+        assert_eq!(r.location.path.to_string(), "secret/app/prod/db");
         assert_eq!(r.field.as_str(), "password");
     }
 
@@ -296,8 +592,7 @@ mod tests {
     fn test_parse_bao_spaces() {
         let raw = "bao://secret/My%20App/password";
         let r = BaoReference::from_str(raw).unwrap();
-        assert_eq!(r.location.mount.as_str(), "secret");
-        assert_eq!(r.location.path.to_string(), "My App");
+        assert_eq!(r.location.path.to_string(), "secret/My App");
         assert_eq!(r.field.as_str(), "password");
     }
 
@@ -336,10 +631,58 @@ mod tests {
     }
 
     #[test]
-    fn test_mount_rejects_slash() {
-        assert!(BaoMount::from_str("team/kv").is_err());
-        assert!(BaoMount::from_str("").is_err());
-        assert!(BaoMount::from_str("secret").is_ok());
+    fn test_mount_allows_nesting() {
+        let mount = BaoMount::from_str("teams/payments/kv/").unwrap();
+        assert_eq!(mount.as_str(), "teams/payments/kv");
+        assert_eq!(mount, BaoMount::from_str("teams/payments/kv").unwrap());
+        for bad in ["", "/", "teams//kv"] {
+            assert!(
+                BaoMount::from_str(bad).is_err(),
+                "'{bad}' should be rejected"
+            );
+        }
+
+        let r = BaoReference::from_str("bao://teams/payments/kv/app/password").unwrap();
+        let within = r.location.path.strip_mount(&mount).unwrap();
+        assert_eq!(within.to_string(), "app");
+        assert!(
+            r.location
+                .path
+                .strip_mount(&BaoMount::from_str("teams/payments/kv/app").unwrap())
+                .is_none()
+        );
+        assert!(
+            r.location
+                .path
+                .strip_mount(&BaoMount::from_str("teams/pay").unwrap())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_parse_bao_version() {
+        let raw = "bao://secret/app/password?version=3";
+        let r = BaoReference::from_str(raw).unwrap();
+        assert_eq!(r.location.path.to_string(), "secret/app");
+        assert_eq!(r.location.version.map(NonZeroU64::get), Some(3));
+        assert_eq!(r.to_string(), raw);
+        assert_ne!(
+            r,
+            BaoReference::from_str("bao://secret/app/password").unwrap()
+        );
+
+        assert!(matches!(
+            BaoReference::from_str("bao://secret/app/password?version=0"),
+            Err(BaoParseError::Version(_))
+        ));
+        assert!(matches!(
+            BaoReference::from_str("bao://secret/app/password?version=latest"),
+            Err(BaoParseError::Version(_))
+        ));
+        assert!(matches!(
+            BaoReference::from_str("bao://secret/app/password?ttl=1h"),
+            Err(BaoParseError::Query(_))
+        ));
     }
 
     #[test]
@@ -354,13 +697,119 @@ mod tests {
     fn test_display_round_trip_encoded() {
         let raw = "bao://My%20Mount/My%20App/pass%2Fword";
         let r = BaoReference::from_str(raw).unwrap();
-        assert_eq!(r.location.mount.as_str(), "My Mount");
-        assert_eq!(r.location.path.to_string(), "My App");
+        assert_eq!(r.location.path.to_string(), "My Mount/My App");
         assert_eq!(r.field.as_str(), "pass/word");
         assert_eq!(r.to_string(), raw);
         assert_eq!(BaoReference::from_str(&r.to_string()).unwrap(), r);
     }
 
+    #[test]
+    fn test_parse_bao_dynamic() {
+        let r = BaoDynamicReference::from_str("bao+dyn://database/creds/app#username").unwrap();
+        assert_eq!(r.path.to_string(), "database/creds/app");
+        assert_eq!(r.field.as_str(), "username");
+        assert_eq!(r.to_string(), "bao+dyn://database/creds/app#username");
+
+        let raw = "bao+dyn://aws/creds/My%20Role#secret_key";
+        let r = BaoDynamicReference::from_str(raw).unwrap();
+        assert_eq!(r.path.to_string(), "aws/creds/My Role");
+        assert_eq!(r.to_string(), raw);
+    }
+
+    #[test]
+    fn test_parse_bao_dynamic_errors() {
+        assert!(matches!(
+            BaoDynamicReference::from_str("bao://database/creds/app#username"),
+            Err(BaoParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            BaoDynamicReference::from_str("bao+dyn://database/creds/app"),
+            Err(BaoParseError::MissingField)
+        ));
+        assert!(matches!(
+            BaoDynamicReference::from_str("bao+dyn://database#username"),
+            Err(BaoParseError::InvalidSegments(1))
+        ));
+        assert!(matches!(
+            BaoDynamicReference::from_str("bao+dyn://database/creds/app#"),
+            Err(BaoParseError::Validation(ValidationError::Field))
+        ));
+    }
+
+    #[test]
+    fn test_parse_bao_reference_either_syntax() {
+        assert!(matches!(
+            parse_bao_reference("bao://secret/app/password"),
+            Some(SecretReference::Bao(_))
+        ));
+        assert!(matches!(
+            parse_bao_reference("bao+dyn://database/creds/app#password"),
+            Some(SecretReference::BaoDynamic(_))
+        ));
+        assert!(matches!(
+            parse_bao_reference("bao+pki://pki/issue/web?common_name=api.internal#certificate"),
+            Some(SecretReference::BaoPki(_))
+        ));
+        assert!(parse_bao_reference("ssm:///app/db").is_none());
+    }
+
+    #[test]
+    fn test_parse_bao_pki() {
+        let raw = "bao+pki://pki/issue/web?ttl=72h&common_name=api.internal#private_key";
+        let r = BaoPkiReference::from_str(raw).unwrap();
+        assert_eq!(r.request.path.to_string(), "pki/issue/web");
+        assert_eq!(r.request.params["common_name"], "api.internal");
+        assert_eq!(r.request.params["ttl"], "72h");
+        assert_eq!(r.field.as_str(), "private_key");
+        // Parameters are sorted, so both orders share one issuance.
+        assert_eq!(
+            r.to_string(),
+            "bao+pki://pki/issue/web?common_name=api.internal&ttl=72h#private_key"
+        );
+        let reordered = BaoPkiReference::from_str(
+            "bao+pki://pki/issue/web?common_name=api.internal&ttl=72h#certificate",
+        )
+        .unwrap();
+        assert_eq!(reordered.request, r.request);
+
+        let nested = BaoPkiReference::from_str(
+            "bao+pki://pki_int/issue/my%20role?alt_names=a.internal%2Cb.internal#ca_chain",
+        )
+        .unwrap();
+        assert_eq!(nested.request.params["alt_names"], "a.internal,b.internal");
+        assert_eq!(
+            BaoPkiReference::from_str(&nested.to_string()).unwrap(),
+            nested
+        );
+    }
+
+    #[test]
+    fn test_parse_bao_pki_errors() {
+        assert!(matches!(
+            BaoPkiReference::from_str("bao+pki://pki/sign/web#certificate"),
+            Err(BaoParseError::PkiPath(_))
+        ));
+        assert!(matches!(
+            BaoPkiReference::from_str("bao+pki://pki/issue#certificate"),
+            Err(BaoParseError::PkiPath(_))
+        ));
+        assert!(matches!(
+            BaoPkiReference::from_str("bao+pki://pki/issue/web?common_name=a"),
+            Err(BaoParseError::MissingField)
+        ));
+        // A repeated key would silently drop all but one value.
+        assert!(matches!(
+            BaoPkiReference::from_str(
+                "bao+pki://pki/issue/web?alt_names=a.internal&alt_names=b.internal#certificate"
+            ),
+            Err(BaoParseError::DuplicateQuery(key)) if key == "alt_names"
+        ));
+        assert!(matches!(
+            BaoReference::from_str("bao://secret/app/password?version=1&version=2"),
+            Err(BaoParseError::DuplicateQuery(_))
+        ));
+    }
+
     #[test]
     fn test_display_canonicalizes() {
         // Gratuitous escapes decode on parse, so semantically equal
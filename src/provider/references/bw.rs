@@ -0,0 +1,221 @@
+//! Defines the Bitwarden vault reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Escapes everything outside the RFC 3986 unreserved set so that every
+/// component survives a display/parse round trip.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+#[derive(Debug, Error)]
+pub enum BwParseError {
+    #[error("reference must start with 'bw://'")]
+    InvalidScheme,
+
+    #[error("invalid reference: expected 'bw://<item>/<field>'")]
+    Malformed,
+
+    #[error("item and field cannot be empty")]
+    EmptySegment,
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+/// How a reference identifies a vault item.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BwItem {
+    Id(Uuid),
+    /// The exact name of the item, which must be unique in the vault
+    Name(String),
+}
+
+impl fmt::Display for BwItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Id(id) => write!(f, "{}", id),
+            Self::Name(name) => write!(f, "{}", utf8_percent_encode(name, COMPONENT)),
+        }
+    }
+}
+
+/// The part of an item a reference resolves to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BwField {
+    UserName,
+    Password,
+    /// The current TOTP code of a login
+    Totp,
+    Notes,
+    /// A custom field, matched by its exact name
+    Custom(String),
+}
+
+impl BwField {
+    fn parse(raw: &str) -> Result<Self, BwParseError> {
+        let name = percent_decode_str(raw).decode_utf8()?;
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "username" => Self::UserName,
+            "password" => Self::Password,
+            "totp" => Self::Totp,
+            "notes" => Self::Notes,
+            _ => Self::Custom(name.into_owned()),
+        })
+    }
+}
+
+impl fmt::Display for BwField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UserName => f.write_str("username"),
+            Self::Password => f.write_str("password"),
+            Self::Totp => f.write_str("totp"),
+            Self::Notes => f.write_str("notes"),
+            Self::Custom(name) => write!(f, "{}", utf8_percent_encode(name, COMPONENT)),
+        }
+    }
+}
+
+/// Represents a syntactically valid Bitwarden vault reference.
+/// Syntax: `bw://<item>/<field>`
+///
+/// * `item` is the id of the item, or its exact name
+/// * `field` is `username`, `password`, `totp` or `notes`
+///   (case-insensitive), or the name of a custom field
+///
+/// Characters which are not allowed in a URL path (such as spaces and `/`)
+/// are percent-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BwReference {
+    item: BwItem,
+    field: BwField,
+}
+
+impl BwReference {
+    pub fn item(&self) -> &BwItem {
+        &self.item
+    }
+
+    pub fn field(&self) -> &BwField {
+        &self.field
+    }
+}
+
+impl FromStr for BwReference {
+    type Err = BwParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s.strip_prefix("bw://").ok_or(BwParseError::InvalidScheme)?;
+        let (item, field) = path.split_once('/').ok_or(BwParseError::Malformed)?;
+        if field.contains('/') {
+            return Err(BwParseError::Malformed);
+        }
+        if item.is_empty() || field.is_empty() {
+            return Err(BwParseError::EmptySegment);
+        }
+
+        let item = match Uuid::parse_str(item) {
+            Ok(id) => BwItem::Id(id),
+            Err(_) => BwItem::Name(percent_decode_str(item).decode_utf8()?.into_owned()),
+        };
+
+        Ok(Self {
+            item,
+            field: BwField::parse(field)?,
+        })
+    }
+}
+
+impl From<BwReference> for SecretReference {
+    fn from(r: BwReference) -> Self {
+        Self::Bw(r)
+    }
+}
+
+impl ReferenceSyntax for BwReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, BwParseError::InvalidScheme) {
+                    tracing::warn!("Invalid Bitwarden reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for BwReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Bw(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BwReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bw://{}/{}", self.item, self.field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bw() {
+        let r = BwReference::from_str("bw://GitHub/Password").unwrap();
+        assert_eq!(r.item(), &BwItem::Name("GitHub".into()));
+        assert_eq!(r.field(), &BwField::Password);
+
+        let r = BwReference::from_str("bw://3f2504e0-4f89-11d3-9a0c-0305e82c3301/totp").unwrap();
+        assert!(matches!(r.item(), BwItem::Id(_)));
+        assert_eq!(r.field(), &BwField::Totp);
+
+        let r = BwReference::from_str("bw://Home%20Router/Admin%20PIN").unwrap();
+        assert_eq!(r.item(), &BwItem::Name("Home Router".into()));
+        assert_eq!(r.field(), &BwField::Custom("Admin PIN".into()));
+    }
+
+    #[test]
+    fn test_parse_bw_errors() {
+        assert!(matches!(
+            BwReference::from_str("bws://a/b"),
+            Err(BwParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            BwReference::from_str("bw://item"),
+            Err(BwParseError::Malformed)
+        ));
+        assert!(matches!(
+            BwReference::from_str("bw://item/field/extra"),
+            Err(BwParseError::Malformed)
+        ));
+        assert!(matches!(
+            BwReference::from_str("bw:///password"),
+            Err(BwParseError::EmptySegment)
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "bw://GitHub/password",
+            "bw://3f2504e0-4f89-11d3-9a0c-0305e82c3301/username",
+            "bw://Home%20Router/Admin%20PIN",
+            "bw://a%2Fb/notes",
+        ] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
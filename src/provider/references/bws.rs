@@ -64,6 +64,7 @@ mod tests {
     fn test_parse_bws() {
         let raw = "3832b656-a93b-45ad-bdfa-b267016802c3";
         let r = SecretReference::from_str(raw).unwrap();
+        assert_eq!(r.to_string(), raw);
         match r {
             SecretReference::Bws(u) => assert_eq!(u.to_string(), raw),
             _ => panic!("wrong type"),
@@ -0,0 +1,190 @@
+//! Defines the Doppler secret reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DopplerParseError {
+    #[error("reference must start with 'doppler://'")]
+    InvalidScheme,
+
+    #[error("invalid reference: expected 'doppler://<project>/<config>/<NAME>'")]
+    Malformed,
+
+    #[error("invalid project or config '{0}': expected ascii letters, digits, '-' or '_'")]
+    Slug(String),
+
+    #[error(
+        "invalid secret name '{0}': expected ascii letters, digits or '_', not starting with a digit"
+    )]
+    Name(String),
+}
+
+/// Represents a syntactically valid Doppler secret reference.
+/// Syntax: `doppler://<project>/<config>/<NAME>`
+///
+/// * `project` is the project slug, e.g. `backend`
+/// * `config` is the config within the project, e.g. `prd` or `dev_personal`
+/// * `NAME` is the secret name, e.g. `DATABASE_URL`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DopplerReference {
+    project: String,
+    config: String,
+    name: String,
+}
+
+impl DopplerReference {
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn config(&self) -> &str {
+        &self.config
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn is_valid_slug(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+fn is_valid_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for DopplerReference {
+    type Err = DopplerParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s
+            .strip_prefix("doppler://")
+            .ok_or(DopplerParseError::InvalidScheme)?;
+
+        let mut parts = path.split('/');
+        let (Some(project), Some(config), Some(name), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(DopplerParseError::Malformed);
+        };
+
+        for slug in [project, config] {
+            if !is_valid_slug(slug) {
+                return Err(DopplerParseError::Slug(slug.to_string()));
+            }
+        }
+        if !is_valid_name(name) {
+            return Err(DopplerParseError::Name(name.to_string()));
+        }
+
+        Ok(Self {
+            project: project.to_string(),
+            config: config.to_string(),
+            name: name.to_string(),
+        })
+    }
+}
+
+impl From<DopplerReference> for SecretReference {
+    fn from(r: DopplerReference) -> Self {
+        Self::Doppler(r)
+    }
+}
+
+impl ReferenceSyntax for DopplerReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, DopplerParseError::InvalidScheme) {
+                    tracing::warn!("Invalid Doppler reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for DopplerReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Doppler(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DopplerReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "doppler://{}/{}/{}",
+            self.project, self.config, self.name
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_doppler() {
+        let r = DopplerReference::from_str("doppler://backend/prd/DATABASE_URL").unwrap();
+        assert_eq!(r.project(), "backend");
+        assert_eq!(r.config(), "prd");
+        assert_eq!(r.name(), "DATABASE_URL");
+
+        let r = DopplerReference::from_str("doppler://my-app/dev_personal/_KEY2").unwrap();
+        assert_eq!(r.config(), "dev_personal");
+        assert_eq!(r.name(), "_KEY2");
+    }
+
+    #[test]
+    fn test_parse_doppler_errors() {
+        assert!(matches!(
+            DopplerReference::from_str("dop://a/b/C"),
+            Err(DopplerParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler://backend/prd"),
+            Err(DopplerParseError::Malformed)
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler://backend/prd/A/B"),
+            Err(DopplerParseError::Malformed)
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler://back end/prd/A"),
+            Err(DopplerParseError::Slug(_))
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler:///prd/A"),
+            Err(DopplerParseError::Slug(_))
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler://backend/prd/1KEY"),
+            Err(DopplerParseError::Name(_))
+        ));
+        assert!(matches!(
+            DopplerReference::from_str("doppler://backend/prd/MY-KEY"),
+            Err(DopplerParseError::Name(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "doppler://backend/prd/DATABASE_URL",
+            "doppler://my-app/dev_personal/API_KEY",
+        ] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
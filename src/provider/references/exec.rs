@@ -0,0 +1,176 @@
+//! Defines the reference type for the exec provider, whose scheme is
+//! chosen at runtime by configuration.
+use super::{Extract, SecretReference};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Schemes resolved by locket itself, which a plugin cannot claim.
+const RESERVED_SCHEMES: &[&str] = &["file", "env"];
+
+#[derive(Debug, Error)]
+pub enum ExecSchemeError {
+    #[error(
+        "invalid scheme '{0}': expected a lowercase letter followed by lowercase letters, digits, '+', '-' or '.'"
+    )]
+    Malformed(String),
+
+    #[error("scheme '{0}' is reserved for local references")]
+    Reserved(String),
+}
+
+/// The URL scheme an exec provider plugin answers for, e.g. `pass` for
+/// `pass://<name>` references.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ExecScheme(String);
+
+impl ExecScheme {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for ExecScheme {
+    type Err = ExecSchemeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_suffix("://").unwrap_or(s);
+        let valid = s.starts_with(|c: char| c.is_ascii_lowercase())
+            && s.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '+' | '-' | '.')
+            });
+        if !valid {
+            return Err(ExecSchemeError::Malformed(s.to_string()));
+        }
+        if RESERVED_SCHEMES.contains(&s) {
+            return Err(ExecSchemeError::Reserved(s.to_string()));
+        }
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for ExecScheme {
+    type Error = ExecSchemeError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ExecScheme> for String {
+    fn from(scheme: ExecScheme) -> Self {
+        scheme.0
+    }
+}
+
+impl fmt::Display for ExecScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Represents a reference handled by an exec provider plugin.
+/// Syntax: `<scheme>://<anything>`
+///
+/// Everything after the scheme is opaque to locket, and is passed to the
+/// plugin exactly as written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecReference {
+    raw: String,
+    scheme_len: usize,
+}
+
+impl ExecReference {
+    /// Parses a reference for the given scheme. Returns `None` if the
+    /// reference belongs to another scheme, or has nothing after it.
+    pub fn parse(scheme: &ExecScheme, raw: &str) -> Option<Self> {
+        let rest = raw
+            .strip_prefix(scheme.as_str())
+            .and_then(|r| r.strip_prefix("://"))?;
+        if rest.is_empty() {
+            tracing::warn!("Invalid {} reference '{}': missing path", scheme, raw);
+            return None;
+        }
+        Some(Self {
+            raw: raw.to_string(),
+            scheme_len: scheme.as_str().len(),
+        })
+    }
+
+    pub fn scheme(&self) -> &str {
+        &self.raw[..self.scheme_len]
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+}
+
+impl From<ExecReference> for SecretReference {
+    fn from(r: ExecReference) -> Self {
+        Self::Exec(r)
+    }
+}
+
+impl Extract for ExecReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Exec(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ExecReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_exec() {
+        let scheme = ExecScheme::from_str("pass").unwrap();
+        let r = ExecReference::parse(&scheme, "pass://work/db/password").unwrap();
+        assert_eq!(r.scheme(), "pass");
+        assert_eq!(r.as_str(), "pass://work/db/password");
+        assert_eq!(r.to_string(), "pass://work/db/password");
+        assert_eq!(
+            SecretReference::Exec(r).to_string(),
+            "pass://work/db/password"
+        );
+
+        assert!(ExecReference::parse(&scheme, "pass://").is_none());
+        assert!(ExecReference::parse(&scheme, "passx://a").is_none());
+        assert!(ExecReference::parse(&scheme, "op://v/i/f").is_none());
+    }
+
+    #[test]
+    fn test_parse_scheme() {
+        assert_eq!(ExecScheme::from_str("gopass").unwrap().as_str(), "gopass");
+        assert_eq!(ExecScheme::from_str("hsm://").unwrap().as_str(), "hsm");
+        assert_eq!(
+            ExecScheme::from_str("my-store.v2").unwrap().as_str(),
+            "my-store.v2"
+        );
+        for bad in ["", "Pass", "1pass", "my_store", "a/b"] {
+            assert!(
+                matches!(
+                    ExecScheme::from_str(bad),
+                    Err(ExecSchemeError::Malformed(_))
+                ),
+                "'{bad}' should be rejected"
+            );
+        }
+        assert!(matches!(
+            ExecScheme::from_str("env"),
+            Err(ExecSchemeError::Reserved(_))
+        ));
+    }
+}
@@ -0,0 +1,214 @@
+//! Defines the Google Cloud Secret Manager reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GcpSmParseError {
+    #[error("reference must start with 'gcpsm://'")]
+    InvalidScheme,
+
+    #[error(
+        "invalid resource name '{0}': expected 'projects/<project>/secrets/<secret>[/versions/<version>]'"
+    )]
+    Malformed(String),
+
+    #[error("invalid project '{0}': expected a project ID or number")]
+    Project(String),
+
+    #[error("invalid secret '{0}': expected letters, digits, '-' and '_'")]
+    Secret(String),
+
+    #[error("invalid version '{0}': expected 'latest', a version number or an alias")]
+    Version(String),
+}
+
+/// Represents a syntactically valid Google Cloud Secret Manager reference.
+/// Syntax: `gcpsm://projects/<project>/secrets/<secret>[/versions/<version>]`
+///
+/// * `project` is the project ID (or number) owning the secret
+/// * `secret` is the secret ID
+/// * `version` is a version number or alias. Defaults to `latest`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GcpSmReference {
+    project: String,
+    secret: String,
+    version: Option<String>,
+}
+
+impl GcpSmReference {
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// The requested version, or `latest` if none was given.
+    pub fn version(&self) -> &str {
+        self.version.as_deref().unwrap_or("latest")
+    }
+}
+
+/// Project IDs are lowercase letters, digits and hyphens. Project numbers
+/// are digits, and legacy domain-scoped IDs add `.` and `:`.
+fn is_valid_project(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.' | ':'))
+}
+
+/// Secret IDs and version aliases share the same character set.
+fn is_valid_id(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 255
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl FromStr for GcpSmReference {
+    type Err = GcpSmParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .strip_prefix("gcpsm://")
+            .ok_or(GcpSmParseError::InvalidScheme)?;
+
+        let segments: Vec<&str> = name.split('/').collect();
+        let (project, secret, version) = match segments.as_slice() {
+            ["projects", project, "secrets", secret] => (*project, *secret, None),
+            ["projects", project, "secrets", secret, "versions", version] => {
+                (*project, *secret, Some(*version))
+            }
+            _ => return Err(GcpSmParseError::Malformed(name.to_string())),
+        };
+
+        if !is_valid_project(project) {
+            return Err(GcpSmParseError::Project(project.to_string()));
+        }
+        if !is_valid_id(secret) {
+            return Err(GcpSmParseError::Secret(secret.to_string()));
+        }
+        if let Some(version) = version
+            && !is_valid_id(version)
+        {
+            return Err(GcpSmParseError::Version(version.to_string()));
+        }
+
+        Ok(Self {
+            project: project.to_string(),
+            secret: secret.to_string(),
+            version: version.map(str::to_string),
+        })
+    }
+}
+
+impl From<GcpSmReference> for SecretReference {
+    fn from(r: GcpSmReference) -> Self {
+        Self::GcpSecretManager(r)
+    }
+}
+
+impl ReferenceSyntax for GcpSmReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, GcpSmParseError::InvalidScheme) {
+                    tracing::warn!("Invalid GCP Secret Manager reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for GcpSmReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::GcpSecretManager(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GcpSmReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gcpsm://projects/{}/secrets/{}",
+            self.project, self.secret
+        )?;
+        if let Some(version) = &self.version {
+            write!(f, "/versions/{}", version)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gcpsm() {
+        let r =
+            GcpSmReference::from_str("gcpsm://projects/my-project/secrets/db-password").unwrap();
+        assert_eq!(r.project(), "my-project");
+        assert_eq!(r.secret(), "db-password");
+        assert_eq!(r.version(), "latest");
+
+        let r = GcpSmReference::from_str("gcpsm://projects/123456789/secrets/api_key/versions/3")
+            .unwrap();
+        assert_eq!(r.project(), "123456789");
+        assert_eq!(r.version(), "3");
+    }
+
+    #[test]
+    fn test_parse_gcpsm_errors() {
+        assert!(matches!(
+            GcpSmReference::from_str("aws-sm://prod/db"),
+            Err(GcpSmParseError::InvalidScheme)
+        ));
+        for bad in [
+            "gcpsm://my-project/db-password",
+            "gcpsm://projects/p/secrets/s/versions",
+            "gcpsm://projects/p/secrets/s/versions/1/extra",
+            "gcpsm://projects/p/secrets",
+        ] {
+            assert!(
+                matches!(
+                    GcpSmReference::from_str(bad),
+                    Err(GcpSmParseError::Malformed(_))
+                ),
+                "'{bad}' should be rejected"
+            );
+        }
+        assert!(matches!(
+            GcpSmReference::from_str("gcpsm://projects/My_Project/secrets/s"),
+            Err(GcpSmParseError::Project(_))
+        ));
+        assert!(matches!(
+            GcpSmReference::from_str("gcpsm://projects/p/secrets/db.password"),
+            Err(GcpSmParseError::Secret(_))
+        ));
+        assert!(matches!(
+            GcpSmReference::from_str("gcpsm://projects/p/secrets/s/versions/"),
+            Err(GcpSmParseError::Version(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "gcpsm://projects/my-project/secrets/db-password",
+            "gcpsm://projects/my-project/secrets/db-password/versions/latest",
+            "gcpsm://projects/example.com:app/secrets/API_KEY/versions/7",
+        ] {
+            let r = GcpSmReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+            assert_eq!(GcpSmReference::from_str(&r.to_string()).unwrap(), r);
+        }
+    }
+}
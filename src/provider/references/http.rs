@@ -0,0 +1,124 @@
+//! Defines the HTTP webhook secret reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HttpSecretParseError {
+    #[error("reference must start with 'http-secret://'")]
+    InvalidScheme,
+
+    #[error("invalid reference: missing secret name")]
+    Empty,
+
+    #[error("invalid secret name '{0}': whitespace and control characters are not allowed")]
+    Name(String),
+}
+
+/// Represents a syntactically valid HTTP webhook secret reference.
+/// Syntax: `http-secret://<name>`
+///
+/// The name is opaque to locket, and is sent to the configured endpoint
+/// exactly as written, e.g. `http-secret://prod/db/password`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HttpSecretReference {
+    name: String,
+}
+
+impl HttpSecretReference {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl FromStr for HttpSecretReference {
+    type Err = HttpSecretParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .strip_prefix("http-secret://")
+            .ok_or(HttpSecretParseError::InvalidScheme)?;
+        if name.is_empty() {
+            return Err(HttpSecretParseError::Empty);
+        }
+        if name.chars().any(|c| c.is_whitespace() || c.is_control()) {
+            return Err(HttpSecretParseError::Name(name.to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl From<HttpSecretReference> for SecretReference {
+    fn from(r: HttpSecretReference) -> Self {
+        Self::HttpSecret(r)
+    }
+}
+
+impl ReferenceSyntax for HttpSecretReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, HttpSecretParseError::InvalidScheme) {
+                    tracing::warn!("Invalid HTTP secret reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for HttpSecretReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::HttpSecret(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HttpSecretReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http-secret://{}", self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_http_secret() {
+        let r = HttpSecretReference::from_str("http-secret://db_password").unwrap();
+        assert_eq!(r.name(), "db_password");
+
+        let r = HttpSecretReference::from_str("http-secret://prod/db/password").unwrap();
+        assert_eq!(r.name(), "prod/db/password");
+    }
+
+    #[test]
+    fn test_parse_http_secret_errors() {
+        assert!(matches!(
+            HttpSecretReference::from_str("https://example.com/secret"),
+            Err(HttpSecretParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            HttpSecretReference::from_str("http-secret://"),
+            Err(HttpSecretParseError::Empty)
+        ));
+        assert!(matches!(
+            HttpSecretReference::from_str("http-secret://my secret"),
+            Err(HttpSecretParseError::Name(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in ["http-secret://api-key", "http-secret://team/app/token"] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
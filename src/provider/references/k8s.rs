@@ -0,0 +1,207 @@
+//! Defines the Kubernetes Secret reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum K8sParseError {
+    #[error("reference must start with 'k8s://'")]
+    InvalidScheme,
+
+    #[error("invalid reference: expected 'k8s://<namespace>/<secret>/<key>'")]
+    Malformed,
+
+    #[error(
+        "invalid namespace '{0}': expected at most 63 lowercase alphanumeric characters or '-'"
+    )]
+    Namespace(String),
+
+    #[error(
+        "invalid secret name '{0}': expected at most 253 lowercase alphanumeric characters, '-' or '.'"
+    )]
+    Name(String),
+
+    #[error("invalid key '{0}': expected alphanumeric characters, '-', '_' or '.'")]
+    Key(String),
+}
+
+/// Represents a syntactically valid Kubernetes Secret reference.
+/// Syntax: `k8s://<namespace>/<secret>/<key>`
+///
+/// * `namespace` is a DNS-1123 label, e.g. `default`
+/// * `secret` is the name of the Secret, a DNS-1123 subdomain
+/// * `key` is a key within the Secret's `data`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct K8sReference {
+    namespace: String,
+    secret: String,
+    key: String,
+}
+
+impl K8sReference {
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+}
+
+/// A DNS-1123 label: lowercase alphanumerics and '-', starting and ending
+/// with an alphanumeric.
+fn is_dns_label(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 63
+        && !s.starts_with('-')
+        && !s.ends_with('-')
+        && s.bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+/// A DNS-1123 subdomain: dot separated labels, at most 253 characters.
+fn is_dns_subdomain(s: &str) -> bool {
+    s.len() <= 253 && s.split('.').all(is_dns_label)
+}
+
+fn is_secret_key(s: &str) -> bool {
+    !s.is_empty()
+        && s.len() <= 253
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+}
+
+impl FromStr for K8sReference {
+    type Err = K8sParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s
+            .strip_prefix("k8s://")
+            .ok_or(K8sParseError::InvalidScheme)?;
+
+        let mut parts = path.split('/');
+        let (Some(namespace), Some(secret), Some(key), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(K8sParseError::Malformed);
+        };
+
+        if !is_dns_label(namespace) {
+            return Err(K8sParseError::Namespace(namespace.to_string()));
+        }
+        if !is_dns_subdomain(secret) {
+            return Err(K8sParseError::Name(secret.to_string()));
+        }
+        if !is_secret_key(key) {
+            return Err(K8sParseError::Key(key.to_string()));
+        }
+
+        Ok(Self {
+            namespace: namespace.to_string(),
+            secret: secret.to_string(),
+            key: key.to_string(),
+        })
+    }
+}
+
+impl From<K8sReference> for SecretReference {
+    fn from(r: K8sReference) -> Self {
+        Self::K8s(r)
+    }
+}
+
+impl ReferenceSyntax for K8sReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, K8sParseError::InvalidScheme) {
+                    tracing::warn!("Invalid Kubernetes reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for K8sReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::K8s(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for K8sReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "k8s://{}/{}/{}", self.namespace, self.secret, self.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_k8s() {
+        let r = K8sReference::from_str("k8s://default/db-credentials/password").unwrap();
+        assert_eq!(r.namespace(), "default");
+        assert_eq!(r.secret(), "db-credentials");
+        assert_eq!(r.key(), "password");
+
+        let r = K8sReference::from_str("k8s://kube-system/tls.example.com/tls.key").unwrap();
+        assert_eq!(r.secret(), "tls.example.com");
+        assert_eq!(r.key(), "tls.key");
+
+        let r = K8sReference::from_str("k8s://apps/app/API_KEY").unwrap();
+        assert_eq!(r.key(), "API_KEY");
+    }
+
+    #[test]
+    fn test_parse_k8s_errors() {
+        assert!(matches!(
+            K8sReference::from_str("kube://default/a/b"),
+            Err(K8sParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://default/secret"),
+            Err(K8sParseError::Malformed)
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://default/secret/key/extra"),
+            Err(K8sParseError::Malformed)
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://Default/secret/key"),
+            Err(K8sParseError::Namespace(_))
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://default/my_secret/key"),
+            Err(K8sParseError::Name(_))
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://default/secret..name/key"),
+            Err(K8sParseError::Name(_))
+        ));
+        assert!(matches!(
+            K8sReference::from_str("k8s://default/secret/my key"),
+            Err(K8sParseError::Key(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "k8s://default/db-credentials/password",
+            "k8s://kube-system/tls.example.com/tls.crt",
+        ] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
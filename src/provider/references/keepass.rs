@@ -0,0 +1,257 @@
+//! Defines the KeePass secret reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Escapes everything outside the RFC 3986 unreserved set so that every
+/// component survives a display/parse round trip.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Marks a field segment as an attachment name rather than a string field.
+const ATTACHMENT_PREFIX: &str = "attachment:";
+
+#[derive(Debug, Error)]
+pub enum KeePassParseError {
+    #[error("reference must start with 'kp://'")]
+    InvalidScheme,
+
+    #[error("invalid path segments: expected at least 2 (entry/field), got {0}")]
+    Segments(usize),
+
+    #[error("path segments cannot be empty")]
+    EmptySegment,
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+/// The part of an entry a reference resolves to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeePassField {
+    Title,
+    UserName,
+    Password,
+    Url,
+    Notes,
+    /// A custom string field, matched by its exact name
+    Custom(String),
+    /// An attachment, matched by its file name
+    Attachment(String),
+}
+
+impl KeePassField {
+    fn parse(raw: &str) -> Result<Self, KeePassParseError> {
+        if let Some(name) = raw.strip_prefix(ATTACHMENT_PREFIX) {
+            let name = percent_decode_str(name).decode_utf8()?;
+            if name.is_empty() {
+                return Err(KeePassParseError::EmptySegment);
+            }
+            return Ok(Self::Attachment(name.into_owned()));
+        }
+        let name = percent_decode_str(raw).decode_utf8()?;
+        Ok(match name.to_ascii_lowercase().as_str() {
+            "title" => Self::Title,
+            "username" => Self::UserName,
+            "password" => Self::Password,
+            "url" => Self::Url,
+            "notes" => Self::Notes,
+            _ => Self::Custom(name.into_owned()),
+        })
+    }
+}
+
+impl fmt::Display for KeePassField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Title => f.write_str("title"),
+            Self::UserName => f.write_str("username"),
+            Self::Password => f.write_str("password"),
+            Self::Url => f.write_str("url"),
+            Self::Notes => f.write_str("notes"),
+            Self::Custom(name) => write!(f, "{}", utf8_percent_encode(name, COMPONENT)),
+            Self::Attachment(name) => write!(
+                f,
+                "{}{}",
+                ATTACHMENT_PREFIX,
+                utf8_percent_encode(name, COMPONENT)
+            ),
+        }
+    }
+}
+
+/// Represents a syntactically valid KeePass reference.
+/// Syntax: `kp://[<group>/...]<entry-title>/<field>`
+///
+/// * `group` segments walk down from the root group of the database
+/// * `entry-title` is the title of the entry within the last group
+/// * `field` is `title`, `username`, `password`, `url` or `notes`
+///   (case-insensitive), the name of a custom string field, or
+///   `attachment:<file-name>`
+///
+/// Characters which are not allowed in a URL path (such as spaces and `/`)
+/// are percent-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeePassReference {
+    groups: Vec<String>,
+    entry: String,
+    field: KeePassField,
+}
+
+impl KeePassReference {
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    pub fn field(&self) -> &KeePassField {
+        &self.field
+    }
+}
+
+impl FromStr for KeePassReference {
+    type Err = KeePassParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = s
+            .strip_prefix("kp://")
+            .ok_or(KeePassParseError::InvalidScheme)?;
+
+        let mut segments: Vec<&str> = path.split('/').collect();
+        if segments.len() < 2 {
+            return Err(KeePassParseError::Segments(segments.len()));
+        }
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(KeePassParseError::EmptySegment);
+        }
+
+        let field = KeePassField::parse(segments.pop().unwrap_or_default())?;
+        let entry = percent_decode_str(segments.pop().unwrap_or_default())
+            .decode_utf8()?
+            .into_owned();
+        let groups = segments
+            .into_iter()
+            .map(|g| Ok(percent_decode_str(g).decode_utf8()?.into_owned()))
+            .collect::<Result<_, KeePassParseError>>()?;
+
+        Ok(Self {
+            groups,
+            entry,
+            field,
+        })
+    }
+}
+
+impl From<KeePassReference> for SecretReference {
+    fn from(r: KeePassReference) -> Self {
+        Self::KeePass(r)
+    }
+}
+
+impl ReferenceSyntax for KeePassReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, KeePassParseError::InvalidScheme) {
+                    tracing::warn!("Invalid KeePass reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for KeePassReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::KeePass(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeePassReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("kp://")?;
+        for group in &self.groups {
+            write!(f, "{}/", utf8_percent_encode(group, COMPONENT))?;
+        }
+        write!(
+            f,
+            "{}/{}",
+            utf8_percent_encode(&self.entry, COMPONENT),
+            self.field
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keepass() {
+        let r = KeePassReference::from_str("kp://Work/Servers/db01/Password").unwrap();
+        assert_eq!(r.groups(), ["Work", "Servers"]);
+        assert_eq!(r.entry(), "db01");
+        assert_eq!(r.field(), &KeePassField::Password);
+
+        let r = KeePassReference::from_str("kp://GitHub/username").unwrap();
+        assert!(r.groups().is_empty());
+        assert_eq!(r.entry(), "GitHub");
+        assert_eq!(r.field(), &KeePassField::UserName);
+
+        let r = KeePassReference::from_str("kp://My%20Group/API/Deploy%20Key").unwrap();
+        assert_eq!(r.groups(), ["My Group"]);
+        assert_eq!(r.field(), &KeePassField::Custom("Deploy Key".into()));
+
+        let r = KeePassReference::from_str("kp://Infra/ssh/attachment:id_ed25519").unwrap();
+        assert_eq!(r.field(), &KeePassField::Attachment("id_ed25519".into()));
+
+        // An escaped prefix is a custom field which happens to contain a colon
+        let r = KeePassReference::from_str("kp://Infra/ssh/attachment%3Ax").unwrap();
+        assert_eq!(r.field(), &KeePassField::Custom("attachment:x".into()));
+    }
+
+    #[test]
+    fn test_parse_keepass_errors() {
+        assert!(matches!(
+            KeePassReference::from_str("op://a/b/c"),
+            Err(KeePassParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            KeePassReference::from_str("kp://entry"),
+            Err(KeePassParseError::Segments(1))
+        ));
+        assert!(matches!(
+            KeePassReference::from_str("kp://group//password"),
+            Err(KeePassParseError::EmptySegment)
+        ));
+        assert!(matches!(
+            KeePassReference::from_str("kp://entry/attachment:"),
+            Err(KeePassParseError::EmptySegment)
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "kp://Work/Servers/db01/password",
+            "kp://GitHub/notes",
+            "kp://My%20Group/a%2Fb/Deploy%20Key",
+            "kp://Infra/ssh/attachment:id%20rsa",
+            "kp://Infra/ssh/attachment%3Ax",
+        ] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Defines the local file, credential and environment variable reference types.
+//!
+//! These are not tied to any provider feature, and are resolved by locket
+//! itself from its own filesystem and process environment.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Escapes only what would be ambiguous in a file URL path.
+const PATH: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%').add(b'?');
+
+#[derive(Debug, Error)]
+pub enum FileParseError {
+    #[error("reference must start with 'file://'")]
+    InvalidScheme,
+
+    #[error("path '{0}' must be absolute: expected 'file:///<path>'")]
+    Relative(String),
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+#[derive(Debug, Error)]
+pub enum CredsParseError {
+    #[error("reference must start with 'creds://'")]
+    InvalidScheme,
+
+    #[error("invalid credential name '{0}': expected a single file name")]
+    Name(String),
+}
+
+#[derive(Debug, Error)]
+pub enum EnvParseError {
+    #[error("reference must start with 'env://'")]
+    InvalidScheme,
+
+    #[error("invalid environment variable name '{0}'")]
+    Name(String),
+}
+
+/// Represents a syntactically valid local file reference.
+/// Syntax: `file:///<absolute-path>`
+///
+/// The file is read from locket's own filesystem, e.g. a Docker or
+/// Kubernetes mounted secret. Characters which are not allowed in a URL
+/// path (such as spaces) are percent-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FileReference {
+    path: PathBuf,
+}
+
+impl FileReference {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl FromStr for FileReference {
+    type Err = FileParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s
+            .strip_prefix("file://")
+            .ok_or(FileParseError::InvalidScheme)?;
+        let path = percent_decode_str(raw).decode_utf8()?;
+        let path = PathBuf::from(path.as_ref());
+        if !path.is_absolute() {
+            return Err(FileParseError::Relative(raw.to_string()));
+        }
+        Ok(Self { path })
+    }
+}
+
+impl From<FileReference> for SecretReference {
+    fn from(r: FileReference) -> Self {
+        Self::File(r)
+    }
+}
+
+impl ReferenceSyntax for FileReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, FileParseError::InvalidScheme) {
+                    tracing::warn!("Invalid file reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for FileReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::File(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FileReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "file://{}",
+            utf8_percent_encode(&self.path.to_string_lossy(), PATH)
+        )
+    }
+}
+
+/// Represents a syntactically valid credential reference.
+/// Syntax: `creds://<name>`
+///
+/// The name is a file in a credentials directory: `$CREDENTIALS_DIRECTORY`
+/// for systemd `LoadCredential=` units, or `/run/secrets` for Docker and
+/// Swarm secrets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CredsReference {
+    name: String,
+}
+
+impl CredsReference {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A plain file name, so a reference can never leave its directory.
+fn is_valid_file_name(s: &str) -> bool {
+    !s.is_empty() && s != "." && s != ".." && !s.chars().any(|c| c == '/' || c.is_control())
+}
+
+impl FromStr for CredsReference {
+    type Err = CredsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .strip_prefix("creds://")
+            .ok_or(CredsParseError::InvalidScheme)?;
+        if !is_valid_file_name(name) {
+            return Err(CredsParseError::Name(name.to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl From<CredsReference> for SecretReference {
+    fn from(r: CredsReference) -> Self {
+        Self::Creds(r)
+    }
+}
+
+impl ReferenceSyntax for CredsReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, CredsParseError::InvalidScheme) {
+                    tracing::warn!("Invalid credential reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for CredsReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Creds(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for CredsReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "creds://{}", self.name)
+    }
+}
+
+/// Represents a syntactically valid environment variable reference.
+/// Syntax: `env://<VAR_NAME>`
+///
+/// The variable is read from the locket process environment, e.g. a value
+/// injected by a CI system.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnvReference {
+    name: String,
+}
+
+impl EnvReference {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn is_valid_name(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl FromStr for EnvReference {
+    type Err = EnvParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .strip_prefix("env://")
+            .ok_or(EnvParseError::InvalidScheme)?;
+        if !is_valid_name(name) {
+            return Err(EnvParseError::Name(name.to_string()));
+        }
+        Ok(Self {
+            name: name.to_string(),
+        })
+    }
+}
+
+impl From<EnvReference> for SecretReference {
+    fn from(r: EnvReference) -> Self {
+        Self::Env(r)
+    }
+}
+
+impl ReferenceSyntax for EnvReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, EnvParseError::InvalidScheme) {
+                    tracing::warn!("Invalid environment reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for EnvReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Env(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EnvReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "env://{}", self.name)
+    }
+}
+
+/// Parses any of the local reference syntaxes.
+pub fn parse_local_reference(raw: &str) -> Option<SecretReference> {
+    FileReference::try_parse(raw)
+        .map(SecretReference::from)
+        .or_else(|| CredsReference::try_parse(raw).map(SecretReference::from))
+        .or_else(|| EnvReference::try_parse(raw).map(SecretReference::from))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_local() {
+        let r = FileReference::from_str("file:///run/secrets/db_password").unwrap();
+        assert_eq!(r.path(), Path::new("/run/secrets/db_password"));
+
+        let r = FileReference::from_str("file:///my%20dir/token").unwrap();
+        assert_eq!(r.path(), Path::new("/my dir/token"));
+
+        let r = EnvReference::from_str("env://CI_DEPLOY_TOKEN").unwrap();
+        assert_eq!(r.name(), "CI_DEPLOY_TOKEN");
+
+        assert!(matches!(
+            parse_local_reference("file:///a"),
+            Some(SecretReference::File(_))
+        ));
+        assert!(matches!(
+            parse_local_reference("env://_A1"),
+            Some(SecretReference::Env(_))
+        ));
+
+        let r = CredsReference::from_str("creds://db_password").unwrap();
+        assert_eq!(r.name(), "db_password");
+        assert!(matches!(
+            parse_local_reference("creds://tls.key"),
+            Some(SecretReference::Creds(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_local_errors() {
+        assert!(matches!(
+            FileReference::from_str("env://A"),
+            Err(FileParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            FileReference::from_str("file://run/secrets/a"),
+            Err(FileParseError::Relative(_))
+        ));
+        assert!(matches!(
+            EnvReference::from_str("file:///a"),
+            Err(EnvParseError::InvalidScheme)
+        ));
+        for bad in ["env://", "env://1VAR", "env://MY-VAR", "env://A/B"] {
+            assert!(
+                matches!(EnvReference::from_str(bad), Err(EnvParseError::Name(_))),
+                "'{bad}' should be rejected"
+            );
+        }
+        assert!(matches!(
+            CredsReference::from_str("file:///a"),
+            Err(CredsParseError::InvalidScheme)
+        ));
+        for bad in ["creds://", "creds://..", "creds://a/b", "creds://../a"] {
+            assert!(
+                matches!(CredsReference::from_str(bad), Err(CredsParseError::Name(_))),
+                "'{bad}' should be rejected"
+            );
+        }
+        assert!(parse_local_reference("op://vault/item/field").is_none());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "file:///run/secrets/db",
+            "file:///my%20dir/a%23b",
+            "env://HOME",
+            "creds://db_password",
+        ] {
+            let r = SecretReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+        }
+    }
+}
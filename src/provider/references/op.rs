@@ -166,4 +166,15 @@ mod tests {
         assert_eq!(r.item, "My Item");
         assert_eq!(r.field, "field");
     }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "op://vault/item/field",
+            "op://My%20Vault/item/section/field?attribute=otp",
+        ] {
+            let r = SecretReference::from(OpReference::from_str(raw).unwrap());
+            assert_eq!(r.to_string(), raw);
+        }
+    }
 }
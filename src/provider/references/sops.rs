@@ -0,0 +1,189 @@
+//! Defines the SOPS encrypted file reference type and its parsing logic.
+use super::{Extract, ReferenceSyntax, SecretReference};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Escapes only what would be ambiguous in the file path.
+const PATH: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'%');
+
+/// Key segments additionally escape the `.` separator.
+const SEGMENT: &AsciiSet = &PATH.add(b'.');
+
+#[derive(Debug, Error)]
+pub enum SopsParseError {
+    #[error("reference must start with 'sops://'")]
+    InvalidScheme,
+
+    #[error("missing file path")]
+    MissingPath,
+
+    #[error("missing key path: expected 'sops://<file>#<key.path>'")]
+    MissingKey,
+
+    #[error("key path '{0}' contains an empty segment")]
+    EmptySegment(String),
+
+    #[error("utf8 decode error: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+}
+
+/// Represents a syntactically valid SOPS reference.
+/// Syntax: `sops://<path-to-file>#<dotted.key.path>`
+///
+/// * `path-to-file` is an absolute (`sops:///etc/app/secrets.yaml`) or
+///   relative (`sops://secrets.yaml`) path to a SOPS encrypted file
+/// * `dotted.key.path` walks the decrypted document, one `.` separated
+///   segment per nesting level. Numeric segments index into lists, and a
+///   literal `.` inside a key is written as `%2E`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SopsReference {
+    path: PathBuf,
+    key: Vec<String>,
+}
+
+impl SopsReference {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn key(&self) -> &[String] {
+        &self.key
+    }
+}
+
+impl FromStr for SopsReference {
+    type Err = SopsParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw = s
+            .strip_prefix("sops://")
+            .ok_or(SopsParseError::InvalidScheme)?;
+        let (path, key) = raw.split_once('#').ok_or(SopsParseError::MissingKey)?;
+
+        let path = percent_decode_str(path).decode_utf8()?;
+        if path.is_empty() {
+            return Err(SopsParseError::MissingPath);
+        }
+        if key.is_empty() {
+            return Err(SopsParseError::MissingKey);
+        }
+
+        let key = key
+            .split('.')
+            .map(|segment| {
+                if segment.is_empty() {
+                    return Err(SopsParseError::EmptySegment(key.to_string()));
+                }
+                Ok(percent_decode_str(segment).decode_utf8()?.into_owned())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            path: PathBuf::from(path.as_ref()),
+            key,
+        })
+    }
+}
+
+impl From<SopsReference> for SecretReference {
+    fn from(r: SopsReference) -> Self {
+        Self::Sops(r)
+    }
+}
+
+impl ReferenceSyntax for SopsReference {
+    fn try_parse(raw: &str) -> Option<Self> {
+        Self::from_str(raw)
+            .inspect_err(|e| {
+                if !matches!(e, SopsParseError::InvalidScheme) {
+                    tracing::warn!("Invalid SOPS reference '{}': {}", raw, e);
+                }
+            })
+            .ok()
+    }
+}
+
+impl Extract for SopsReference {
+    fn extract(r: &SecretReference) -> Option<&Self> {
+        #[allow(unreachable_patterns)]
+        match r {
+            SecretReference::Sops(inner) => Some(inner),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SopsReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "sops://{}#",
+            utf8_percent_encode(&self.path.to_string_lossy(), PATH)
+        )?;
+        let mut sep = "";
+        for segment in &self.key {
+            write!(f, "{}{}", sep, utf8_percent_encode(segment, SEGMENT))?;
+            sep = ".";
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sops() {
+        let r = SopsReference::from_str("sops:///etc/app/secrets.yaml#db.password").unwrap();
+        assert_eq!(r.path(), Path::new("/etc/app/secrets.yaml"));
+        assert_eq!(r.key(), ["db", "password"]);
+
+        let r = SopsReference::from_str("sops://secrets/app.env#API_KEY").unwrap();
+        assert_eq!(r.path(), Path::new("secrets/app.env"));
+        assert_eq!(r.key(), ["API_KEY"]);
+
+        let r = SopsReference::from_str("sops://app.yaml#hosts.0.example%2Ecom").unwrap();
+        assert_eq!(r.key(), ["hosts", "0", "example.com"]);
+    }
+
+    #[test]
+    fn test_parse_sops_errors() {
+        assert!(matches!(
+            SopsReference::from_str("akv://vault/secret"),
+            Err(SopsParseError::InvalidScheme)
+        ));
+        assert!(matches!(
+            SopsReference::from_str("sops://#db.password"),
+            Err(SopsParseError::MissingPath)
+        ));
+        assert!(matches!(
+            SopsReference::from_str("sops:///etc/app/secrets.yaml"),
+            Err(SopsParseError::MissingKey)
+        ));
+        assert!(matches!(
+            SopsReference::from_str("sops:///etc/app/secrets.yaml#"),
+            Err(SopsParseError::MissingKey)
+        ));
+        assert!(matches!(
+            SopsReference::from_str("sops://app.yaml#db..password"),
+            Err(SopsParseError::EmptySegment(_))
+        ));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for raw in [
+            "sops:///etc/app/secrets.yaml#db.password",
+            "sops://app.env#API_KEY",
+            "sops:///my%20dir/app.yaml#hosts.0.example%2Ecom",
+        ] {
+            let r = SopsReference::from_str(raw).unwrap();
+            assert_eq!(r.to_string(), raw);
+            assert_eq!(SopsReference::from_str(&r.to_string()).unwrap(), r);
+        }
+    }
+}
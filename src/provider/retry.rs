@@ -0,0 +1,298 @@
+//! Retry policy for transient provider failures.
+//!
+//! Failed batches are retried with exponential backoff. Each delay is
+//! `base * 2^(attempt - 1)`, capped at the maximum delay, with a random
+//! portion (the jitter) taken off so that many clients recovering from the
+//! same outage don't retry in lockstep. A `Retry-After` delay sent by the
+//! backend takes precedence over the computed one. Retries stop once the
+//! attempts are used up, or when the next one could not start before the
+//! deadline.
+
+use super::{ProviderDuration, ProviderError};
+use clap::Args;
+use locket_derive::LayeredConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::num::NonZeroU32;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RetryPolicy {
+    pub max_attempts: NonZeroU32,
+    pub base_delay: ProviderDuration,
+    pub max_delay: ProviderDuration,
+    pub jitter: JitterPercent,
+    pub deadline: ProviderDuration,
+}
+
+impl RetryPolicy {
+    /// A policy which makes a single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: NonZeroU32::MIN,
+            ..Self::default()
+        }
+    }
+
+    /// The delay before the attempt following `attempt` (counted from 1),
+    /// or `None` if the error should not be retried.
+    pub fn next_delay(
+        &self,
+        attempt: u32,
+        elapsed: Duration,
+        err: &ProviderError,
+    ) -> Option<Duration> {
+        if !err.is_transient() || attempt >= self.max_attempts.get() {
+            return None;
+        }
+
+        let delay = match err {
+            ProviderError::RateLimit {
+                retry_after: Some(after),
+            } => *after,
+            _ => self.jittered(self.backoff(attempt), random_fraction()),
+        };
+
+        (elapsed + delay <= self.deadline.0).then_some(delay)
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay
+            .0
+            .saturating_mul(factor)
+            .min(self.max_delay.0)
+    }
+
+    /// Takes a random portion of up to `jitter` percent off the delay.
+    fn jittered(&self, delay: Duration, fraction: f64) -> Duration {
+        let jitter = f64::from(self.jitter.0) / 100.0;
+        delay.mul_f64(1.0 - jitter * fraction)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: NonZeroU32::new(3).unwrap(),
+            base_delay: ProviderDuration(Duration::from_millis(250)),
+            max_delay: ProviderDuration(Duration::from_secs(10)),
+            jitter: JitterPercent(50),
+            deadline: ProviderDuration(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// A uniformly distributed value in `[0, 1)`.
+///
+/// Jitter only needs to decorrelate clients, so the randomly keyed std
+/// hasher is a good enough source.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().hash_one(std::time::Instant::now()) >> 11;
+    bits as f64 / (1u64 << 53) as f64
+}
+
+#[derive(
+    Args, Debug, Clone, LayeredConfig, Deserialize, Serialize, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "kebab-case")]
+pub struct RetryArgs {
+    /// Maximum attempts for a batch of secrets, including the first one,
+    /// when a provider is unreachable, rate limited or unavailable
+    #[arg(long, env = "PROVIDER_RETRY_ATTEMPTS")]
+    #[locket(default = RetryPolicy::default().max_attempts)]
+    pub provider_retry_attempts: Option<NonZeroU32>,
+
+    /// Delay before the first retry, doubled for every further retry
+    ///
+    /// Supports human-friendly durations like 500ms or 2s. Plain numbers are seconds.
+    #[arg(long, env = "PROVIDER_RETRY_BASE_DELAY")]
+    #[locket(default = RetryPolicy::default().base_delay)]
+    pub provider_retry_base_delay: Option<ProviderDuration>,
+
+    /// Upper bound for the delay between two retries, at least the base delay
+    #[arg(long, env = "PROVIDER_RETRY_MAX_DELAY")]
+    #[locket(default = RetryPolicy::default().max_delay)]
+    pub provider_retry_max_delay: Option<ProviderDuration>,
+
+    /// Percentage of each delay which is randomized, so that clients don't retry in lockstep
+    #[arg(long, env = "PROVIDER_RETRY_JITTER")]
+    #[locket(default = RetryPolicy::default().jitter)]
+    pub provider_retry_jitter: Option<JitterPercent>,
+
+    /// Total time after which a failing batch is no longer retried
+    #[arg(long, env = "PROVIDER_RETRY_DEADLINE")]
+    #[locket(default = RetryPolicy::default().deadline)]
+    pub provider_retry_deadline: Option<ProviderDuration>,
+}
+
+impl TryFrom<RetryArgs> for RetryPolicy {
+    type Error = crate::error::LocketError;
+
+    fn try_from(args: RetryArgs) -> Result<Self, Self::Error> {
+        use crate::config::ApplyDefaults;
+        let args = args.apply_defaults();
+        let defaults = Self::default();
+        let policy = Self {
+            max_attempts: args
+                .provider_retry_attempts
+                .unwrap_or(defaults.max_attempts),
+            base_delay: args
+                .provider_retry_base_delay
+                .unwrap_or(defaults.base_delay),
+            max_delay: args.provider_retry_max_delay.unwrap_or(defaults.max_delay),
+            jitter: args.provider_retry_jitter.unwrap_or(defaults.jitter),
+            deadline: args.provider_retry_deadline.unwrap_or(defaults.deadline),
+        };
+        if policy.max_delay < policy.base_delay {
+            return Err(crate::config::ConfigError::Validation(format!(
+                "--provider-retry-max-delay ({}) must not be shorter than --provider-retry-base-delay ({})",
+                policy.max_delay, policy.base_delay
+            ))
+            .into());
+        }
+        Ok(policy)
+    }
+}
+
+/// A percentage between 0 and 100.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "u8")]
+pub struct JitterPercent(u8);
+
+impl TryFrom<u8> for JitterPercent {
+    type Error = String;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        if val > 100 {
+            return Err("Jitter must be a percentage between 0 and 100".to_string());
+        }
+        Ok(Self(val))
+    }
+}
+
+impl FromStr for JitterPercent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let val: u8 = s
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| "not a number")?;
+        val.try_into()
+    }
+}
+
+impl std::fmt::Display for JitterPercent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: NonZeroU32::new(5).unwrap(),
+            base_delay: ProviderDuration(Duration::from_millis(100)),
+            max_delay: ProviderDuration(Duration::from_millis(300)),
+            jitter: JitterPercent(0),
+            deadline: ProviderDuration(Duration::from_secs(1)),
+        }
+    }
+
+    fn network() -> ProviderError {
+        ProviderError::Network("connection reset".into())
+    }
+
+    #[test]
+    fn test_backoff_is_exponential_and_capped() {
+        let p = policy();
+        let delays: Vec<_> = (1..5)
+            .map(|attempt| p.next_delay(attempt, Duration::ZERO, &network()))
+            .collect();
+        assert_eq!(
+            delays,
+            [100, 200, 300, 300].map(|ms| Some(Duration::from_millis(ms)))
+        );
+        assert_eq!(p.next_delay(5, Duration::ZERO, &network()), None);
+    }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        let err = ProviderError::Unauthorized("denied".into());
+        assert_eq!(policy().next_delay(1, Duration::ZERO, &err), None);
+        let err = ProviderError::Unavailable("503".into());
+        assert!(policy().next_delay(1, Duration::ZERO, &err).is_some());
+    }
+
+    #[test]
+    fn test_retry_after_and_deadline() {
+        let p = policy();
+        let err = ProviderError::RateLimit {
+            retry_after: Some(Duration::from_millis(700)),
+        };
+        assert_eq!(
+            p.next_delay(1, Duration::ZERO, &err),
+            Some(Duration::from_millis(700))
+        );
+        // Waiting would overshoot the deadline.
+        assert_eq!(p.next_delay(1, Duration::from_millis(500), &err), None);
+    }
+
+    #[test]
+    fn test_jitter_shortens_delay() {
+        let p = RetryPolicy {
+            jitter: JitterPercent(50),
+            ..policy()
+        };
+        let delay = Duration::from_millis(200);
+        assert_eq!(p.jittered(delay, 0.0), delay);
+        assert_eq!(p.jittered(delay, 0.5), Duration::from_millis(150));
+        for _ in 0..100 {
+            let fraction = random_fraction();
+            assert!((0.0..1.0).contains(&fraction));
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        // Plain numbers are seconds, like every other duration option.
+        assert_eq!(
+            "3".parse::<ProviderDuration>().unwrap().0,
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            "1500ms".parse::<ProviderDuration>().unwrap().0,
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            "2s".parse::<ProviderDuration>().unwrap().0,
+            Duration::from_secs(2)
+        );
+        assert_eq!("25%".parse::<JitterPercent>().unwrap(), JitterPercent(25));
+        assert!("101".parse::<JitterPercent>().is_err());
+    }
+
+    #[test]
+    fn test_max_delay_must_not_be_shorter_than_base_delay() {
+        let args = |base: &str, max: &str| RetryArgs {
+            provider_retry_base_delay: Some(base.parse().unwrap()),
+            provider_retry_max_delay: Some(max.parse().unwrap()),
+            ..Default::default()
+        };
+        let policy = RetryPolicy::try_from(args("2s", "2s")).unwrap();
+        assert_eq!(policy.max_delay, policy.base_delay);
+        assert!(RetryPolicy::try_from(args("2s", "500ms")).is_err());
+        // The default max delay applies when only the base delay is given.
+        let args = RetryArgs {
+            provider_retry_base_delay: Some("1m".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(RetryPolicy::try_from(args).is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Coalesces concurrent fetches of the same secrets.
+//!
+//! The volume plugin shares one provider across volumes, and `exec` runs
+//! its file and env managers against the same provider, so overlapping
+//! batches are often requested at the same time. Each reference which is
+//! already being fetched joins that in-flight request instead of reaching
+//! the backend again, and only the remaining references are fetched.
+//!
+//! Every waiter receives the outcome of the request it joined. An error is
+//! only shared with waiters which asked for every reference of the failed
+//! request; others fetch their own references of it once more, so a batch
+//! never fails because of a reference it did not ask for. A flight is
+//! forgotten as soon as it completes, so later batches always fetch fresh
+//! values.
+
+use super::{ProviderError, ReferenceParser, SecretReference, SecretsProvider};
+use async_trait::async_trait;
+use futures::future::{BoxFuture, FutureExt, Shared, join_all};
+use secrecy::SecretString;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+type FlightResult = Arc<Result<HashMap<SecretReference, SecretString>, ProviderError>>;
+type Flight = Shared<BoxFuture<'static, FlightResult>>;
+
+type InFlight = Arc<Mutex<HashMap<SecretReference, Entry>>>;
+
+/// A flight, as registered for each of its references.
+#[derive(Clone)]
+struct Entry {
+    id: u64,
+    references: Arc<[SecretReference]>,
+    flight: Flight,
+}
+
+/// A flight a batch waits on.
+struct Joined {
+    references: Arc<[SecretReference]>,
+    flight: Flight,
+    /// Whether the flight also fetches references the batch did not ask for
+    foreign: bool,
+}
+
+pub struct SingleFlightProvider {
+    inner: Arc<dyn SecretsProvider>,
+    in_flight: InFlight,
+    next_id: AtomicU64,
+}
+
+impl SingleFlightProvider {
+    pub fn new(inner: Arc<dyn SecretsProvider>) -> Self {
+        Self {
+            inner,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Joins the flights already fetching any of the references, and starts
+    /// a single new flight for the rest.
+    fn join_or_start(&self, references: &[SecretReference]) -> Vec<Joined> {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut misses = Vec::new();
+        for reference in references {
+            match in_flight.get(reference) {
+                Some(entry) => {
+                    if !entries.iter().any(|joined| joined.id == entry.id) {
+                        entries.push(entry.clone());
+                    }
+                }
+                None if !misses.contains(reference) => misses.push(reference.clone()),
+                None => {}
+            }
+        }
+
+        if !misses.is_empty() {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            let references: Arc<[SecretReference]> = misses.into();
+            let entry = Entry {
+                id,
+                references: references.clone(),
+                flight: self.start(id, references),
+            };
+            for reference in entry.references.iter() {
+                in_flight.insert(reference.clone(), entry.clone());
+            }
+            entries.push(entry);
+        }
+
+        let requested: HashSet<&SecretReference> = references.iter().collect();
+        entries
+            .into_iter()
+            .map(|entry| Joined {
+                foreign: entry.references.iter().any(|r| !requested.contains(r)),
+                references: entry.references,
+                flight: entry.flight,
+            })
+            .collect()
+    }
+
+    fn start(&self, id: u64, references: Arc<[SecretReference]>) -> Flight {
+        let inner = self.inner.clone();
+        let in_flight = self.in_flight.clone();
+        async move {
+            let result = inner.fetch_map(&references).await;
+            let mut in_flight = in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            for reference in references.iter() {
+                if in_flight.get(reference).is_some_and(|entry| entry.id == id) {
+                    in_flight.remove(reference);
+                }
+            }
+            Arc::new(result)
+        }
+        .boxed()
+        .shared()
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for SingleFlightProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let joined = self.join_or_start(references);
+        let results = join_all(joined.iter().map(|j| j.flight.clone())).await;
+        let mut merged = HashMap::with_capacity(references.len());
+        for (joined, result) in joined.into_iter().zip(results) {
+            match result.as_ref() {
+                Ok(map) => merged.extend(
+                    references
+                        .iter()
+                        .filter_map(|r| map.get(r).map(|v| (r.clone(), v.clone()))),
+                ),
+                // The failure may be caused by references of another batch,
+                // so fetch our own once more instead of sharing it.
+                Err(e) if joined.foreign => {
+                    tracing::debug!("Shared fetch failed ({}), fetching own references", e);
+                    let requested: HashSet<&SecretReference> = references.iter().collect();
+                    let own: Vec<SecretReference> = joined
+                        .references
+                        .iter()
+                        .filter(|r| requested.contains(r))
+                        .cloned()
+                        .collect();
+                    merged.extend(self.inner.fetch_map(&own).await?);
+                }
+                Err(e) => return Err(e.duplicate()),
+            }
+        }
+        Ok(merged)
+    }
+
+    async fn expired(&self) {
+        self.inner.expired().await
+    }
+
+    async fn holds_leases(&self) -> bool {
+        self.inner.holds_leases().await
+    }
+
+    async fn shutdown(&self) {
+        self.inner.shutdown().await
+    }
+}
+
+impl ReferenceParser for SingleFlightProvider {
+    fn parse(&self, raw: &str) -> Option<SecretReference> {
+        self.inner.parse(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secrecy::ExposeSecret;
+    use std::time::Duration;
+
+    /// Slow backend which records every batch it is asked to resolve.
+    struct SlowBackend {
+        fail: bool,
+        /// Fails every batch containing this reference
+        poison: Option<SecretReference>,
+        calls: Mutex<Vec<Vec<SecretReference>>>,
+    }
+
+    impl SlowBackend {
+        fn new(fail: bool) -> Arc<Self> {
+            Arc::new(Self {
+                fail,
+                poison: None,
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn poisoned(reference: SecretReference) -> Arc<Self> {
+            Arc::new(Self {
+                fail: false,
+                poison: Some(reference),
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn calls(&self) -> Vec<Vec<SecretReference>> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl ReferenceParser for SlowBackend {
+        fn parse(&self, raw: &str) -> Option<SecretReference> {
+            Some(SecretReference::Mock(raw.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl SecretsProvider for SlowBackend {
+        async fn fetch_map(
+            &self,
+            references: &[SecretReference],
+        ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+            self.calls.lock().unwrap().push(references.to_vec());
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            if self.fail {
+                return Err(ProviderError::Network("connection reset".into()));
+            }
+            if let Some(poison) = self.poison.as_ref().filter(|p| references.contains(p)) {
+                return Err(ProviderError::NotFound(poison.to_string()));
+            }
+            Ok(references
+                .iter()
+                .map(|r| (r.clone(), SecretString::from(format!("{r}-value"))))
+                .collect())
+        }
+    }
+
+    fn mock(raw: &str) -> SecretReference {
+        SecretReference::Mock(raw.to_string())
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn coalesces_overlapping_batches() {
+        let backend = SlowBackend::new(false);
+        let provider = SingleFlightProvider::new(backend.clone());
+
+        let first = [mock("a"), mock("b")];
+        let second = [mock("b"), mock("c")];
+        let (first, second) = tokio::join!(provider.fetch_map(&first), provider.fetch_map(&second));
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(
+            backend.calls(),
+            vec![vec![mock("a"), mock("b")], vec![mock("c")]]
+        );
+        assert_eq!(first.len(), 2);
+        assert_eq!(second.len(), 2);
+        assert_eq!(second[&mock("b")].expose_secret(), "b-value");
+        assert_eq!(second[&mock("c")].expose_secret(), "c-value");
+
+        // Completed flights are forgotten.
+        assert!(provider.in_flight.lock().unwrap().is_empty());
+        provider.fetch_map(&[mock("a")]).await.unwrap();
+        assert_eq!(backend.calls().len(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shares_errors_with_waiters() {
+        let backend = SlowBackend::new(true);
+        let provider = SingleFlightProvider::new(backend.clone());
+
+        let refs = [mock("a")];
+        let (first, second) = tokio::join!(provider.fetch_map(&refs), provider.fetch_map(&refs));
+
+        assert_eq!(backend.calls().len(), 1);
+        for result in [first, second] {
+            let err = result.unwrap_err();
+            assert!(err.is_transient(), "{err}");
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_share_foreign_errors() {
+        let backend = SlowBackend::poisoned(mock("bad"));
+        let provider = SingleFlightProvider::new(backend.clone());
+
+        let first = [mock("a"), mock("bad")];
+        let second = [mock("a")];
+        let (first, second) = tokio::join!(provider.fetch_map(&first), provider.fetch_map(&second));
+
+        assert!(matches!(first.unwrap_err(), ProviderError::NotFound(_)));
+        let second = second.unwrap();
+        assert_eq!(second[&mock("a")].expose_secret(), "a-value");
+        assert_eq!(
+            backend.calls(),
+            vec![vec![mock("a"), mock("bad")], vec![mock("a")]]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+//! SOPS provider implementation.
+//!
+//! Decrypts SOPS encrypted YAML, JSON and dotenv files locally using age
+//! identities, without the `sops` binary. Each referenced file is read and
+//! decrypted once per batch, and its MAC is verified before any value is
+//! handed out.
+//!
+//! Rotated identity files are picked up by the `ManagedProvider` wrapper,
+//! which rebuilds the provider when the key file changes.
+
+mod cipher;
+mod tree;
+
+use super::{
+    ProviderError, SecretsProvider,
+    config::sops::SopsConfig,
+    references::{Extract, HasReference, SecretReference, SopsReference},
+};
+use age::x25519::Identity;
+use async_trait::async_trait;
+use cipher::{DataKey, ValueType};
+use secrecy::{ExposeSecret, SecretString};
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+use tracing::debug;
+use tree::{Document, Format, Node};
+
+#[derive(Debug, Error)]
+enum SopsError {
+    #[error("failed to parse file: {0}")]
+    Parse(String),
+
+    #[error("invalid SOPS metadata: {0}")]
+    Metadata(&'static str),
+
+    #[error("file is not encrypted for any age recipient")]
+    NoAgeRecipients,
+
+    #[error("none of the configured age identities can decrypt the data key")]
+    NoMatchingIdentity,
+
+    #[error("failed to decrypt data key: {0}")]
+    DataKey(String),
+
+    #[error("malformed encrypted value")]
+    InvalidValue,
+
+    #[error("failed to decrypt value")]
+    Decrypt,
+
+    #[error("MAC mismatch, the file may have been tampered with")]
+    MacMismatch,
+}
+
+/// Decrypted values of a file, keyed by their path in the document.
+type Values = HashMap<Vec<String>, SecretString>;
+
+pub struct SopsProvider {
+    identities: Vec<Identity>,
+}
+
+impl SopsProvider {
+    pub async fn new(config: SopsConfig) -> Result<Self, ProviderError> {
+        let key = config.sops_age_key.resolve().await?;
+        let identities = parse_identities(key.expose_secret())?;
+        Ok(Self { identities })
+    }
+
+    /// Reads and decrypts a whole file. Returns `None` if it does not exist.
+    async fn decrypt_file(&self, path: &Path) -> Result<Option<Values>, ProviderError> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ProviderError::Io(e)),
+        };
+
+        let doc = Document::parse(&content, Format::from_path(path))
+            .and_then(|doc| decrypt_document(&doc, &self.identities))
+            .map_err(|e| match e {
+                SopsError::NoMatchingIdentity => {
+                    ProviderError::Unauthorized(format!("{}: {}", path.display(), e))
+                }
+                e => ProviderError::Other(format!(
+                    "failed to decrypt SOPS file {}: {}",
+                    path.display(),
+                    e
+                )),
+            })?;
+        Ok(Some(doc))
+    }
+}
+
+impl HasReference for SopsProvider {
+    type Reference = SopsReference;
+}
+
+#[async_trait]
+impl SecretsProvider for SopsProvider {
+    async fn fetch_map(
+        &self,
+        references: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        let mut files: HashMap<&Path, Vec<&SopsReference>> = HashMap::new();
+        for r in references.iter().filter_map(SopsReference::extract) {
+            files.entry(r.path()).or_default().push(r);
+        }
+
+        let mut map = HashMap::new();
+        for (path, refs) in files {
+            let Some(values) = self.decrypt_file(path).await? else {
+                debug!("SOPS file {} not found", path.display());
+                continue;
+            };
+            for r in refs {
+                if let Some(value) = values.get(r.key()) {
+                    map.insert(SecretReference::Sops(r.clone()), value.clone());
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+/// Parses age identities, one `AGE-SECRET-KEY-1...` per line, in the format
+/// written by `age-keygen`.
+fn parse_identities(keys: &str) -> Result<Vec<Identity>, ProviderError> {
+    let identities = keys
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            Identity::from_str(line)
+                .map_err(|e| ProviderError::InvalidConfig(format!("invalid age identity: {}", e)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if identities.is_empty() {
+        return Err(ProviderError::InvalidConfig(
+            "no age identities found in SOPS age key".to_string(),
+        ));
+    }
+    Ok(identities)
+}
+
+/// Decrypts every value of the document and verifies the MAC over them.
+fn decrypt_document(doc: &Document, identities: &[Identity]) -> Result<Values, SopsError> {
+    let key = DataKey::decrypt(&doc.metadata.age, identities)?;
+
+    let mut walker = Walker {
+        key: &key,
+        mac_only_encrypted: doc.metadata.mac_only_encrypted,
+        hasher: Sha512::new(),
+        values: HashMap::new(),
+    };
+    let (mut lookup, mut aad) = (Vec::new(), Vec::new());
+    for (k, v) in &doc.data {
+        lookup.push(k.clone());
+        aad.push(k.as_str());
+        walker.walk(v, &mut lookup, &mut aad)?;
+        lookup.pop();
+        aad.pop();
+    }
+
+    let computed = hex::encode_upper(walker.hasher.finalize());
+    let (_, expected) = key.decrypt_value(&doc.metadata.mac, &doc.metadata.last_modified)?;
+    if computed != expected.expose_secret() {
+        return Err(SopsError::MacMismatch);
+    }
+
+    Ok(walker.values)
+}
+
+/// Walks the document in order, decrypting leaves and feeding the MAC.
+struct Walker<'a> {
+    key: &'a DataKey,
+    mac_only_encrypted: bool,
+    hasher: Sha512,
+    values: Values,
+}
+
+impl Walker<'_> {
+    /// `lookup` is the path used by references, including list indices.
+    /// `aad` is the path values are authenticated against, which only
+    /// contains mapping keys.
+    fn walk<'n>(
+        &mut self,
+        node: &'n Node,
+        lookup: &mut Vec<String>,
+        aad: &mut Vec<&'n str>,
+    ) -> Result<(), SopsError> {
+        match node {
+            Node::Map(entries) => {
+                for (k, v) in entries {
+                    lookup.push(k.clone());
+                    aad.push(k);
+                    self.walk(v, lookup, aad)?;
+                    lookup.pop();
+                    aad.pop();
+                }
+            }
+            Node::Seq(items) => {
+                for (i, v) in items.iter().enumerate() {
+                    lookup.push(i.to_string());
+                    self.walk(v, lookup, aad)?;
+                    lookup.pop();
+                }
+            }
+            Node::Null => {}
+            Node::Str(s) if cipher::is_encrypted(s) => {
+                let additional_data = format!("{}:", aad.join(":"));
+                let (value_type, plaintext) = self.key.decrypt_value(s, &additional_data)?;
+                let value = match value_type {
+                    ValueType::Comment => return Ok(()),
+                    ValueType::Bool => {
+                        let b = parse_bool(plaintext.expose_secret()).ok_or(SopsError::Decrypt)?;
+                        self.hasher.update(bool_mac_bytes(b));
+                        SecretString::from(b.to_string())
+                    }
+                    _ => {
+                        self.hasher.update(plaintext.expose_secret());
+                        plaintext
+                    }
+                };
+                self.values.insert(lookup.clone(), value);
+            }
+            scalar => {
+                let (mac, value) = match scalar {
+                    Node::Bool(b) => (bool_mac_bytes(*b).to_string(), b.to_string()),
+                    Node::Int(i) => (i.to_string(), i.to_string()),
+                    Node::Float(f) => (f.to_string(), f.to_string()),
+                    Node::Str(s) => (s.clone(), s.clone()),
+                    _ => unreachable!("containers and null are handled above"),
+                };
+                if !self.mac_only_encrypted {
+                    self.hasher.update(mac);
+                }
+                self.values
+                    .insert(lookup.clone(), SecretString::from(value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SOPS hashes booleans in their Python spelling.
+fn bool_mac_bytes(b: bool) -> &'static str {
+    if b { "True" } else { "False" }
+}
+
+/// Accepts the spellings of Go's `strconv.ParseBool`.
+fn parse_bool(s: &str) -> Option<bool> {
+    match s {
+        "1" | "t" | "T" | "true" | "TRUE" | "True" => Some(true),
+        "0" | "f" | "F" | "false" | "FALSE" | "False" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cipher::testing::{encrypt_data_key, encrypt_value};
+    use super::*;
+    use crate::provider::AuthToken;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const KEY: [u8; 32] = [9u8; 32];
+    const LAST_MODIFIED: &str = "2025-01-01T00:00:00Z";
+
+    /// Computes the encrypted MAC over the given leaves.
+    fn mac(leaves: &[&str]) -> String {
+        let mut hasher = Sha512::new();
+        for leaf in leaves {
+            hasher.update(leaf);
+        }
+        let mac = hex::encode_upper(hasher.finalize());
+        encrypt_value(&KEY, &mac, LAST_MODIFIED, "str")
+    }
+
+    async fn provider(identity: &Identity) -> SopsProvider {
+        let key = format!(
+            "# created: 2025-01-01\n# public key: {}\n{}\n",
+            identity.to_public(),
+            identity.to_string().expose_secret()
+        );
+        SopsProvider::new(SopsConfig {
+            sops_age_key: AuthToken::new(SecretString::from(key)),
+        })
+        .await
+        .unwrap()
+    }
+
+    fn write(suffix: &str, content: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file
+    }
+
+    fn reference(path: &Path, key: &str) -> SecretReference {
+        SecretReference::Sops(
+            SopsReference::from_str(&format!("sops://{}#{}", path.display(), key)).unwrap(),
+        )
+    }
+
+    async fn fetch(
+        provider: &SopsProvider,
+        refs: &[SecretReference],
+    ) -> Result<HashMap<SecretReference, String>, ProviderError> {
+        Ok(provider
+            .fetch_map(refs)
+            .await?
+            .into_iter()
+            .map(|(k, v)| (k, v.expose_secret().to_string()))
+            .collect())
+    }
+
+    fn yaml_fixture(identity: &Identity, mac: &str) -> String {
+        format!(
+            "db:\n  password: {}\n  port: {}\n  tls: {}\n\
+             hosts:\n  - {}\n  - {}\n\
+             region_unencrypted: us-east-1\n\
+             sops:\n  age:\n    - recipient: {}\n      enc: |\n{}\n  lastmodified: \"{}\"\n  mac: {}\n  version: 3.9.0\n",
+            encrypt_value(&KEY, "hunter2", "db:password:", "str"),
+            encrypt_value(&KEY, "5432", "db:port:", "int"),
+            encrypt_value(&KEY, "True", "db:tls:", "bool"),
+            encrypt_value(&KEY, "a.example.com", "hosts:", "str"),
+            encrypt_value(&KEY, "b.example.com", "hosts:", "str"),
+            identity.to_public(),
+            encrypt_data_key(&KEY, identity)
+                .lines()
+                .map(|l| format!("        {}", l))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            LAST_MODIFIED,
+            mac,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fetch_yaml() {
+        let identity = Identity::generate();
+        let mac = mac(&[
+            "hunter2",
+            "5432",
+            "True",
+            "a.example.com",
+            "b.example.com",
+            "us-east-1",
+        ]);
+        let file = write(".yaml", &yaml_fixture(&identity, &mac));
+
+        let refs = [
+            reference(file.path(), "db.password"),
+            reference(file.path(), "db.port"),
+            reference(file.path(), "db.tls"),
+            reference(file.path(), "hosts.1"),
+            reference(file.path(), "region_unencrypted"),
+            reference(file.path(), "db.missing"),
+        ];
+        let map = fetch(&provider(&identity).await, &refs).await.unwrap();
+
+        assert_eq!(map[&refs[0]], "hunter2");
+        assert_eq!(map[&refs[1]], "5432");
+        assert_eq!(map[&refs[2]], "true");
+        assert_eq!(map[&refs[3]], "b.example.com");
+        assert_eq!(map[&refs[4]], "us-east-1");
+        assert!(!map.contains_key(&refs[5]));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_json_and_dotenv() {
+        let identity = Identity::generate();
+        let stanza = encrypt_data_key(&KEY, &identity);
+
+        let json = write(
+            ".json",
+            &serde_json::json!({
+                "api": { "token": encrypt_value(&KEY, "tok", "api:token:", "str") },
+                "sops": {
+                    "age": [{ "recipient": identity.to_public().to_string(), "enc": stanza }],
+                    "lastmodified": LAST_MODIFIED,
+                    "mac": mac(&["tok"]),
+                }
+            })
+            .to_string(),
+        );
+        let dotenv = write(
+            ".env",
+            &format!(
+                "API_KEY={}\nsops_age__list_0__map_enc={}\nsops_lastmodified={}\nsops_mac={}\n",
+                encrypt_value(&KEY, "abc", "API_KEY:", "str"),
+                stanza.replace('\n', "\\n"),
+                LAST_MODIFIED,
+                mac(&["abc"]),
+            ),
+        );
+
+        let refs = [
+            reference(json.path(), "api.token"),
+            reference(dotenv.path(), "API_KEY"),
+        ];
+        let map = fetch(&provider(&identity).await, &refs).await.unwrap();
+        assert_eq!(map[&refs[0]], "tok");
+        assert_eq!(map[&refs[1]], "abc");
+    }
+
+    #[tokio::test]
+    async fn test_tampered_file_is_rejected() {
+        let identity = Identity::generate();
+        let mac = mac(&["hunter2"]);
+        let file = write(".yaml", &yaml_fixture(&identity, &mac));
+
+        let err = fetch(
+            &provider(&identity).await,
+            &[reference(file.path(), "db.password")],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("MAC mismatch"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_wrong_identity_is_unauthorized() {
+        let identity = Identity::generate();
+        let file = write(".yaml", &yaml_fixture(&identity, &mac(&[])));
+
+        let err = fetch(
+            &provider(&Identity::generate()).await,
+            &[reference(file.path(), "db.password")],
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, ProviderError::Unauthorized(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_missing_file_is_omitted() {
+        let identity = Identity::generate();
+        let refs = [reference(Path::new("/nonexistent/secrets.yaml"), "a")];
+        let map = fetch(&provider(&identity).await, &refs).await.unwrap();
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_parse_identities() {
+        assert!(matches!(
+            parse_identities("# just a comment\n"),
+            Err(ProviderError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            parse_identities("AGE-SECRET-KEY-1NOTAKEY"),
+            Err(ProviderError::InvalidConfig(_))
+        ));
+    }
+}
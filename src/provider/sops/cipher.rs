@@ -0,0 +1,234 @@
+//! Cryptographic primitives of the SOPS file format.
+//!
+//! Every value is encrypted with AES-256-GCM under a per-file data key,
+//! which is in turn encrypted to each recipient as an armored age file.
+
+use super::SopsError;
+use aes_gcm::aead::consts::U32;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::aes::Aes256;
+use aes_gcm::{AesGcm, Key, Nonce};
+use age::armor::ArmoredReader;
+use age::x25519::Identity;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use secrecy::{ExposeSecret, SecretSlice, SecretString};
+use std::io::Read;
+
+/// SOPS uses AES-256-GCM with a 32 byte nonce.
+type SopsCipher = AesGcm<Aes256, U32>;
+
+/// Length of the GCM authentication tag appended to the ciphertext.
+const TAG_LEN: usize = 16;
+
+/// The type a value had before it was encrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ValueType {
+    Str,
+    Int,
+    Float,
+    Bool,
+    Bytes,
+    Comment,
+}
+
+impl ValueType {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "str" => Self::Str,
+            "int" => Self::Int,
+            "float" => Self::Float,
+            "bool" => Self::Bool,
+            "bytes" => Self::Bytes,
+            "comment" => Self::Comment,
+            _ => return None,
+        })
+    }
+}
+
+/// An encrypted value: `ENC[AES256_GCM,data:<b64>,iv:<b64>,tag:<b64>,type:<type>]`.
+struct EncryptedValue {
+    data: Vec<u8>,
+    iv: Vec<u8>,
+    tag: Vec<u8>,
+    value_type: ValueType,
+}
+
+impl EncryptedValue {
+    fn parse(s: &str) -> Option<Self> {
+        let body = s.strip_prefix("ENC[AES256_GCM,")?.strip_suffix(']')?;
+
+        let (mut data, mut iv, mut tag, mut value_type) = (None, None, None, None);
+        for field in body.split(',') {
+            let (name, value) = field.split_once(':')?;
+            match name {
+                "data" => data = Some(BASE64.decode(value).ok()?),
+                "iv" => iv = Some(BASE64.decode(value).ok()?),
+                "tag" => tag = Some(BASE64.decode(value).ok()?),
+                "type" => value_type = Some(ValueType::parse(value)?),
+                _ => return None,
+            }
+        }
+
+        let (iv, tag) = (iv?, tag?);
+        if iv.len() != 32 || tag.len() != TAG_LEN {
+            return None;
+        }
+        Some(Self {
+            data: data?,
+            iv,
+            tag,
+            value_type: value_type?,
+        })
+    }
+}
+
+/// Returns true if the value is in the SOPS encrypted value format.
+pub(super) fn is_encrypted(s: &str) -> bool {
+    s.starts_with("ENC[AES256_GCM,")
+}
+
+/// The per-file symmetric key all values are encrypted with.
+pub(super) struct DataKey(SecretSlice<u8>);
+
+impl DataKey {
+    /// Decrypts the data key from the first age stanza that one of the
+    /// identities can open.
+    pub(super) fn decrypt(stanzas: &[String], identities: &[Identity]) -> Result<Self, SopsError> {
+        if stanzas.is_empty() {
+            return Err(SopsError::NoAgeRecipients);
+        }
+
+        for stanza in stanzas {
+            let decryptor = age::Decryptor::new_buffered(ArmoredReader::new(stanza.as_bytes()))
+                .map_err(|e| SopsError::DataKey(e.to_string()))?;
+
+            let mut reader =
+                match decryptor.decrypt(identities.iter().map(|i| i as &dyn age::Identity)) {
+                    Ok(reader) => reader,
+                    Err(age::DecryptError::NoMatchingKeys) => continue,
+                    Err(e) => return Err(SopsError::DataKey(e.to_string())),
+                };
+
+            let mut key = Vec::with_capacity(32);
+            reader
+                .read_to_end(&mut key)
+                .map_err(|e| SopsError::DataKey(e.to_string()))?;
+            let key = SecretSlice::from(key);
+            if key.expose_secret().len() != 32 {
+                return Err(SopsError::DataKey("data key must be 32 bytes".to_string()));
+            }
+            return Ok(Self(key));
+        }
+
+        Err(SopsError::NoMatchingIdentity)
+    }
+
+    /// Decrypts a single value, authenticating it against its position in
+    /// the document (`additional_data`).
+    pub(super) fn decrypt_value(
+        &self,
+        value: &str,
+        additional_data: &str,
+    ) -> Result<(ValueType, SecretString), SopsError> {
+        let enc = EncryptedValue::parse(value).ok_or(SopsError::InvalidValue)?;
+
+        let cipher = SopsCipher::new(Key::<SopsCipher>::from_slice(self.0.expose_secret()));
+        let mut ciphertext = enc.data;
+        ciphertext.extend_from_slice(&enc.tag);
+        let plaintext = cipher
+            .decrypt(
+                Nonce::<U32>::from_slice(&enc.iv),
+                Payload {
+                    msg: &ciphertext,
+                    aad: additional_data.as_bytes(),
+                },
+            )
+            .map_err(|_| SopsError::Decrypt)?;
+
+        let plaintext = String::from_utf8(plaintext).map_err(|_| SopsError::Decrypt)?;
+        Ok((enc.value_type, SecretString::from(plaintext)))
+    }
+}
+
+#[cfg(test)]
+pub(super) mod testing {
+    //! Produces SOPS encrypted values, for round trip tests.
+    use super::*;
+
+    pub fn encrypt_data_key(key: &[u8; 32], identity: &Identity) -> String {
+        age::encrypt_and_armor(&identity.to_public(), key).unwrap()
+    }
+
+    pub fn encrypt_value(key: &[u8; 32], plaintext: &str, aad: &str, value_type: &str) -> String {
+        let cipher = SopsCipher::new(Key::<SopsCipher>::from_slice(key));
+        let iv = [7u8; 32];
+        let mut data = cipher
+            .encrypt(
+                Nonce::<U32>::from_slice(&iv),
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: aad.as_bytes(),
+                },
+            )
+            .unwrap();
+        let tag = data.split_off(data.len() - TAG_LEN);
+        format!(
+            "ENC[AES256_GCM,data:{},iv:{},tag:{},type:{}]",
+            BASE64.encode(data),
+            BASE64.encode(iv),
+            BASE64.encode(tag),
+            value_type
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    const KEY: [u8; 32] = [42u8; 32];
+
+    #[test]
+    fn test_value_round_trip() {
+        let identity = Identity::generate();
+        let key = DataKey::decrypt(&[encrypt_data_key(&KEY, &identity)], &[identity]).unwrap();
+
+        let enc = encrypt_value(&KEY, "hunter2", "db:password:", "str");
+        let (value_type, plaintext) = key.decrypt_value(&enc, "db:password:").unwrap();
+        assert_eq!(value_type, ValueType::Str);
+        assert_eq!(plaintext.expose_secret(), "hunter2");
+
+        // Values are bound to their position in the document
+        assert!(matches!(
+            key.decrypt_value(&enc, "db:user:"),
+            Err(SopsError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn test_data_key_requires_matching_identity() {
+        let stanza = encrypt_data_key(&KEY, &Identity::generate());
+        assert!(matches!(
+            DataKey::decrypt(&[stanza], &[Identity::generate()]),
+            Err(SopsError::NoMatchingIdentity)
+        ));
+        assert!(matches!(
+            DataKey::decrypt(&[], &[Identity::generate()]),
+            Err(SopsError::NoAgeRecipients)
+        ));
+    }
+
+    #[test]
+    fn test_parse_encrypted_value() {
+        let enc = encrypt_value(&KEY, "1", "", "int");
+        assert!(is_encrypted(&enc));
+        assert_eq!(
+            EncryptedValue::parse(&enc).unwrap().value_type,
+            ValueType::Int
+        );
+        assert!(EncryptedValue::parse("ENC[AES256_GCM,data:,iv:,tag:,type:str]").is_none());
+        assert!(EncryptedValue::parse("plain").is_none());
+    }
+}
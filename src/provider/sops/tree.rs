@@ -0,0 +1,382 @@
+//! Order preserving document model for SOPS encrypted files.
+//!
+//! The SOPS MAC is computed over every value in document order, so the
+//! parsed tree must keep mapping keys in the order they appear in the file.
+
+use super::SopsError;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use std::fmt;
+use std::path::Path;
+
+/// Prefix of the flattened metadata keys in dotenv files.
+const DOTENV_METADATA_PREFIX: &str = "sops_";
+
+/// A parsed YAML, JSON or dotenv document.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Node {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Seq(Vec<Node>),
+    Map(Vec<(String, Node)>),
+}
+
+impl Node {
+    fn get(&self, key: &str) -> Option<&Node> {
+        match self {
+            Node::Map(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Node::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a YAML or JSON value")
+            }
+
+            fn visit_unit<E>(self) -> Result<Node, E> {
+                Ok(Node::Null)
+            }
+
+            fn visit_none<E>(self) -> Result<Node, E> {
+                Ok(Node::Null)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Node, D::Error> {
+                Node::deserialize(d)
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Node, E> {
+                Ok(Node::Bool(v))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Node, E> {
+                Ok(Node::Int(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Node, E> {
+                i64::try_from(v)
+                    .map(Node::Int)
+                    .map_err(|_| E::custom(format!("integer {} is out of range", v)))
+            }
+
+            fn visit_f64<E>(self, v: f64) -> Result<Node, E> {
+                Ok(Node::Float(v))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Node, E> {
+                Ok(Node::Str(v.to_string()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Node, E> {
+                Ok(Node::Str(v))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    items.push(item);
+                }
+                Ok(Node::Seq(items))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+                let mut entries = Vec::new();
+                while let Some((MapKey(key), value)) = map.next_entry()? {
+                    entries.push((key, value));
+                }
+                Ok(Node::Map(entries))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+/// A mapping key. YAML allows scalar keys of any type, which are used by
+/// their textual form like SOPS does.
+struct MapKey(String);
+
+impl<'de> Deserialize<'de> for MapKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KeyVisitor;
+
+        impl Visitor<'_> for KeyVisitor {
+            type Value = MapKey;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a scalar mapping key")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<MapKey, E> {
+                Ok(MapKey(v.to_string()))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<MapKey, E> {
+                Ok(MapKey(v.to_string()))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<MapKey, E> {
+                Ok(MapKey(v.to_string()))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<MapKey, E> {
+                Ok(MapKey(v.to_string()))
+            }
+        }
+
+        deserializer.deserialize_any(KeyVisitor)
+    }
+}
+
+/// The file formats SOPS can encrypt structurally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Format {
+    Yaml,
+    Json,
+    Dotenv,
+}
+
+impl Format {
+    /// Picks the format from the file extension, like SOPS does.
+    /// Unknown extensions are treated as YAML.
+    pub(super) fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::Json,
+            Some("env") => Self::Dotenv,
+            _ => Self::Yaml,
+        }
+    }
+}
+
+/// The SOPS metadata needed to decrypt a file with age.
+#[derive(Debug)]
+pub(super) struct Metadata {
+    /// Armored age files, each holding the data key for one recipient.
+    pub age: Vec<String>,
+    /// The encrypted MAC over all values.
+    pub mac: String,
+    /// Additional data the MAC is encrypted with.
+    pub last_modified: String,
+    /// Whether the MAC only covers encrypted values.
+    pub mac_only_encrypted: bool,
+}
+
+/// An encrypted document, split into its data and metadata.
+#[derive(Debug)]
+pub(super) struct Document {
+    pub data: Vec<(String, Node)>,
+    pub metadata: Metadata,
+}
+
+impl Document {
+    pub(super) fn parse(content: &str, format: Format) -> Result<Self, SopsError> {
+        match format {
+            Format::Yaml => {
+                let root: Node =
+                    serde_yaml::from_str(content).map_err(|e| SopsError::Parse(e.to_string()))?;
+                Self::from_root(root)
+            }
+            Format::Json => {
+                let root: Node =
+                    serde_json::from_str(content).map_err(|e| SopsError::Parse(e.to_string()))?;
+                Self::from_root(root)
+            }
+            Format::Dotenv => Self::from_dotenv(content),
+        }
+    }
+
+    /// YAML and JSON files keep their metadata under a top level `sops` key.
+    fn from_root(root: Node) -> Result<Self, SopsError> {
+        let Node::Map(mut data) = root else {
+            return Err(SopsError::Parse(
+                "top level value must be a mapping".to_string(),
+            ));
+        };
+        let index = data
+            .iter()
+            .position(|(k, _)| k == "sops")
+            .ok_or(SopsError::Metadata("missing 'sops' section"))?;
+        let (_, sops) = data.remove(index);
+
+        let age = match sops.get("age") {
+            Some(Node::Seq(stanzas)) => stanzas
+                .iter()
+                .filter_map(|s| s.get("enc").and_then(Node::as_str).map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let mac = sops
+            .get("mac")
+            .and_then(Node::as_str)
+            .ok_or(SopsError::Metadata("missing 'mac'"))?;
+        let last_modified = sops
+            .get("lastmodified")
+            .and_then(Node::as_str)
+            .ok_or(SopsError::Metadata("missing 'lastmodified'"))?;
+        let mac_only_encrypted = matches!(sops.get("mac_only_encrypted"), Some(Node::Bool(true)));
+
+        Ok(Self {
+            metadata: Metadata {
+                age,
+                mac: mac.to_string(),
+                last_modified: last_modified.to_string(),
+                mac_only_encrypted,
+            },
+            data,
+        })
+    }
+
+    /// Dotenv files flatten the metadata into `sops_` prefixed variables,
+    /// e.g. `sops_age__list_0__map_enc`, with newlines escaped as `\n`.
+    fn from_dotenv(content: &str) -> Result<Self, SopsError> {
+        let mut data = Vec::new();
+        let mut age = Vec::new();
+        let mut mac = None;
+        let mut last_modified = None;
+        let mut mac_only_encrypted = false;
+
+        for line in content.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| SopsError::Parse(format!("invalid dotenv line '{}'", line)))?;
+            let value = value.replace("\\n", "\n");
+
+            let Some(meta) = key.strip_prefix(DOTENV_METADATA_PREFIX) else {
+                data.push((key.to_string(), Node::Str(value)));
+                continue;
+            };
+            match meta {
+                "mac" => mac = Some(value),
+                "lastmodified" => last_modified = Some(value),
+                "mac_only_encrypted" => mac_only_encrypted = value == "true",
+                _ if meta.starts_with("age__list_") && meta.ends_with("__map_enc") => {
+                    age.push(value)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            data,
+            metadata: Metadata {
+                age,
+                mac: mac.ok_or(SopsError::Metadata("missing 'sops_mac'"))?,
+                last_modified: last_modified
+                    .ok_or(SopsError::Metadata("missing 'sops_lastmodified'"))?,
+                mac_only_encrypted,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_preserves_order() {
+        let doc = Document::parse(
+            "zeta: 1\nalpha:\n  b: true\n  a: [x, 2.5]\nsops:\n  mac: M\n  lastmodified: T\n",
+            Format::Yaml,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.data,
+            vec![
+                ("zeta".to_string(), Node::Int(1)),
+                (
+                    "alpha".to_string(),
+                    Node::Map(vec![
+                        ("b".to_string(), Node::Bool(true)),
+                        (
+                            "a".to_string(),
+                            Node::Seq(vec![Node::Str("x".to_string()), Node::Float(2.5)])
+                        ),
+                    ])
+                ),
+            ]
+        );
+        assert_eq!(doc.metadata.mac, "M");
+        assert_eq!(doc.metadata.last_modified, "T");
+        assert!(doc.metadata.age.is_empty());
+    }
+
+    #[test]
+    fn test_json_preserves_order() {
+        let doc = Document::parse(
+            r#"{"b": "1", "a": null, "sops": {"mac": "M", "lastmodified": "T",
+               "age": [{"recipient": "age1", "enc": "E"}], "mac_only_encrypted": true}}"#,
+            Format::Json,
+        )
+        .unwrap();
+        let keys: Vec<&str> = doc.data.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["b", "a"]);
+        assert_eq!(doc.metadata.age, ["E"]);
+        assert!(doc.metadata.mac_only_encrypted);
+    }
+
+    #[test]
+    fn test_dotenv_metadata_is_unflattened() {
+        let doc = Document::parse(
+            "# comment\nAPI_KEY=ENC[x]\nsops_age__list_0__map_enc=-----BEGIN-----\\nabc\\n\n\
+             sops_age__list_0__map_recipient=age1\nsops_mac=M\nsops_lastmodified=T\n",
+            Format::Dotenv,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.data,
+            vec![("API_KEY".to_string(), Node::Str("ENC[x]".to_string()))]
+        );
+        assert_eq!(doc.metadata.age, ["-----BEGIN-----\nabc\n"]);
+    }
+
+    #[test]
+    fn test_missing_metadata() {
+        assert!(matches!(
+            Document::parse("a: b\n", Format::Yaml),
+            Err(SopsError::Metadata(_))
+        ));
+        assert!(matches!(
+            Document::parse("A=b\n", Format::Dotenv),
+            Err(SopsError::Metadata(_))
+        ));
+        assert!(matches!(
+            Document::parse("- a\n", Format::Yaml),
+            Err(SopsError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("a/b.json")), Format::Json);
+        assert_eq!(Format::from_path(Path::new("prod.env")), Format::Dotenv);
+        assert_eq!(Format::from_path(Path::new("secrets.yaml")), Format::Yaml);
+        assert_eq!(Format::from_path(Path::new("secrets")), Format::Yaml);
+    }
+}
@@ -7,6 +7,7 @@ use serde::{Deserialize, Serialize, Serializer};
 use std::fmt;
 use std::num::NonZeroUsize;
 use std::str::FromStr;
+use std::time::Duration;
 use thiserror::Error;
 use url::Url;
 
@@ -268,6 +269,126 @@ impl std::fmt::Display for ConcurrencyLimit {
     }
 }
 
+/// Duration wrapper for provider options, such as cache TTLs and retry
+/// delays, to support human-readable parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ProviderDuration(pub Duration);
+
+impl TryFrom<String> for ProviderDuration {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+            .map_err(|e: humantime::DurationError| e.to_string())
+    }
+}
+
+/// Defaults to seconds if no unit specified, otherwise uses humantime parsing.
+impl FromStr for ProviderDuration {
+    type Err = humantime::DurationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(secs) = s.parse::<u64>() {
+            return Ok(ProviderDuration(Duration::from_secs(secs)));
+        }
+        Ok(ProviderDuration(humantime::parse_duration(s)?))
+    }
+}
+
+impl Serialize for ProviderDuration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl std::fmt::Display for ProviderDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", humantime::format_duration(self.0))
+    }
+}
+
+impl From<ProviderDuration> for Duration {
+    fn from(val: ProviderDuration) -> Self {
+        val.0
+    }
+}
+
+/// A single field value in a JSON object of secret fields
+/// (e.g. a KV v2 data map, or a JSON encoded secret string).
+///
+/// Scalars are captured as secrets at deserialization time so plaintext
+/// never sits in a non-zeroizing type. Numbers and bools resolve to their
+/// string form. Nulls, arrays, and objects cannot be injected as a value.
+#[cfg(any(feature = "bao", feature = "aws"))]
+pub(crate) enum JsonField {
+    Scalar(SecretString),
+    Unsupported,
+}
+
+#[cfg(any(feature = "bao", feature = "aws"))]
+impl<'de> Deserialize<'de> for JsonField {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+
+        struct JsonFieldVisitor;
+
+        impl<'de> Visitor<'de> for JsonFieldVisitor {
+            type Value = JsonField;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a JSON field value")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.into())))
+            }
+
+            fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.into())))
+            }
+
+            fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.to_string().into())))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.to_string().into())))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.to_string().into())))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                Ok(JsonField::Scalar(SecretString::new(v.to_string().into())))
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(JsonField::Unsupported)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+                Ok(JsonField::Unsupported)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+                Ok(JsonField::Unsupported)
+            }
+        }
+
+        deserializer.deserialize_any(JsonFieldVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -348,4 +469,36 @@ mod tests {
             "https://example.com/v1/auth/app%20role/login"
         );
     }
+
+    #[cfg(any(feature = "bao", feature = "aws"))]
+    #[test]
+    fn test_json_scalars_deserialize_as_secrets() {
+        use secrecy::ExposeSecret;
+
+        let json = r#"{"password": "hunter2", "port": 5432, "enabled": true, "ratio": 1.5}"#;
+        let fields: std::collections::HashMap<String, JsonField> =
+            serde_json::from_str(json).unwrap();
+
+        let expect_secret = |key: &str| match &fields[key] {
+            JsonField::Scalar(s) => s.expose_secret().to_string(),
+            JsonField::Unsupported => panic!("field '{key}' should be a scalar"),
+        };
+
+        assert_eq!(expect_secret("password"), "hunter2");
+        assert_eq!(expect_secret("port"), "5432");
+        assert_eq!(expect_secret("enabled"), "true");
+        assert_eq!(expect_secret("ratio"), "1.5");
+    }
+
+    #[cfg(any(feature = "bao", feature = "aws"))]
+    #[test]
+    fn test_json_structured_values_unsupported() {
+        let json = r#"{"nothing": null, "list": [1, 2], "nested": {"a": 1}}"#;
+        let fields: std::collections::HashMap<String, JsonField> =
+            serde_json::from_str(json).unwrap();
+
+        for key in ["nothing", "list", "nested"] {
+            assert!(matches!(fields[key], JsonField::Unsupported));
+        }
+    }
 }
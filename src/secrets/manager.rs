@@ -5,15 +5,19 @@
 
 use crate::events::{EventHandler, FsEvent, HandlerError};
 use crate::path::{AbsolutePath, CanonicalPath};
-use crate::provider::SecretsProvider;
+use crate::provider::{SecretReference, SecretsProvider};
 use crate::secrets::config::{InjectFailurePolicy, SecretManagerConfig};
 use crate::secrets::registry::SecretFileRegistry;
 use crate::secrets::{SecretError, SecretSource, file::SecretFile};
 use crate::template::Template;
 use async_trait::async_trait;
-use secrecy::ExposeSecret;
+use futures::future::{BoxFuture, join_all};
+use indexmap::IndexSet;
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use tracing::{debug, info, warn};
 
 /// Manager for secret files, responsible for resolving and materializing secrets
@@ -26,6 +30,28 @@ pub struct SecretFileManager {
     registry: SecretFileRegistry,
     literals: Vec<SecretFile>,
     provider: Arc<dyn SecretsProvider>,
+    /// Whether the last `inject_all` fetched leased secrets
+    leased: AtomicBool,
+}
+
+/// File content parsed for secret references.
+enum Parsed<'a> {
+    /// Content with template tags
+    Template(Template<'a>),
+    /// Content which is a single bare reference
+    Bare(SecretReference),
+    /// Content without secrets, passed through as is
+    Plain,
+}
+
+impl Parsed<'_> {
+    fn references(&self) -> Vec<SecretReference> {
+        match self {
+            Self::Template(tpl) => tpl.references(),
+            Self::Bare(reference) => vec![reference.clone()],
+            Self::Plain => Vec::new(),
+        }
+    }
 }
 
 impl SecretFileManager {
@@ -53,6 +79,7 @@ impl SecretFileManager {
             registry,
             literals,
             provider,
+            leased: AtomicBool::new(false),
         };
 
         manager.collisions()?;
@@ -82,46 +109,77 @@ impl SecretFileManager {
         pinned.chain(mapped).collect()
     }
 
-    async fn resolve(&self, file: &SecretFile) -> Result<String, SecretError> {
+    async fn read(file: &SecretFile) -> Result<String, SecretError> {
         let f = file.clone();
         let content =
             tokio::task::spawn_blocking(move || f.content().map(|c| c.into_owned())).await??;
+        Ok(content)
+    }
 
-        let tpl = Template::parse(&content, &*self.provider);
-
+    /// Parses the content once, for both collecting its references and
+    /// rendering it.
+    fn parse<'a>(&self, content: &'a str) -> Parsed<'a> {
+        let tpl = Template::parse(content, &*self.provider);
         if tpl.has_secrets() {
-            let references_to_fetch = tpl.references();
-
-            info!(dst=?file.dest(), count=references_to_fetch.len(), "fetching secrets from template");
-            let secrets_map = self.provider.fetch_map(&references_to_fetch).await?;
-
-            let output = tpl.render_with(|k| secrets_map.get(k).map(|s| s.expose_secret()));
-            Ok(output.into_owned())
+            Parsed::Template(tpl)
         } else {
-            // Try to parse the entire trimmed content as a single reference.
-            if let Some(reference) = self.provider.parse(content.trim()) {
-                info!(dst=?file.dest(), "fetching bare secret");
-
-                let secrets_map = self
-                    .provider
-                    .fetch_map(std::slice::from_ref(&reference))
-                    .await?;
-
-                match secrets_map.get(&reference) {
-                    Some(val) => Ok(val.expose_secret().to_string()),
-                    None => {
-                        warn!(dst=?file.dest(), "provider returned success but secret value was missing");
-                        Ok(content) // Fallback to original content
-                    }
+            match self.provider.parse(content.trim()) {
+                Some(reference) => Parsed::Bare(reference),
+                None => Parsed::Plain,
+            }
+        }
+    }
+
+    /// Renders parsed content with already resolved secrets.
+    fn render(
+        &self,
+        file: &SecretFile,
+        content: &str,
+        parsed: &Parsed<'_>,
+        secrets_map: &HashMap<SecretReference, SecretString>,
+    ) -> String {
+        match parsed {
+            Parsed::Template(tpl) => tpl
+                .render_with(|k| secrets_map.get(k).map(|s| s.expose_secret()))
+                .into_owned(),
+            Parsed::Bare(reference) => match secrets_map.get(reference) {
+                Some(val) => val.expose_secret().to_string(),
+                None => {
+                    warn!(dst=?file.dest(), "provider returned success but secret value was missing");
+                    content.to_string() // Fallback to original content
                 }
-            } else {
+            },
+            Parsed::Plain => {
                 // Not a template and not a bare secret, so just return the original content.
                 debug!(dst=?file.dest(), "no resolvable secrets found; passing through");
-                Ok(content)
+                content.to_string()
             }
         }
     }
 
+    /// Fetches the secrets of already parsed content and renders it.
+    async fn fetch_and_render(
+        &self,
+        file: &SecretFile,
+        content: &str,
+        parsed: &Parsed<'_>,
+    ) -> Result<String, SecretError> {
+        let references_to_fetch = parsed.references();
+        if references_to_fetch.is_empty() {
+            return Ok(self.render(file, content, parsed, &HashMap::new()));
+        }
+
+        info!(dst=?file.dest(), count=references_to_fetch.len(), "fetching secrets from template");
+        let secrets_map = self.provider.fetch_map(&references_to_fetch).await?;
+        Ok(self.render(file, content, parsed, &secrets_map))
+    }
+
+    async fn resolve(&self, file: &SecretFile) -> Result<String, SecretError> {
+        let content = Self::read(file).await?;
+        let parsed = self.parse(&content);
+        self.fetch_and_render(file, &content, &parsed).await
+    }
+
     pub async fn materialize(&self, file: &SecretFile, content: String) -> Result<(), SecretError> {
         let writer = self.config.writer.clone();
         let dest = file.dest().clone();
@@ -173,7 +231,16 @@ impl SecretFileManager {
     }
 
     pub async fn process(&self, file: &SecretFile) -> Result<(), SecretError> {
-        match self.resolve(file).await {
+        let resolved = self.resolve(file).await;
+        self.write_resolved(file, resolved).await
+    }
+
+    async fn write_resolved(
+        &self,
+        file: &SecretFile,
+        resolved: Result<String, SecretError>,
+    ) -> Result<(), SecretError> {
+        match resolved {
             Ok(content) => {
                 if let Err(e) = self.materialize(file, content).await {
                     return self
@@ -189,11 +256,95 @@ impl SecretFileManager {
         }
     }
 
+    /// Resolves and writes every managed secret file.
+    ///
+    /// The references of all files are fetched in a single batch, and the
+    /// files are then rendered and written concurrently. If the batch
+    /// fails because of one of its references, the files are resolved one
+    /// after the other with a single fetch each, so that one bad reference
+    /// only affects the files which use it, under the failure policy. If
+    /// the provider is unavailable instead, every file which needs it fails
+    /// right away.
     pub async fn inject_all(&self) -> Result<(), SecretError> {
-        for file in self.iter_secrets() {
-            self.process(file).await?;
+        let files: Vec<&SecretFile> = self.iter_secrets().collect();
+        let contents = join_all(files.iter().map(|file| Self::read(file))).await;
+
+        let mut loaded = Vec::with_capacity(files.len());
+        let mut writes = Vec::new();
+        for (file, content) in files.iter().zip(contents) {
+            match content {
+                Ok(content) => loaded.push((*file, content)),
+                Err(e) => writes.push(self.write_resolved(file, Err(e))),
+            }
         }
-        Ok(())
+        let parsed: Vec<(&SecretFile, &str, Parsed<'_>)> = loaded
+            .iter()
+            .map(|(file, content)| (*file, content.as_str(), self.parse(content)))
+            .collect();
+
+        let references: Vec<SecretReference> = parsed
+            .iter()
+            .flat_map(|(_, _, p)| p.references())
+            .collect::<IndexSet<_>>()
+            .into_iter()
+            .collect();
+        self.leased.store(
+            references.iter().any(SecretReference::is_leased),
+            Ordering::Relaxed,
+        );
+
+        let secrets_map = if references.is_empty() {
+            HashMap::new()
+        } else {
+            info!(
+                files = files.len(),
+                count = references.len(),
+                "fetching secrets for all files"
+            );
+            match self.provider.fetch_map(&references).await {
+                Ok(map) => map,
+                // Fetching each file on its own would only wait out the
+                // retries of an unavailable provider once per file.
+                Err(e) if e.is_transient() => {
+                    warn!(error = ?e, "batched fetch failed; provider is unavailable");
+                    writes.extend(parsed.iter().map(|(file, content, p)| {
+                        let resolved = if p.references().is_empty() {
+                            Ok(self.render(file, content, p, &HashMap::new()))
+                        } else {
+                            Err(e.duplicate().into())
+                        };
+                        self.write_resolved(file, resolved)
+                    }));
+                    return join_all(writes).await.into_iter().collect();
+                }
+                Err(e) => {
+                    warn!(error = ?e, "batched fetch failed; resolving files individually");
+                    let mut results: Vec<Result<(), SecretError>> = join_all(writes).await;
+                    for (file, content, p) in &parsed {
+                        let resolved = self.fetch_and_render(file, content, p).await;
+                        results.push(self.write_resolved(file, resolved).await);
+                    }
+                    return results.into_iter().collect();
+                }
+            }
+        };
+
+        writes.extend(parsed.iter().map(|(file, content, p)| {
+            let resolved = Ok(self.render(file, content, p, &secrets_map));
+            self.write_resolved(file, resolved)
+        }));
+        join_all(writes).await.into_iter().collect()
+    }
+
+    /// A future that resolves when the secrets written by the last
+    /// [`Self::inject_all`] have expired, or `None` if none of them are
+    /// leased and so never expire.
+    pub fn expiry(&self) -> Option<BoxFuture<'static, ()>> {
+        if !self.leased.load(Ordering::Relaxed) {
+            return None;
+        }
+        let provider = self.provider.clone();
+        Some(Box::pin(async move { provider.expired().await }))
     }
 
     fn collisions(&self) -> Result<(), SecretError> {
@@ -416,6 +567,18 @@ impl EventHandler for SecretFileManager {
         }
         Ok(())
     }
+
+    fn expired(&self) -> BoxFuture<'static, ()> {
+        let provider = self.provider.clone();
+        Box::pin(async move { provider.expired().await })
+    }
+
+    async fn refresh(&mut self) -> Result<(), HandlerError> {
+        if let Err(e) = self.inject_all().await {
+            warn!(error = ?e, "failed to refresh expired secrets");
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -41,7 +41,7 @@ impl VolumePlugin {
             .await?,
         );
 
-        let service = DockerPluginService::new(driver);
+        let service = DockerPluginService::new(driver.clone());
 
         info!(socket=?socket_path, "Docker Plugin listening");
 
@@ -72,6 +72,7 @@ impl VolumePlugin {
             }
         }
 
+        driver.shutdown().await;
         Ok(())
     }
 
@@ -8,14 +8,15 @@ use super::{
 use crate::{
     config::Overlay,
     error::LocketError,
-    events::StoppableHandler,
+    events::{EventHandler, StoppableHandler},
     path::{AbsolutePath, CanonicalPath},
-    provider::{Provider, ProviderArgs, SecretsProvider},
+    provider::{ProviderArgs, ProviderSet, SecretsProvider},
     volume::types::DockerOptions,
     watch::FsWatcher,
 };
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -175,6 +176,12 @@ impl VolumeRegistry {
         Ok(())
     }
 
+    /// Releases resources held by every provider, such as secret leases.
+    pub async fn shutdown(&self) {
+        let providers: Vec<_> = self.provider_cache.read().await.values().cloned().collect();
+        join_all(providers.iter().map(|p| p.shutdown())).await;
+    }
+
     async fn provision_resources(&self, vol: &Volume) -> Result<ActiveResources, PluginError> {
         info!(volume=%vol.config.name, "Provisioning volume resources");
 
@@ -201,7 +208,7 @@ impl VolumeRegistry {
             } else {
                 drop(cache);
 
-                let provider_config: Provider = provider_args
+                let provider_config: ProviderSet = provider_args
                     .clone()
                     .try_into()
                     .map_err(PluginError::Locket)?;
@@ -246,6 +253,26 @@ impl VolumeRegistry {
                 }
             });
             Some(WatcherHandle { task, token })
+        } else if let Some(expiry) = manager.expiry() {
+            // Not watching, but secrets whose lease expires must still be
+            // rendered again.
+            let token = CancellationToken::new();
+            let stop = token.clone();
+            let mut manager = manager;
+            let task = tokio::spawn(async move {
+                let mut expiry = Some(expiry);
+                while let Some(expired) = expiry.take() {
+                    tokio::select! {
+                        _ = stop.cancelled() => break,
+                        _ = expired => {
+                            info!("secrets expired, refreshing");
+                            let _ = manager.refresh().await;
+                            expiry = manager.expiry();
+                        }
+                    }
+                }
+            });
+            Some(WatcherHandle { task, token })
         } else {
             None
         };
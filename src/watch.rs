@@ -93,6 +93,7 @@ impl<H: EventHandler> FsWatcher<H> {
             info!(path=?watched, "watching for changes");
         }
 
+        let mut expired = self.handler.expired();
         loop {
             debug!("waiting for fs event");
             let exit = self.handler.wait();
@@ -107,6 +108,12 @@ impl<H: EventHandler> FsWatcher<H> {
                         Err(e) => return Err(WatchError::Handler(e)),
                     }
                 }
+                _ = &mut expired => {
+                    info!("secrets expired, refreshing");
+                    self.handler.refresh().await?;
+                    expired = self.handler.expired();
+                    continue;
+                }
                 signal = rx.recv() => {
                     match signal {
                         Some(Ok(ev)) => ev,
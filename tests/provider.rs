@@ -10,12 +10,16 @@ use secrecy::SecretString;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tempfile::tempdir;
 
 // Holds a static map of "Remote" secrets to serve.
 #[derive(Debug, Clone, Default)]
 struct MockProvider {
     data: HashMap<String, SecretString>,
+    calls: Arc<AtomicUsize>,
+    parses: Arc<AtomicUsize>,
+    unavailable: bool,
 }
 
 impl MockProvider {
@@ -24,7 +28,12 @@ impl MockProvider {
         for (k, v) in data {
             map.insert(k.to_string(), SecretString::new(v.into()));
         }
-        Self { data: map }
+        Self {
+            data: map,
+            calls: Arc::default(),
+            parses: Arc::default(),
+            unavailable: false,
+        }
     }
 }
 
@@ -32,6 +41,7 @@ impl MockProvider {
 // Generates SecretReference::Mock, avoiding OpReference entirely.
 impl ReferenceParser for MockProvider {
     fn parse(&self, raw: &str) -> Option<SecretReference> {
+        self.parses.fetch_add(1, Ordering::SeqCst);
         if raw.starts_with("test:") {
             Some(SecretReference::Mock(raw.to_string()))
         } else {
@@ -46,6 +56,10 @@ impl SecretsProvider for MockProvider {
         &self,
         references: &[SecretReference],
     ) -> Result<HashMap<SecretReference, SecretString>, ProviderError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.unavailable {
+            return Err(ProviderError::Unavailable("maintenance".into()));
+        }
         let mut result = HashMap::new();
 
         for ref_obj in references {
@@ -174,6 +188,83 @@ async fn test_ignore_unknown_providers() {
     assert_eq!(result, "A: {{ op://real/secret }}\nB: value");
 }
 
+#[tokio::test]
+async fn test_inject_all_batches_references() {
+    let (tmp, out_dir, config) = setup("a.yaml", "user: {{ test:user }}");
+    let tpl_dir = tmp.path().join("templates");
+    std::fs::write(
+        tpl_dir.join("b.yaml"),
+        "user: {{ test:user }}\npass: {{ test:pass }}",
+    )
+    .unwrap();
+    std::fs::write(tpl_dir.join("key"), "test:key").unwrap();
+    std::fs::write(tpl_dir.join("plain.txt"), "no secrets").unwrap();
+
+    let provider = Arc::new(MockProvider::new(vec![
+        ("test:user", "admin"),
+        ("test:pass", "secret123"),
+        ("test:key", "ssh-key"),
+    ]));
+    let manager = SecretFileManager::new(config, provider.clone()).unwrap();
+
+    manager.inject_all().await.unwrap();
+
+    assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    // Every tag, bare reference and plain file is parsed once.
+    assert_eq!(provider.parses.load(Ordering::SeqCst), 5);
+    let read = |name: &str| std::fs::read_to_string(out_dir.join(name)).unwrap();
+    assert_eq!(read("a.yaml"), "user: admin");
+    assert_eq!(read("b.yaml"), "user: admin\npass: secret123");
+    assert_eq!(read("key"), "ssh-key");
+    assert_eq!(read("plain.txt"), "no secrets");
+}
+
+#[tokio::test]
+async fn test_inject_all_isolates_failed_batch() {
+    // The missing reference fails the batch, but only its own file.
+    let (tmp, out_dir, mut config) = setup("bad.yaml", "Key: {{ test:missing }}");
+    std::fs::write(
+        tmp.path().join("templates/good.yaml"),
+        "Key: {{ test:valid }}",
+    )
+    .unwrap();
+    config.inject_failure_policy = InjectFailurePolicy::Passthrough;
+
+    let provider = Arc::new(MockProvider::new(vec![("test:valid", "value")]));
+    let manager = SecretFileManager::new(config, provider.clone()).unwrap();
+
+    manager.inject_all().await.unwrap();
+
+    // The batch, then a single fetch per file.
+    assert_eq!(provider.calls.load(Ordering::SeqCst), 3);
+    let read = |name: &str| std::fs::read_to_string(out_dir.join(name)).unwrap();
+    assert_eq!(read("bad.yaml"), "Key: {{ test:missing }}");
+    assert_eq!(read("good.yaml"), "Key: value");
+}
+
+#[tokio::test]
+async fn test_inject_all_fails_every_file_when_unavailable() {
+    // An unavailable provider fails the batch, so no file is fetched on its own.
+    let (tmp, out_dir, mut config) = setup("a.yaml", "Key: {{ test:a }}");
+    let tpl_dir = tmp.path().join("templates");
+    std::fs::write(tpl_dir.join("b.yaml"), "Key: {{ test:b }}").unwrap();
+    std::fs::write(tpl_dir.join("plain.txt"), "no secrets").unwrap();
+    config.inject_failure_policy = InjectFailurePolicy::Passthrough;
+
+    let mut provider = MockProvider::new(vec![("test:a", "1"), ("test:b", "2")]);
+    provider.unavailable = true;
+    let provider = Arc::new(provider);
+    let manager = SecretFileManager::new(config, provider.clone()).unwrap();
+
+    manager.inject_all().await.unwrap();
+
+    assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    let read = |name: &str| std::fs::read_to_string(out_dir.join(name)).unwrap();
+    assert_eq!(read("a.yaml"), "Key: {{ test:a }}");
+    assert_eq!(read("b.yaml"), "Key: {{ test:b }}");
+    assert_eq!(read("plain.txt"), "no secrets");
+}
+
 fn make_mapping(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> PathMapping {
     PathMapping::try_new(
         CanonicalPath::try_new(src).expect("test source must exist"),